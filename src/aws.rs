@@ -0,0 +1,225 @@
+//! Variables stored in AWS Systems Manager Parameter Store and Secrets Manager
+//!
+//! The AWS SDK is async and heavy, so rather than depending on it, the sources
+//! call a client supplied by the user, which is usually a thin wrapper around
+//! the SDK client the service already has. Closures work as clients too.
+
+use crate::{json, Error, Result, Source};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The part of the Parameter Store API needed by [`SsmSource`]
+pub trait SsmClient {
+    /// Fetch the `(name, value)` pairs of every parameter under `path`,
+    /// recursively and with secure strings decrypted, following `NextToken`
+    /// until all of them are fetched, the way `GetParametersByPath` does
+    ///
+    /// # Errors
+    ///
+    /// If the parameters can't be fetched
+    fn get_parameters_by_path(&self, path: &str) -> Result<Vec<(String, String)>>;
+}
+
+impl<F> SsmClient for F
+where
+    F: Fn(&str) -> Result<Vec<(String, String)>>,
+{
+    fn get_parameters_by_path(&self, path: &str) -> Result<Vec<(String, String)>> {
+        self(path)
+    }
+}
+
+/// The part of the Secrets Manager API needed by [`SecretsManagerSource`]
+pub trait SecretsManagerClient {
+    /// Fetch the `SecretString` of the secret identified by `secret_id`,
+    /// the way `GetSecretValue` does
+    ///
+    /// # Errors
+    ///
+    /// If the secret can't be fetched
+    fn get_secret_value(&self, secret_id: &str) -> Result<String>;
+}
+
+impl<F> SecretsManagerClient for F
+where
+    F: Fn(&str) -> Result<String>,
+{
+    fn get_secret_value(&self, secret_id: &str) -> Result<String> {
+        self(secret_id)
+    }
+}
+
+/// Every parameter under a path of the Parameter Store, such as `/myapp/prod/`,
+/// as a [`Source`]. The path is stripped from the names of the parameters
+/// and the remaining slashes, dashes and dots become underscores,
+/// so `/myapp/prod/db/host` is read as `DB_HOST`.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_source, SsmSource};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     db_host: String,
+///     db_port: u16,
+/// }
+///
+/// // Stands in for a wrapper around `aws_sdk_ssm::Client`
+/// let client = |path: &str| {
+///     Ok(vec![
+///         (format!("{}db/host", path), "localhost".to_owned()),
+///         (format!("{}db/port", path), "5432".to_owned()),
+///     ])
+/// };
+///
+/// let source = SsmSource::new(client, "/myapp/prod/");
+///
+/// assert_eq!(
+///     from_source::<CustomStruct, _>(&source).unwrap(),
+///     CustomStruct {
+///         db_host: "localhost".to_owned(),
+///         db_port: 5432
+///     }
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct SsmSource<C> {
+    client: C,
+    path: String,
+}
+
+impl<C> SsmSource<C> {
+    /// Read the parameters under `path` with `client`
+    pub fn new(client: C, path: &str) -> Self {
+        Self {
+            client,
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl<C> Source for SsmSource<C>
+where
+    C: SsmClient,
+{
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        let path = self.path.trim_end_matches('/');
+
+        Ok(self
+            .client
+            .get_parameters_by_path(&self.path)?
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(path)?.strip_prefix('/')?;
+
+                Some((key.replace(['/', '-', '.'], "_").to_uppercase(), value))
+            })
+            .collect())
+    }
+}
+
+/// A Secrets Manager secret holding a JSON object, such as
+/// `{"username":"admin","password":"hunter2"}`, as a [`Source`]
+/// whose variables are the fields of that object.
+///
+/// String fields are taken as they are, anything else is written as JSON.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_source, SecretsManagerSource};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     username: String,
+///     port: u16,
+/// }
+///
+/// // Stands in for a wrapper around `aws_sdk_secretsmanager::Client`
+/// let client = |_: &str| Ok(r#"{"username":"admin","port":5432}"#.to_owned());
+///
+/// let source = SecretsManagerSource::new(client, "prod/myapp/db");
+///
+/// assert_eq!(
+///     from_source::<CustomStruct, _>(&source).unwrap(),
+///     CustomStruct {
+///         username: "admin".to_owned(),
+///         port: 5432
+///     }
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecretsManagerSource<C> {
+    client: C,
+    secret_id: String,
+}
+
+impl<C> SecretsManagerSource<C> {
+    /// Read the secret identified by `secret_id`,
+    /// its name or ARN, with `client`
+    pub fn new(client: C, secret_id: &str) -> Self {
+        Self {
+            client,
+            secret_id: secret_id.to_owned(),
+        }
+    }
+}
+
+impl<C> Source for SecretsManagerSource<C>
+where
+    C: SecretsManagerClient,
+{
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        let secret = self.client.get_secret_value(&self.secret_id)?;
+
+        serde_json::from_str(&secret)
+            .ok()
+            .and_then(json::object_vars)
+            .ok_or_else(|| {
+                Error::Custom(format!(
+                    "secret {} doesn't hold a JSON object",
+                    self.secret_id
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SecretsManagerSource, SsmSource};
+    use crate::{Error, Source};
+
+    #[test]
+    fn test_ssm_keys() {
+        let client = |_: &str| {
+            Ok(vec![
+                (String::from("/app/log-level"), String::from("debug")),
+                (String::from("/app/cache/ttl.secs"), String::from("30")),
+                (String::from("/other/key"), String::from("ignored")),
+            ])
+        };
+
+        assert_eq!(
+            SsmSource::new(client, "/app").load().unwrap(),
+            vec![
+                (String::from("LOG_LEVEL"), String::from("debug")),
+                (String::from("CACHE_TTL_SECS"), String::from("30")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_secret_not_an_object() {
+        let client = |_: &str| Ok(String::from("hunter2"));
+
+        assert_eq!(
+            SecretsManagerSource::new(client, "db").load(),
+            Err(Error::Custom(String::from(
+                "secret db doesn't hold a JSON object"
+            )))
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Checks the environment, or an env file, against a schema written
+//! by [`renvar::Template::env_example`], for CI and entrypoint scripts
+//!
+//! ```text
+//! renvar-cli check .env.example
+//! renvar-cli explain .env.example --env-file .env --prefix APP_
+//! ```
+//!
+//! Exits with `0` when every variable is fine, `1` when some are missing,
+//! invalid or unknown, and `2` when the arguments or files can't be read
+
+use renvar::{from_iter, RawEnv};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, env, fmt, fs, process::ExitCode};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+const USAGE: &str = "\
+usage: renvar-cli <check|explain> <SCHEMA> [--env-file <PATH>] [--prefix <PREFIX>]
+
+  check       report the variables that are missing, invalid or unknown
+  explain     report the state of every variable of the schema
+
+  SCHEMA      an .env.example file, as written by renvar::Template::env_example
+  --env-file  read the variables from an env file instead of the environment
+  --prefix    report unknown variables starting with the prefix,
+              always done for env files";
+
+/// A variable of the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spec {
+    key: String,
+    hint: String,
+    optional: bool,
+    default: Option<String>,
+}
+
+/// What was found for a variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Set,
+    Default(String),
+    Unset,
+    Missing,
+    Invalid(String),
+    Unknown,
+}
+
+impl Status {
+    fn is_problem(&self) -> bool {
+        matches!(self, Status::Missing | Status::Invalid(_) | Status::Unknown)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Set => write!(fmt, "set"),
+            Status::Default(default) => write!(fmt, "unset, defaults to `{}`", default),
+            Status::Unset => write!(fmt, "unset, optional"),
+            Status::Missing => write!(fmt, "missing"),
+            Status::Invalid(reason) => write!(fmt, "invalid, {}", reason),
+            Status::Unknown => write!(fmt, "unknown, not in the schema"),
+        }
+    }
+}
+
+/// Read the variables out of an `.env.example` file. Every variable is preceded
+/// by a comment with its type, ending with `, optional` for optional ones,
+/// which are commented out themselves. Hand-written files without types work too.
+fn parse_schema(input: &str) -> Vec<Spec> {
+    let mut specs = Vec::new();
+    let mut last_comment: Option<&str> = None;
+
+    for line in input.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+
+            match last_comment.and_then(|hint| hint.strip_suffix(", optional")) {
+                Some(hint) if is_assignment(comment) => {
+                    specs.push(spec(comment, hint, true));
+                    last_comment = None;
+                }
+                _ => last_comment = Some(comment),
+            }
+        } else if is_assignment(line) {
+            specs.push(spec(line, last_comment.unwrap_or("any"), false));
+            last_comment = None;
+        } else {
+            last_comment = None;
+        }
+    }
+
+    specs
+}
+
+/// Whether `line` looks like `KEY=value`
+fn is_assignment(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(key, _)| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn spec(line: &str, hint: &str, optional: bool) -> Spec {
+    let (key, default) = line.split_once('=').unwrap_or((line, ""));
+    let default = default
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'');
+
+    Spec {
+        key: key.to_owned(),
+        hint: hint.to_owned(),
+        optional,
+        default: (!default.is_empty()).then(|| default.to_owned()),
+    }
+}
+
+/// Deserialize `value` like a field of type `T` would be
+fn parses_as<T>(key: &str, value: &str) -> Result<(), String>
+where
+    T: DeserializeOwned,
+{
+    from_iter::<HashMap<String, T>, _>([(key.to_owned(), value.to_owned())])
+        .map(drop)
+        .map_err(|error| error.to_string())
+}
+
+/// Check `value` against the type written in the schema. Types that
+/// deserialize themselves, such as enums, are accepted as they are
+fn validate(spec: &Spec, value: &str) -> Result<(), String> {
+    let key = spec.key.as_str();
+
+    match spec.hint.as_str() {
+        "bool" => parses_as::<bool>(key, value),
+        "u8" => parses_as::<u8>(key, value),
+        "u16" => parses_as::<u16>(key, value),
+        "u32" => parses_as::<u32>(key, value),
+        "u64" => parses_as::<u64>(key, value),
+        "u128" => parses_as::<u128>(key, value),
+        "i8" => parses_as::<i8>(key, value),
+        "i16" => parses_as::<i16>(key, value),
+        "i32" => parses_as::<i32>(key, value),
+        "i64" => parses_as::<i64>(key, value),
+        "i128" => parses_as::<i128>(key, value),
+        "f32" => parses_as::<f32>(key, value),
+        "f64" => parses_as::<f64>(key, value),
+        "char" => parses_as::<char>(key, value),
+        _ => Ok(()),
+    }
+}
+
+/// The status of every variable of the schema, followed by the unknown variables:
+/// those of an env file not in the schema, or those starting with `prefix`
+fn statuses(
+    specs: &[Spec],
+    vars: &RawEnv,
+    prefix: Option<&str>,
+    from_file: bool,
+) -> Vec<(String, Status)> {
+    let mut statuses: Vec<(String, Status)> = specs
+        .iter()
+        .map(|spec| {
+            let status = match (vars.get(&spec.key), &spec.default) {
+                (Some(value), _) => match validate(spec, value) {
+                    Ok(()) => Status::Set,
+                    Err(reason) => Status::Invalid(reason),
+                },
+                (None, Some(default)) => Status::Default(default.clone()),
+                (None, None) if spec.optional => Status::Unset,
+                (None, None) => Status::Missing,
+            };
+
+            (spec.key.clone(), status)
+        })
+        .collect();
+
+    let mut unknown: Vec<&str> = vars
+        .keys()
+        .filter(|key| from_file || prefix.is_some_and(|prefix| key.starts_with(prefix)))
+        .filter(|key| !specs.iter().any(|spec| spec.key == *key))
+        .collect();
+    unknown.sort_unstable();
+    unknown.dedup();
+
+    statuses.extend(
+        unknown
+            .into_iter()
+            .map(|key| (key.to_owned(), Status::Unknown)),
+    );
+    statuses
+}
+
+struct Args {
+    explain: bool,
+    schema: String,
+    env_file: Option<String>,
+    prefix: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let explain = match args.next().as_deref() {
+        Some("check") => false,
+        Some("explain") => true,
+        Some(command) => return Err(format!("unknown command `{}`", command)),
+        None => return Err(String::from("missing command")),
+    };
+
+    let mut schema = None;
+    let mut env_file = None;
+    let mut prefix = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+
+        match arg.as_str() {
+            "--env-file" => env_file = Some(value("--env-file")?),
+            "--prefix" => prefix = Some(value("--prefix")?),
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option `{}`", arg))
+            }
+            _ if schema.is_none() => schema = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    Ok(Args {
+        explain,
+        schema: schema.ok_or("missing schema")?,
+        env_file,
+        prefix,
+    })
+}
+
+fn run(args: Args) -> Result<bool, String> {
+    let schema = fs::read_to_string(&args.schema)
+        .map_err(|error| format!("can't read {}: {}", args.schema, error))?;
+    let specs = parse_schema(&schema);
+
+    let vars = match &args.env_file {
+        Some(path) => RawEnv::from_file(path).map_err(|error| error.to_string())?,
+        None => RawEnv::from_env(),
+    };
+
+    let statuses = statuses(
+        &specs,
+        &vars,
+        args.prefix.as_deref(),
+        args.env_file.is_some(),
+    );
+
+    for (key, status) in &statuses {
+        if args.explain || status.is_problem() {
+            println!("{}: {}", key, status);
+        }
+    }
+
+    Ok(statuses
+        .iter()
+        .all(|(_, status)| !status.is_problem()))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args
+        .iter()
+        .any(|arg| arg == "-h" || arg == "--help")
+    {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let args = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_schema, statuses, Spec, Status};
+    use renvar::{template, RawEnv};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct App {
+        port: u16,
+        debug: bool,
+        log_level: Option<String>,
+    }
+
+    #[test]
+    fn test_check_against_template() {
+        let example = template::<App>().prefix("APP_").env_example();
+        let specs = parse_schema(&example);
+
+        assert_eq!(
+            specs[2],
+            Spec {
+                key: String::from("APP_LOG_LEVEL"),
+                hint: String::from("string"),
+                optional: true,
+                default: None,
+            }
+        );
+
+        let vars = RawEnv::parse("APP_PORT=http\nAPP_VERBOSE=1\nPATH=/usr/bin\n");
+
+        assert_eq!(
+            statuses(&specs, &vars, Some("APP_"), false)
+                .into_iter()
+                .map(|(key, status)| format!("{}: {}", key, status))
+                .collect::<Vec<_>>(),
+            vec![
+                "APP_PORT: invalid, invalid digit found in string while parsing value 'http'",
+                "APP_DEBUG: missing",
+                "APP_LOG_LEVEL: unset, optional",
+                "APP_VERBOSE: unknown, not in the schema",
+            ]
+        );
+
+        let vars = RawEnv::parse("APP_PORT=8080\nAPP_DEBUG=true\n");
+
+        assert!(statuses(&specs, &vars, None, true)
+            .iter()
+            .all(|(_, status)| *status == Status::Set || *status == Status::Unset));
+    }
+}
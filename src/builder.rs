@@ -0,0 +1,3526 @@
+use crate::convert::{
+    is_blank_or_comment, maybe_invalid_unicode_vars_os, split_line, split_lines,
+    unescape_quoted,
+};
+use crate::de::{field_key, names_field};
+use crate::describe::describe;
+use crate::explain::{Explanation, FieldExplanation};
+use crate::interpolate::interpolate;
+use crate::options::{
+    DuplicateKeyPolicy, EmptyStringPolicy, ErrorHook, Hook, Options, ValueHook,
+};
+#[cfg(target_os = "linux")]
+use crate::pid;
+use crate::profiles::Profiles;
+use crate::resolve::{resolve, ResolverHook, ValueResolver};
+use crate::rules::{self, Rule};
+use crate::sanitize::is_quote_or_whitespace;
+#[cfg(feature = "shellwords")]
+use crate::shellwords;
+#[cfg(feature = "async_source")]
+use crate::source::AsyncSource;
+use crate::source::Source;
+use crate::value::{to_value, Value};
+use crate::warning::{Warning, Warnings};
+#[cfg(feature = "watch")]
+use crate::watch::{watch, Watched};
+use crate::{
+    de::EnvVarDeserializer, file, format, validate, Decode, Deprecation, EnvPrecedence,
+    Error, Format, KeyCase, RawEnv, Result, TrimPolicy, Validate,
+};
+use serde::{de, Serialize};
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    env, fmt,
+    io::BufRead,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Configurable entry point for deserializing some type `T` from environment variables.
+/// Users are meant to obtain this struct by calling [`builder`].
+///
+/// Without any settings, the `from_*` methods behave exactly
+/// like their free function counterparts, such as [`crate::from_env`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, Builder};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     key: String,
+/// }
+///
+/// let vars = vec![("KEY".to_owned(), "value".to_owned())];
+///
+/// let builder: Builder = builder();
+/// let custom_struct: CustomStruct = builder.from_iter(vars).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         key: "value".to_owned()
+///     }
+/// )
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    options: Options,
+    prefixes: Vec<String>,
+    postfix: Option<String>,
+    keep_affixes: bool,
+    max_age: Option<Duration>,
+    on_stale: Option<ErrorHook>,
+    defaults: Option<PathBuf>,
+    require_known_defaults: bool,
+    interpolate: bool,
+    map_values: Option<ValueHook>,
+    resolvers: Vec<ResolverHook>,
+    trim_keys: TrimPolicy,
+    trim_values: TrimPolicy,
+    profiles: Option<Profiles>,
+    rules: Vec<Rule>,
+    file_indirection: bool,
+    aliases: Vec<(String, Vec<String>)>,
+    deprecations: Vec<(String, Deprecation)>,
+    secrets: Vec<String>,
+    #[cfg(feature = "shellwords")]
+    shell_quoting: bool,
+}
+
+impl Builder {
+    /// Only consider the variables whose key starts with `prefix`,
+    /// which is stripped from the keys before deserialization
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     name: String,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_NAME".to_owned(), "value".to_owned()),
+    ///     ("NAME".to_owned(), "other value".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder().prefix("APP_").from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         name: "value".to_owned()
+    ///     }
+    /// )
+    /// ```
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        match self.prefixes.first_mut() {
+            Some(first) => *first = prefix.into(),
+            None => self.prefixes.push(prefix.into()),
+        }
+        self
+    }
+
+    /// Also consider the variables whose key starts with `prefix`, for the keys
+    /// no variable with the prefix set with [`Builder::prefix`], or with an earlier
+    /// fallback prefix, sets. Meant for renamed prefixes, so `APP_PORT` keeps
+    /// working after moving to `MYAPP_PORT`, with `MYAPP_PORT` taking precedence.
+    ///
+    /// Errors name the variables with the prefix they were read with,
+    /// or the first prefix for variables that weren't read at all.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_HOST".to_owned(), "localhost".to_owned()),
+    ///     ("APP_PORT".to_owned(), "8080".to_owned()),
+    ///     ("MYAPP_PORT".to_owned(), "9090".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("MYAPP_")
+    ///     .fallback_prefix("APP_")
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host: "localhost".to_owned(),
+    ///         port: 9090,
+    ///     }
+    /// )
+    /// ```
+    pub fn fallback_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Only consider the variables whose key ends with `postfix`,
+    /// which is stripped from the keys before deserialization.
+    /// Can be combined with [`Builder::prefix`] to strip both ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     name: String,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_NAME_PROD".to_owned(), "production".to_owned()),
+    ///     ("APP_NAME_DEV".to_owned(), "development".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .postfix("_PROD")
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         name: "production".to_owned()
+    ///     }
+    /// )
+    /// ```
+    pub fn postfix(mut self, postfix: impl Into<String>) -> Self {
+        self.postfix = Some(postfix.into());
+        self
+    }
+
+    /// Whether to strip the prefix and postfix from the keys, so they match the
+    /// fields of the target struct. When disabled, the variables are still filtered
+    /// on them, but the keys are matched with the fields as they are.
+    ///
+    /// Only the first occurrence of the prefix and of the postfix is ever stripped,
+    /// so `APP_APP_NAME` with the prefix `APP_` is matched as `APP_NAME`.
+    ///
+    /// Enabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     app_name: String,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_NAME".to_owned(), "renvar".to_owned()),
+    ///     ("NAME".to_owned(), "other".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().prefix("APP_").strip(false).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         app_name: "renvar".to_owned()
+    ///     }
+    /// )
+    /// ```
+    pub fn strip(mut self, strip: bool) -> Self {
+        self.keep_affixes = !strip;
+        self
+    }
+
+    /// Name a field of the target struct that receives every variable
+    /// not consumed by any of the other fields.
+    ///
+    /// The field is expected to be a map, such as `HashMap<String, String>`.
+    /// Contrary to the other fields, the keys of the captured variables
+    /// keep their original casing, so they can be forwarded as-is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    /// use std::collections::HashMap;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct Proxy {
+    ///     upstream: String,
+    ///     extra: HashMap<String, String>,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("UPSTREAM".to_owned(), "localhost:8080".to_owned()),
+    ///     ("TIMEOUT".to_owned(), "30".to_owned()),
+    /// ];
+    ///
+    /// let proxy: Proxy = builder().capture_rest("extra").from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     proxy,
+    ///     Proxy {
+    ///         upstream: "localhost:8080".to_owned(),
+    ///         extra: HashMap::from([("TIMEOUT".to_owned(), "30".to_owned())])
+    ///     }
+    /// )
+    /// ```
+    pub fn capture_rest(mut self, field: &str) -> Self {
+        self.options.capture_rest = Some(field.to_lowercase());
+        self
+    }
+
+    /// Read the nested struct at `path` from the variables prefixed with `prefix`,
+    /// rather than with the name of its field. The path names the field from the
+    /// target struct, with dots between nested fields, such as `db.primary`, and
+    /// the prefix is relative to the variables of the struct it belongs to.
+    ///
+    /// Struct fields are read from the variables prefixed with their name by default,
+    /// such as `DB_HOST` for the `host` field of a `db` field, when no variable is named
+    /// after them. Fields given a prefix always are, ignoring the variable named after them.
+    /// Fields holding tagged or untagged enums, which may just as well hold a single
+    /// value, are only read from prefixed variables when they're given a prefix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct Redis {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct Queue {
+    ///     redis: Redis,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct App {
+    ///     redis: Redis,
+    ///     queue: Queue,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_CACHE_HOST".to_owned(), "cache.internal".to_owned()),
+    ///     ("APP_CACHE_PORT".to_owned(), "6379".to_owned()),
+    ///     ("APP_QUEUE_BROKER_HOST".to_owned(), "broker.internal".to_owned()),
+    ///     ("APP_QUEUE_BROKER_PORT".to_owned(), "6380".to_owned()),
+    /// ];
+    ///
+    /// let app: App = builder()
+    ///     .prefix("APP_")
+    ///     .scope("redis", "CACHE_")
+    ///     .scope("queue.redis", "BROKER_")
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(app.redis.host, "cache.internal");
+    /// assert_eq!(app.queue.redis.port, 6380);
+    /// ```
+    pub fn scope(mut self, path: &str, prefix: &str) -> Self {
+        self.options
+            .scopes
+            .push((path.to_owned(), prefix.to_owned()));
+        self
+    }
+
+    /// Prefer the variables of the given [`Profiles`], such as `PORT_PROD`,
+    /// over the plain ones, such as `PORT`. Profiles are resolved after the
+    /// prefix and the postfix are stripped, so `APP_PORT_PROD` overrides `APP_PORT`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, profiles};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_HOST".to_owned(), "localhost".to_owned()),
+    ///     ("APP_HOST_STAGING".to_owned(), "staging.internal".to_owned()),
+    ///     ("APP_PORT".to_owned(), "8080".to_owned()),
+    ///     ("APP_PORT_STAGING".to_owned(), "8081".to_owned()),
+    ///     ("APP_PORT_PROD".to_owned(), "80".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .profiles(profiles(["prod", "staging"]))
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host: "staging.internal".to_owned(),
+    ///         port: 80,
+    ///     }
+    /// )
+    /// ```
+    pub fn profiles(mut self, profiles: Profiles) -> Self {
+        self.profiles = Some(profiles);
+        self
+    }
+
+    /// Fail with [`crate::Error::ControlCharacters`] if the value of any of
+    /// the variables contains NUL or other control characters, which are
+    /// often the sign of a corrupted injection. Tabs and newlines are allowed.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     token: String,
+    /// }
+    ///
+    /// let vars = vec![("TOKEN".to_owned(), "abc\0def".to_owned())];
+    ///
+    /// let error = builder()
+    ///     .reject_control_chars(true)
+    ///     .from_iter::<CustomStruct, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error,
+    ///     Error::ControlCharacters {
+    ///         key: "TOKEN".to_owned(),
+    ///         offsets: vec![3]
+    ///     }
+    /// )
+    /// ```
+    pub fn reject_control_chars(mut self, reject: bool) -> Self {
+        self.options.reject_control_chars = reject;
+        self
+    }
+
+    /// Accept the common truthy and falsy strings for `bool` fields,
+    /// ignoring case:
+    ///
+    /// * `1`, `yes`, `on`, `true`
+    /// * `0`, `no`, `off`, `false`
+    ///
+    /// By default, only `true` and `false` are accepted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     debug: bool,
+    ///     verbose: bool,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("DEBUG".to_owned(), "1".to_owned()),
+    ///     ("VERBOSE".to_owned(), "Off".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().lenient_bools(true).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         debug: true,
+    ///         verbose: false
+    ///     }
+    /// )
+    /// ```
+    pub fn lenient_bools(mut self, lenient: bool) -> Self {
+        self.options.lenient_bools = lenient;
+        self
+    }
+
+    /// Match the values of enum fields with their variants ignoring case,
+    /// dashes and underscores, so `debug`, `DEBUG` and `Debug` all match
+    /// `Debug`, and `log-level` or `LOG_LEVEL` match `LogLevel`.
+    /// Applies to the variants as they're named after `#[serde(rename_all)]`.
+    ///
+    /// By default, values must match the variants exactly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// enum Level {
+    ///     Debug,
+    ///     NoticeOnly,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     level: Level,
+    ///     levels: Vec<Level>,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("LEVEL".to_owned(), "debug".to_owned()),
+    ///     ("LEVELS".to_owned(), "DEBUG,notice-only".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().lenient_variants(true).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         level: Level::Debug,
+    ///         levels: vec![Level::Debug, Level::NoticeOnly]
+    ///     }
+    /// )
+    /// ```
+    pub fn lenient_variants(mut self, lenient: bool) -> Self {
+        self.options.lenient_variants = lenient;
+        self
+    }
+
+    /// Accept integers written the way people write sizes and limits:
+    ///
+    /// * with underscores between digits, such as `1_000_000`
+    /// * in hexadecimal, octal or binary, such as `0x1F`, `0o755` or `0b1010`
+    /// * followed by a decimal unit, `k`, `M`, `G`, `T`, `P` or `E`,
+    ///   such as `8k` for 8000
+    /// * followed by a binary unit, `Ki`, `Mi`, `Gi`, `Ti`, `Pi` or `Ei`,
+    ///   such as `16Mi` for 16 × 1024 × 1024
+    ///
+    /// Floats accept underscores as well. Values too large for the type of
+    /// their field, once multiplied by their unit, are rejected.
+    ///
+    /// By default, only what [`str::parse`] accepts is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     max_rows: u32,
+    ///     cache_size: u64,
+    ///     umask: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("MAX_ROWS".to_owned(), "1_000_000".to_owned()),
+    ///     ("CACHE_SIZE".to_owned(), "256Mi".to_owned()),
+    ///     ("UMASK".to_owned(), "0o022".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().lenient_numbers(true).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         max_rows: 1_000_000,
+    ///         cache_size: 256 * 1024 * 1024,
+    ///         umask: 0o022,
+    ///     }
+    /// )
+    /// ```
+    pub fn lenient_numbers(mut self, lenient: bool) -> Self {
+        self.options.lenient_numbers = lenient;
+        self
+    }
+
+    /// Require the contents of env files read by [`Builder::from_file`]
+    /// to be at most `max_age` old, failing with [`crate::Error::Stale`] otherwise.
+    ///
+    /// The age is determined by a `# generated-at:` comment header,
+    /// holding either an RFC 3339 timestamp or seconds since the unix epoch,
+    /// and falls back to the modification time of the file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = builder().max_age(Duration::from_secs(7 * 24 * 60 * 60));
+    /// ```
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Instead of failing, hand the [`crate::Error::Stale`] error raised
+    /// due to [`Builder::max_age`] to `callback` and carry on
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use std::time::Duration;
+    ///
+    /// let builder = builder()
+    ///     .max_age(Duration::from_secs(7 * 24 * 60 * 60))
+    ///     .on_stale(|error| eprintln!("warning: {}", error));
+    /// ```
+    pub fn on_stale<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Error) + Send + Sync + 'static,
+    {
+        self.on_stale = Some(Hook(Arc::new(callback)));
+        self
+    }
+
+    /// Set the delimiter between the elements of tuples and tuple structs,
+    /// such as `:` for `HOST_PORT=localhost:8080` into a `(String, u16)`.
+    /// The last element keeps any further delimiters.
+    ///
+    /// Defaults to `,`, the same as sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct Version(u8, u8, u8);
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host_port: (String, u16),
+    ///     version: Version,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("HOST_PORT".to_owned(), "localhost:8080".to_owned()),
+    ///     ("VERSION".to_owned(), "1:2:3".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().tuple_delimiter(':').from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host_port: ("localhost".to_owned(), 8080),
+    ///         version: Version(1, 2, 3)
+    ///     }
+    /// );
+    /// ```
+    pub fn tuple_delimiter(mut self, delimiter: char) -> Self {
+        self.options.tuple_delimiter = delimiter;
+        self
+    }
+
+    /// Deserialize variables set to an empty string as `None` for options of
+    /// sequences, such as `Option<Vec<T>>`, instead of as an empty sequence.
+    /// Variables that aren't set are always `None`.
+    ///
+    /// Elements of sequences are treated like any other value, so an empty
+    /// element of a `Vec<Option<T>>` is `None`, and so are empty values of
+    /// `Option<T>` fields, while they're `Some(None)` for `Option<Option<T>>` fields.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     hosts: Option<Vec<String>>,
+    ///     ports: Option<Vec<u16>>,
+    /// }
+    ///
+    /// let vars = vec![("HOSTS".to_owned(), "".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct = builder().from_iter(vars.clone()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         hosts: Some(vec![]),
+    ///         ports: None
+    ///     }
+    /// );
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().empty_seqs_as_none(true).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         hosts: None,
+    ///         ports: None
+    ///     }
+    /// );
+    /// ```
+    pub fn empty_seqs_as_none(mut self, empty_as_none: bool) -> Self {
+        self.options.empty_seqs_as_none = empty_as_none;
+        self
+    }
+
+    /// Read default values from the env file at `path`, usually `.env.defaults`,
+    /// before every other source. Any variable of the other sources overrides
+    /// the default with the same name, so the real environment always wins.
+    ///
+    /// The defaults file is meant to be committed along with the code,
+    /// so it should never contain secrets.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     port: u16,
+    ///     log_level: String,
+    /// }
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .defaults(".env.defaults")
+    ///     .from_env()
+    ///     .unwrap();
+    /// ```
+    pub fn defaults(mut self, path: impl Into<PathBuf>) -> Self {
+        self.defaults = Some(path.into());
+        self
+    }
+
+    /// Fail with [`crate::Error::UnknownKeys`] if any of the variables of the
+    /// file set with [`Builder::defaults`] don't match a field of the target
+    /// struct, so the defaults can't document settings that don't exist
+    ///
+    /// Disabled by default.
+    pub fn require_known_defaults(mut self, require: bool) -> Self {
+        self.require_known_defaults = require;
+        self
+    }
+
+    /// Hand the non-fatal issues found while deserializing, such as
+    /// variables shadowed by others with the same key, to `callback`
+    ///
+    /// Replaces the collection set with [`Builder::warnings`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    ///
+    /// let builder = builder().on_warning(|warning| eprintln!("warning: {}", warning));
+    /// ```
+    pub fn on_warning<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Warning) + Send + Sync + 'static,
+    {
+        self.options.on_warning = Some(Hook(Arc::new(callback)));
+        self
+    }
+
+    /// Require every field of `required` to be set when `field` is set, such as a
+    /// key along with a certificate. Fields of nested structs read from prefixed
+    /// variables are written with their path, such as `tls.cert` for `TLS_CERT`.
+    ///
+    /// A field counts as set if a variable holds a value for it, even a default
+    /// from [`Builder::defaults`]. Checked once deserialization succeeds, reporting
+    /// every broken rule at once in [`crate::Error::Validation`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error, ValidationError};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Tls {
+    ///     cert: Option<String>,
+    ///     key: Option<String>,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Server {
+    ///     tls: Tls,
+    /// }
+    ///
+    /// let vars = vec![("TLS_CERT".to_owned(), "/etc/tls/cert.pem".to_owned())];
+    ///
+    /// let error = builder()
+    ///     .requires("tls.cert", &["tls.key"])
+    ///     .from_iter::<Server, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error,
+    ///     Error::Validation(vec![ValidationError::new(
+    ///         "tls.cert",
+    ///         "requires `tls.key` to be set as well"
+    ///     )])
+    /// )
+    /// ```
+    pub fn requires(mut self, field: &str, required: &[&str]) -> Self {
+        self.rules.push(Rule::Requires {
+            field: field.to_owned(),
+            required: required
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+        });
+        self
+    }
+
+    /// Allow at most one of `fields` to be set, such as a password and the path
+    /// of a file holding it. Same as [`Builder::requires`] otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error, ValidationError};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Database {
+    ///     password: Option<String>,
+    ///     password_file: Option<String>,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("DB_PASSWORD".to_owned(), "hunter2".to_owned()),
+    ///     ("DB_PASSWORD_FILE".to_owned(), "/run/secrets/db".to_owned()),
+    /// ];
+    ///
+    /// let error = builder()
+    ///     .prefix("DB_")
+    ///     .conflicts(&["password", "password_file"])
+    ///     .from_iter::<Database, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error,
+    ///     Error::Validation(vec![ValidationError::new(
+    ///         "password",
+    ///         "conflicts with `password_file`, only one of them may be set"
+    ///     )])
+    /// )
+    /// ```
+    pub fn conflicts(mut self, fields: &[&str]) -> Self {
+        self.rules.push(Rule::Conflicts(
+            fields
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+        ));
+        self
+    }
+
+    /// Hand the key and the value of every variable that doesn't match any field
+    /// of the target struct to `callback`, to log them rather than failing like
+    /// [`Builder::deny_unknown`] does. Variables whose key doesn't start with
+    /// the prefix, or end with the postfix, aren't considered at all.
+    ///
+    /// Nothing is reported when [`Builder::deny_unknown`] or [`Builder::capture_rest`]
+    /// is in effect, or for maps and flattened structs, which take every variable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     timeout: u64,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_TIMEOUT".to_owned(), "30".to_owned()),
+    ///     ("APP_TIMEOTU".to_owned(), "60".to_owned()),
+    ///     ("HOME".to_owned(), "/root".to_owned()),
+    /// ];
+    ///
+    /// let unused = Arc::new(Mutex::new(Vec::new()));
+    /// let collected = Arc::clone(&unused);
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .on_unused_key(move |key, value| {
+    ///         collected
+    ///             .lock()
+    ///             .unwrap()
+    ///             .push(format!("{}={}", key, value))
+    ///     })
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(custom_struct.timeout, 30);
+    /// assert_eq!(*unused.lock().unwrap(), vec!["APP_TIMEOTU=60".to_owned()]);
+    /// ```
+    pub fn on_unused_key<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.options.on_unused_key = Some(Hook(Arc::new(callback)));
+        self
+    }
+
+    /// Expand references to other variables inside values, the way docker-compose does:
+    ///
+    /// - `$NAME` and `${NAME}` are replaced with the value of `NAME`, or nothing if it's unset
+    /// - `${NAME:-default}` is replaced with `default` if `NAME` is unset or empty
+    /// - `$$` and `\$` stand for a literal `$`
+    ///
+    /// References are looked up among the variables being deserialized first, which
+    /// includes the ones from [`Builder::defaults`], and in the environment of the running
+    /// process second. The values they're replaced with are expanded as well, which fails
+    /// with [`crate::Error::Interpolation`] if references nest too deep, such as in a cycle.
+    /// Only the variables that are deserialized, those with the prefix and postfix
+    /// or an alias, are expanded, so other variables of the process can't make it fail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     database_url: String,
+    /// }
+    ///
+    /// let input = "HOST=localhost\nDATABASE_URL=postgres://${HOST}:${PORT:-5432}/app\n";
+    ///
+    /// let custom_struct: CustomStruct = builder().interpolate(true).from_str(input).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         database_url: "postgres://localhost:5432/app".to_owned()
+    ///     }
+    /// );
+    /// ```
+    pub fn interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    /// Rewrite the value of every variable with `f`, which is handed the key
+    /// and the value of the variable as they were read, before the prefix or
+    /// postfix is stripped. Runs after quotes and whitespace are trimmed,
+    /// but before the value is parsed into the type of its field.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     token: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("TOKEN".to_owned(), "SECRET::abc".to_owned()),
+    ///     ("PORT".to_owned(), "SECRET::8080".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .map_values(|_, value| value.trim_start_matches("SECRET::").to_owned())
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         token: "abc".to_owned(),
+    ///         port: 8080
+    ///     }
+    /// );
+    /// ```
+    pub fn map_values<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &str) -> String + Send + Sync + 'static,
+    {
+        self.map_values = Some(Hook(Arc::new(f)));
+        self
+    }
+
+    /// Register a [`ValueResolver`], which replaces the values written as
+    /// `scheme:reference` for its scheme with the content they refer to, such as
+    /// [`crate::FileResolver`] for `file:/run/secrets/db_pass`. Values are resolved
+    /// after [`Builder::map_values`] and only for the variables that are deserialized,
+    /// so a prefix keeps unrelated variables from being resolved.
+    ///
+    /// Several resolvers can be registered, the first one for a scheme is used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, FileResolver};
+    ///
+    /// let builder = builder().prefix("APP_").resolver(FileResolver);
+    /// ```
+    pub fn resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: ValueResolver + 'static,
+    {
+        self.resolvers.push(Hook(Arc::new(resolver)));
+        self
+    }
+
+    /// Follow the convention of Docker images, where a variable such as `DB_PASSWORD`
+    /// can instead be given as `DB_PASSWORD_FILE`, the path of a file holding its value.
+    /// A single trailing newline is removed from the contents of the file.
+    ///
+    /// Variables ending in `_FILE` are replaced by the variable they stand in for,
+    /// so they don't count as unknown for [`Builder::deny_unknown`], and are ignored
+    /// if that variable is set and isn't empty. With a prefix, `APP_DB_PASSWORD_FILE` stands in for
+    /// `APP_DB_PASSWORD`. Only variables standing in for a field are replaced, so a field
+    /// such as `log_file` is still read from `LOG_FILE`, and other variables ending in `_FILE`
+    /// are left alone.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    /// use std::{env, fs};
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct Database {
+    ///     user: String,
+    ///     password: String,
+    /// }
+    ///
+    /// let path = env::temp_dir().join("renvar_doc_file_indirection");
+    /// fs::write(&path, "hunter2\n").unwrap();
+    ///
+    /// let vars = vec![
+    ///     ("DB_USER".to_owned(), "admin".to_owned()),
+    ///     ("DB_PASSWORD_FILE".to_owned(), path.display().to_string()),
+    /// ];
+    ///
+    /// let database: Database = builder()
+    ///     .prefix("DB_")
+    ///     .file_indirection(true)
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    /// # fs::remove_file(&path).unwrap();
+    ///
+    /// assert_eq!(database.password, "hunter2");
+    /// ```
+    pub fn file_indirection(mut self, enabled: bool) -> Self {
+        self.file_indirection = enabled;
+        self
+    }
+
+    /// Read the field `field` from the variables `aliases` when the variable
+    /// named after it isn't set, so renamed variables keep working during migrations.
+    /// Aliases are the names of the variables as they're set, prefix included if
+    /// they have one, and the earlier ones take precedence over the later ones.
+    ///
+    /// Reading a field from an alias emits a [`Warning::DeprecatedKey`],
+    /// handed to [`Builder::on_warning`] or [`Builder::warnings`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Warning, Warnings};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     database_url: String,
+    /// }
+    ///
+    /// let warnings = Warnings::new();
+    /// let vars = vec![("DB_URL".to_owned(), "postgres://localhost/app".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .alias("database_url", ["DB_URL", "DATABASE_URI"])
+    ///     .warnings(&warnings)
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(custom_struct.database_url, "postgres://localhost/app");
+    /// assert_eq!(
+    ///     warnings.take(),
+    ///     vec![Warning::DeprecatedKey {
+    ///         key: "DB_URL".to_owned(),
+    ///         replacement: Some("APP_DATABASE_URL".to_owned()),
+    ///         removal: None,
+    ///     }]
+    /// );
+    /// ```
+    pub fn alias<I>(mut self, field: &str, aliases: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.aliases.push((
+            field.to_owned(),
+            aliases.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Mark the field `field` as deprecated, so reading it emits a [`Warning::DeprecatedKey`]
+    /// telling which variable to use instead and when it'll be removed, handed to
+    /// [`Builder::on_warning`] or [`Builder::warnings`], and logged with the `tracing` feature.
+    /// The field is still deserialized as usual.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Deprecation, Warnings};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     timeout: Option<u64>,
+    ///     request_timeout: Option<u64>,
+    /// }
+    ///
+    /// let warnings = Warnings::new();
+    /// let vars = vec![("APP_TIMEOUT".to_owned(), "30".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .deprecate(
+    ///         "timeout",
+    ///         Deprecation::new().replacement("request_timeout").removal("2.0"),
+    ///     )
+    ///     .warnings(&warnings)
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(custom_struct.timeout, Some(30));
+    /// assert_eq!(
+    ///     warnings.take()[0].to_string(),
+    ///     "environment variable APP_TIMEOUT is deprecated, \
+    ///      use APP_REQUEST_TIMEOUT instead, it will be removed in 2.0"
+    /// );
+    /// ```
+    pub fn deprecate(mut self, field: &str, deprecation: Deprecation) -> Self {
+        self.deprecations
+            .push((field.to_owned(), deprecation));
+        self
+    }
+
+    /// Parse the values of the field or variable called `name` with `parser`,
+    /// instead of the usual parsing, for one-off formats that would otherwise
+    /// need a `deserialize_with` function. The value returned by the parser is
+    /// handed to the field through serde, so it only needs to serialize into
+    /// something the type of the field deserializes from, which any type does
+    /// for itself.
+    ///
+    /// `name` is either the name of a field or of a variable, with or without
+    /// the prefix and postfix. The parser receives the trimmed value of the variable
+    /// and its errors are reported along with the value. A parser registered later
+    /// for the same name replaces the earlier one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     retry_backoff: Duration,
+    ///     retries: u8,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_RETRY_BACKOFF".to_owned(), "250ms".to_owned()),
+    ///     ("APP_RETRIES".to_owned(), "3".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .parse_with("retry_backoff", |value| {
+    ///         value
+    ///             .strip_suffix("ms")
+    ///             .ok_or("expected milliseconds")?
+    ///             .parse()
+    ///             .map(Duration::from_millis)
+    ///             .map_err(|_| "invalid number of milliseconds")
+    ///     })
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         retry_backoff: Duration::from_millis(250),
+    ///         retries: 3
+    ///     }
+    /// );
+    /// ```
+    pub fn parse_with<F, V, E>(mut self, name: &str, parser: F) -> Self
+    where
+        F: Fn(&str) -> std::result::Result<V, E> + Send + Sync + 'static,
+        V: Serialize,
+        E: fmt::Display,
+    {
+        let parse = move |value: &str| match parser(value) {
+            Ok(parsed) => to_value(&parsed),
+            Err(error) => Err(Error::Custom(format!(
+                "{} while parsing value '{}'",
+                error, value
+            ))),
+        };
+
+        self.options
+            .parsers
+            .push((name.to_owned(), Hook(Arc::new(parse))));
+        self
+    }
+
+    /// Decode the values of the field or variable called `name` before they're
+    /// deserialized, for binary or multi-line data, such as keys and certificates,
+    /// given as base64 where only single-line values are allowed. See [`Decode`].
+    ///
+    /// The decoded bytes are deserialized into `String` fields if they're valid
+    /// UTF-8, and into `Vec<u8>` fields as they are. `name` is matched like
+    /// for [`Builder::parse_with`], which [`Builder::decode`] replaces for the same
+    /// name, and the other way around.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Decode};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     jwt_key: String,
+    ///     salt: Vec<u8>,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_JWT_KEY".to_owned(), "LS0tLS1CRUdJTgpzZWNyZXQK".to_owned()),
+    ///     ("APP_SALT".to_owned(), "3q2+7w==".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("APP_")
+    ///     .decode("JWT_KEY", Decode::Base64)
+    ///     .decode("salt", Decode::Base64)
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         jwt_key: "-----BEGIN\nsecret\n".to_owned(),
+    ///         salt: vec![0xde, 0xad, 0xbe, 0xef],
+    ///     }
+    /// );
+    /// ```
+    pub fn decode(mut self, name: &str, decode: Decode) -> Self {
+        let parse = move |value: &str| match decode.decode(value) {
+            Ok(bytes) => Ok(Value::Bytes(bytes)),
+            Err(reason) => Err(Error::Custom(format!(
+                "{} while decoding value '{}'",
+                reason, value
+            ))),
+        };
+
+        self.options
+            .parsers
+            .push((name.to_owned(), Hook(Arc::new(parse))));
+        self
+    }
+
+    /// Set how the keys of variables are trimmed, see [`TrimPolicy`]
+    ///
+    /// Defaults to [`TrimPolicy::QuotesAndWhitespace`].
+    pub fn trim_keys(mut self, policy: TrimPolicy) -> Self {
+        self.trim_keys = policy;
+        self
+    }
+
+    /// Set how the values of variables are trimmed, see [`TrimPolicy`],
+    /// such as [`TrimPolicy::None`] for values whose surrounding
+    /// whitespace is significant, or [`TrimPolicy::Unquote`] for values
+    /// quoted to keep it.
+    ///
+    /// Defaults to [`TrimPolicy::QuotesAndWhitespace`], which strips
+    /// every quote and space from both ends, even unbalanced ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, TrimPolicy};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     separator: String,
+    ///     greeting: String,
+    /// }
+    ///
+    /// let input = "SEPARATOR=\" | \"\nGREETING= 'it's me' \n";
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .trim_values(TrimPolicy::Unquote)
+    ///     .from_str(input)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         separator: " | ".to_owned(),
+    ///         greeting: "it's me".to_owned()
+    ///     }
+    /// );
+    /// ```
+    pub fn trim_values(mut self, policy: TrimPolicy) -> Self {
+        self.trim_values = policy;
+        self
+    }
+
+    /// Remove the quotes and backslashes of the values read by [`Builder::from_str`],
+    /// [`Builder::from_file`] and [`Builder::from_reader`] following the rules of POSIX
+    /// shells, so files that are also sourced by a shell read the same way. Values
+    /// can mix single quotes, which keep everything as it is, double quotes, where
+    /// `\` only escapes `$`, `` ` ``, `"` and `\`, and backslashes escaping
+    /// any character, such as `'it'\''s'` or `"a"' 'b`.
+    ///
+    /// The whitespace around values is removed, but not the one between their
+    /// words, and [`Builder::trim_values`] is ignored for these values.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     greeting: String,
+    ///     path: String,
+    /// }
+    ///
+    /// let input = "GREETING='it'\\''s' \"a \\\"quoted\\\" word\"\nPATH=C:\\\\temp\\ dir\n";
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().shell_quoting(true).from_str(input).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         greeting: "it's a \"quoted\" word".to_owned(),
+    ///         path: "C:\\temp dir".to_owned(),
+    ///     }
+    /// );
+    /// ```
+    #[cfg(feature = "shellwords")]
+    pub fn shell_quoting(mut self, enabled: bool) -> Self {
+        self.shell_quoting = enabled;
+        self
+    }
+
+    /// Split the values of the field or variable called `name` into words at
+    /// unquoted whitespace, following the quoting rules of POSIX shells, for
+    /// sequences of command line arguments, such as `EXTRA_ARGS="--foo 'a b'"`.
+    /// `name` is matched like for [`Builder::parse_with`], which [`Builder::shell_words`]
+    /// replaces for the same name, and the other way around.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     extra_args: Vec<String>,
+    /// }
+    ///
+    /// let input = "EXTRA_ARGS=\"--foo 'a b' --bar=\\\"c\\\"\"\n";
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .shell_words("extra_args")
+    ///     .from_str(input)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct.extra_args,
+    ///     vec!["--foo", "a b", "--bar=c"]
+    /// );
+    /// ```
+    #[cfg(feature = "shellwords")]
+    pub fn shell_words(mut self, name: &str) -> Self {
+        let parse = |value: &str| match shellwords::split(value) {
+            Ok(words) => Ok(Value::Seq(words.into_iter().map(Value::String).collect())),
+            Err(reason) => Err(Error::Custom(format!(
+                "{} while parsing value '{}'",
+                reason, value
+            ))),
+        };
+
+        self.options
+            .parsers
+            .push((name.to_owned(), Hook(Arc::new(parse))));
+        self
+    }
+
+    /// Keep the quotes at both ends of values and of the elements of sequences,
+    /// trimming only whitespace, for values that legitimately begin or end
+    /// with quotes, such as regexes. Same as setting [`Builder::trim_values`]
+    /// to [`TrimPolicy::Whitespace`], which it replaces, and applying it to elements.
+    ///
+    /// To keep surrounding whitespace as well, use [`TrimPolicy::None`].
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::builder;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     pattern: String,
+    ///     quoted: Vec<String>,
+    /// }
+    ///
+    /// let input = "PATTERN= \"[a-z]+'\nQUOTED='a', \"b\"\n";
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().preserve_quotes(true).from_str(input).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         pattern: "\"[a-z]+'".to_owned(),
+    ///         quoted: vec!["'a'".to_owned(), "\"b\"".to_owned()]
+    ///     }
+    /// );
+    /// ```
+    pub fn preserve_quotes(mut self, preserve: bool) -> Self {
+        self.options.preserve_quotes = preserve;
+        self.trim_values = match preserve {
+            true => TrimPolicy::Whitespace,
+            false => TrimPolicy::QuotesAndWhitespace,
+        };
+        self
+    }
+
+    /// Set how the elements of sequences and tuples are trimmed, see [`TrimPolicy`].
+    ///
+    /// Elements are split at each delimiter that isn't escaped with a backslash,
+    /// as in `a\,b`, or inside the quotes an element starts with, as in `"a,b"`,
+    /// then trimmed. Defaults to trimming quotes and whitespace, or only
+    /// whitespace when [`Builder::preserve_quotes`] is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, TrimPolicy};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     names: Vec<String>,
+    /// }
+    ///
+    /// let vars = vec![(
+    ///     "NAMES".to_owned(),
+    ///     r#"Doe\, John, " Smith, Jane ", Roe"#.to_owned(),
+    /// )];
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .trim_elements(TrimPolicy::Unquote)
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         names: vec![
+    ///             "Doe, John".to_owned(),
+    ///             " Smith, Jane ".to_owned(),
+    ///             "Roe".to_owned()
+    ///         ]
+    ///     }
+    /// );
+    /// ```
+    pub fn trim_elements(mut self, policy: TrimPolicy) -> Self {
+        self.options.trim_elements = Some(policy);
+        self
+    }
+
+    /// Collect the non-fatal issues found while deserializing into `warnings`,
+    /// see [`Warnings`]
+    ///
+    /// Replaces the callback set with [`Builder::on_warning`].
+    pub fn warnings(self, warnings: &Warnings) -> Self {
+        let warnings = warnings.clone();
+        self.on_warning(move |warning| warnings.push(warning.clone()))
+    }
+
+    /// Fail with [`crate::Error::UnknownKeys`] if any of the variables
+    /// don't match a field of the target struct, to catch typos such as
+    /// `APP_TIMEOTU`. Meant to be used along with [`Builder::prefix`],
+    /// since the environment of a process is full of unrelated variables.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     timeout: Option<u64>,
+    /// }
+    ///
+    /// let vars = vec![("APP_TIMEOTU".to_owned(), "30".to_owned())];
+    ///
+    /// let error = builder()
+    ///     .prefix("APP_")
+    ///     .deny_unknown(true)
+    ///     .from_iter::<CustomStruct, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error, Error::UnknownKeys(vec!["APP_TIMEOTU".to_owned()]))
+    /// ```
+    pub fn deny_unknown(mut self, deny: bool) -> Self {
+        self.options.deny_unknown = deny;
+        self
+    }
+
+    /// Fail with [`crate::Error::DuplicateElement`] if an element is repeated
+    /// in the value of a sequence, such as a `Vec`, or a set, such as a `BTreeSet`,
+    /// which otherwise keeps only one of them. Elements are compared as they're
+    /// written, after trimming, rather than once parsed, so `1` and `01` aren't
+    /// duplicates, even though a `HashSet<u16>` only holds one of them.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error};
+    /// use serde::Deserialize;
+    /// use std::collections::BTreeSet;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     tags: BTreeSet<String>,
+    /// }
+    ///
+    /// let vars = vec![("TAGS".to_owned(), "web, api, web".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct = builder().from_iter(vars.clone()).unwrap();
+    ///
+    /// assert_eq!(custom_struct.tags.len(), 2);
+    ///
+    /// let error = builder()
+    ///     .deny_duplicate_elements(true)
+    ///     .from_iter::<CustomStruct, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error, Error::DuplicateElement("web".to_owned()))
+    /// ```
+    pub fn deny_duplicate_elements(mut self, deny: bool) -> Self {
+        self.options.deny_duplicate_elements = deny;
+        self
+    }
+
+    /// Wrap errors in [`crate::Error::AtPath`] with the path of the field they
+    /// belong to, such as `server.tls.port` for the `port` field of the `tls` field
+    /// of `server`, read from `SERVER_TLS_PORT`, and `server.ports[1]` for the second
+    /// element of a sequence. [`crate::Error::path`] returns the path.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Tls {
+    ///     cert_path: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Server {
+    ///     tls: Tls,
+    ///     ports: Vec<u16>,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct App {
+    ///     server: Server,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("SERVER_TLS_CERT_PATH".to_owned(), "/etc/tls/cert.pem".to_owned()),
+    ///     ("SERVER_TLS_PORT".to_owned(), "443".to_owned()),
+    ///     ("SERVER_PORTS".to_owned(), "80, http".to_owned()),
+    /// ];
+    ///
+    /// let error = builder()
+    ///     .track_paths(true)
+    ///     .from_iter::<App, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error.path(), Some("server.ports[1]"));
+    /// ```
+    pub fn track_paths(mut self, track: bool) -> Self {
+        self.options.track_paths = track;
+        self
+    }
+
+    /// Choose what happens to variables that are set to an empty string,
+    /// such as `KEY=`, which can then be told apart from variables that aren't set
+    ///
+    /// Defaults to [`EmptyStringPolicy::TreatAsNone`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, EmptyStringPolicy, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     banner: Option<String>,
+    ///     motd: Option<String>,
+    /// }
+    ///
+    /// let input = "BANNER=\n";
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .empty_strings(EmptyStringPolicy::TreatAsEmpty)
+    ///     .from_str(input)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         banner: Some(String::new()),
+    ///         motd: None
+    ///     }
+    /// );
+    ///
+    /// let error = builder()
+    ///     .empty_strings(EmptyStringPolicy::Error)
+    ///     .from_str::<CustomStruct>(input)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error, Error::EmptyValue("BANNER".to_owned()))
+    /// ```
+    pub fn empty_strings(mut self, policy: EmptyStringPolicy) -> Self {
+        self.options.empty_strings = policy;
+        self
+    }
+
+    /// Choose what happens when several variables map to the same key,
+    /// such as `APP_key` and `APP_KEY`, or a key repeated in a `.env` file
+    ///
+    /// Defaults to [`DuplicateKeyPolicy::LastWins`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, DuplicateKeyPolicy, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     key: String,
+    /// }
+    ///
+    /// let input = "KEY=first\nkey=second\n";
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .duplicate_keys(DuplicateKeyPolicy::FirstWins)
+    ///     .from_str(input)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(custom_struct.key, "first");
+    ///
+    /// let error = builder()
+    ///     .duplicate_keys(DuplicateKeyPolicy::Error)
+    ///     .from_str::<CustomStruct>(input)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error,
+    ///     Error::DuplicateKey {
+    ///         first: "KEY".to_owned(),
+    ///         second: "key".to_owned()
+    ///     }
+    /// )
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.options.duplicate_keys = policy;
+        self
+    }
+
+    /// Set the naming convention of the keys, used to match them with
+    /// the snake_case fields of the target struct
+    ///
+    /// Defaults to [`KeyCase::ScreamingSnake`], which matches keys ignoring case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, KeyCase};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     max_connections: u32,
+    /// }
+    ///
+    /// let vars = vec![("max-connections".to_owned(), "16".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().key_case(KeyCase::Kebab).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(custom_struct, CustomStruct { max_connections: 16 })
+    /// ```
+    pub fn key_case(mut self, case: KeyCase) -> Self {
+        self.options.key_case = case;
+        self
+    }
+
+    /// Set the naming convention of the fields of the target struct, for
+    /// when they're renamed with `#[serde(rename_all = "...")]`. Keys are
+    /// converted from [`Builder::key_case`] to this convention before matching.
+    ///
+    /// Defaults to snake_case, as is the Rust convention.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, KeyCase};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// #[serde(rename_all = "camelCase")]
+    /// struct CustomStruct {
+    ///     max_connections: u32,
+    /// }
+    ///
+    /// let vars = vec![("MAX_CONNECTIONS".to_owned(), "16".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().field_case(KeyCase::Camel).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(custom_struct, CustomStruct { max_connections: 16 })
+    /// ```
+    pub fn field_case(mut self, case: KeyCase) -> Self {
+        self.options.field_case = Some(case);
+        self
+    }
+
+    /// Match keys with the fields of the target struct exactly as they're written,
+    /// for fields renamed to names that aren't in snake_case, such as `HTTPPort`,
+    /// which no [`Builder::key_case`] can turn keys into.
+    /// [`Builder::key_case`] and [`Builder::field_case`] are ignored when enabled.
+    ///
+    /// Disabled by default, which matches keys ignoring case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     #[serde(rename = "HTTPPort")]
+    ///     http_port: u16,
+    /// }
+    ///
+    /// let vars = vec![("HTTPPort".to_owned(), "8080".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     builder().exact_keys(true).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(custom_struct, CustomStruct { http_port: 8080 });
+    ///
+    /// let vars = vec![("HTTPPORT".to_owned(), "8080".to_owned())];
+    ///
+    /// assert_eq!(
+    ///     builder().exact_keys(true).from_iter::<CustomStruct, _>(vars),
+    ///     Err(Error::MissingValue("HTTPPort".to_owned()))
+    /// );
+    /// ```
+    pub fn exact_keys(mut self, exact: bool) -> Self {
+        self.options.exact_keys = exact;
+        self
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Panics
+    ///
+    /// If any of the environment variables contain invalid unicode.
+    /// For a non-panicky alternative, use [`Builder::from_os_env`]
+    pub fn from_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(env::vars())
+    }
+
+    /// Same as [`Builder::from_env`], then run the checks of [`Validate`] on the value
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization, or
+    /// [`Error::Validation`] with every constraint the value breaks
+    ///
+    /// # Panics
+    ///
+    /// If any of the environment variables contain invalid unicode
+    pub fn from_env_validated<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned + Validate,
+    {
+        self.from_env().and_then(validate)
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time, but doesn't panic
+    /// if any of the environment variables contain invalid unicode, instead returns
+    /// an error.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_os_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(maybe_invalid_unicode_vars_os()?)
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently running process's
+    /// environment variables at invocation time, converting the names and values
+    /// that aren't valid unicode lossily, replacing the invalid sequences with `U+FFFD`.
+    /// Every variable converted this way that could be read raises
+    /// [`Warning::LossyConversion`].
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Warnings};
+    /// use serde::Deserialize;
+    /// use std::env;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     name: String,
+    /// }
+    ///
+    /// env::set_var("LOSSY_DOC_NAME", "api");
+    ///
+    /// let warnings = Warnings::new();
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("LOSSY_DOC_")
+    ///     .warnings(&warnings)
+    ///     .from_os_env_lossy()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(custom_struct.name, "api");
+    /// assert!(warnings.is_empty());
+    /// ```
+    pub fn from_os_env_lossy<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        let vars: Vec<(String, String)> = env::vars_os()
+            .map(|(key, value)| {
+                let lossy = (
+                    key.to_string_lossy().into_owned(),
+                    value.to_string_lossy().into_owned(),
+                );
+
+                if (key.to_str().is_none() || value.to_str().is_none())
+                    && (self.strip_key(&lossy.0).is_some()
+                        || self.alias_of(&lossy.0).is_some())
+                {
+                    self.options.warn(Warning::LossyConversion {
+                        key: lossy.0.clone(),
+                    });
+                }
+
+                lossy
+            })
+            .collect();
+
+        self.from_iter(vars)
+    }
+
+    /// Deserialize some type `T` from a [`str`], which follows
+    /// the same format as described in [`crate::from_str`]
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_str<T>(&self, input: &str) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(self.parse(input)?.into_iter())
+    }
+
+    /// Deserialize some type `T` from a [`str`], which follows the same format as
+    /// described in [`crate::from_str`], merged with the variables of the currently
+    /// running process. `precedence` decides which of them is kept when both set
+    /// the same variable, see [`crate::from_str_with_env_override`]
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Panics
+    /// if any of the environment variables contain invalid unicode
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, EnvPrecedence};
+    /// use serde::Deserialize;
+    /// use std::env;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// env::set_var("SVC_HOST", "0.0.0.0");
+    /// env::set_var("SVC_PORT", "8080");
+    ///
+    /// let custom_struct: CustomStruct = builder()
+    ///     .prefix("SVC_")
+    ///     .from_str_with_env("SVC_PORT=9090\n", EnvPrecedence::Input)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host: "0.0.0.0".to_owned(),
+    ///         port: 9090
+    ///     }
+    /// );
+    /// ```
+    pub fn from_str_with_env<T>(
+        &self,
+        input: &str,
+        precedence: EnvPrecedence,
+    ) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        let input = self.parse(input)?;
+        let env: Vec<(String, String)> = self.trim(env::vars()).collect();
+
+        let (under, over) = match precedence {
+            EnvPrecedence::Env => (input, env),
+            EnvPrecedence::Input => (env, input),
+        };
+        let overridden: HashSet<&str> =
+            over.iter().map(|(key, _)| key.as_str()).collect();
+
+        let vars: Vec<(String, String)> = under
+            .iter()
+            .filter(|(key, _)| !overridden.contains(key.as_str()))
+            .chain(&over)
+            .cloned()
+            .collect();
+
+        self.deserialize(vars.into_iter())
+    }
+
+    /// Deserialize some type `T` from the env file at `path`,
+    /// which follows the same format as described in [`crate::from_str`]
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read, if it's older than allowed by [`Builder::max_age`]
+    /// or any errors that might occur during deserialization
+    pub fn from_file<T>(&self, path: impl AsRef<Path>) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_str(&self.read_file(path.as_ref())?)
+    }
+
+    /// Deserialize some type `T` from the variables read out of `reader`,
+    /// written in `format`, see [`crate::from_reader`]. Values written
+    /// as [`Format::Dotenv`] are trimmed according to [`Builder::trim_values`],
+    /// the ones of the other formats are taken as they're written.
+    ///
+    /// # Errors
+    ///
+    /// If `reader` fails, [`Error::InvalidUnicode`] if a line isn't valid UTF-8,
+    /// if the variables can't be parsed or any errors that might occur during deserialization
+    pub fn from_reader<T, R>(&self, reader: R, format: Format) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        R: BufRead,
+    {
+        let mut vars = Vec::new();
+        let mut systemd = format::Systemd::default();
+
+        // Split on bytes rather than reading `String`s, so lines that aren't valid
+        // UTF-8 are reported as such, along with their contents, like `from_nul_delimited` does
+        for (i, line) in reader.split(b'\n').enumerate() {
+            // The `\r` of `\r\n` line endings is stripped by `parse_line`
+            let line = line.map_err(|error| {
+                Error::Custom(format!("failed to read input: {}", error))
+            })?;
+
+            let line = String::from_utf8(line).map_err(|e| {
+                Error::InvalidUnicode(format::os_string(e.into_bytes()))
+            })?;
+            let line = match i {
+                0 => line.strip_prefix(file::BOM).unwrap_or(&line),
+                _ => &line,
+            };
+
+            self.parse_line(format, i + 1, line, &mut systemd, &mut vars)?;
+        }
+
+        systemd.finish(&mut vars);
+
+        self.deserialize(vars.into_iter())
+    }
+
+    /// Deserialize some type `T` from NUL separated `KEY=value` entries,
+    /// with values taken as they're written, see [`crate::from_nul_delimited`]
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidUnicode`] if a key or value isn't valid UTF-8,
+    /// or any errors that might occur during deserialization
+    pub fn from_nul_delimited<T>(&self, bytes: &[u8]) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.deserialize(format::nul_delimited(bytes)?.into_iter())
+    }
+
+    /// Deserialize some type `T` from the UTF-16 encoded env file at `path`, such as
+    /// the ones written by Windows tools, which follows the same format as described
+    /// in [`crate::from_str`]. The byte order is taken from the byte order mark
+    /// the file starts with, little endian if there's none.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or isn't valid UTF-16, if it's older than allowed
+    /// by [`Builder::max_age`] or any errors that might occur during deserialization
+    pub fn from_utf16_file<T>(&self, path: impl AsRef<Path>) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        let path = path.as_ref();
+
+        self.from_str(&self.check_age(path, file::read_utf16(path)?)?)
+    }
+
+    /// Deserialize some type `T` from the variables loaded from `source`,
+    /// see [`crate::from_source`]
+    ///
+    /// # Errors
+    ///
+    /// If the source fails to load or any errors that might occur during deserialization
+    pub fn from_source<T, S>(&self, source: &S) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        S: Source + ?Sized,
+    {
+        self.from_iter(source.load()?)
+    }
+
+    /// Deserialize some type `T` from the variables loaded from the async `source`,
+    /// see [`crate::from_async_source`]
+    ///
+    /// # Errors
+    ///
+    /// If the source fails to load or any errors that might occur during deserialization
+    #[cfg(feature = "async_source")]
+    pub async fn from_async_source<T, S>(&self, source: &S) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        S: AsyncSource + ?Sized,
+    {
+        self.from_iter(source.load().await?)
+    }
+
+    /// Deserialize some type `T` from the env file at `path` and deserialize it
+    /// again whenever the file changes, see [`crate::watch_file`]
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or watched, or any errors that might
+    /// occur during the first deserialization
+    #[cfg(feature = "watch")]
+    pub fn watch_file<T, F>(
+        &self,
+        path: impl AsRef<Path>,
+        callback: F,
+    ) -> Result<Watched<T>>
+    where
+        T: de::DeserializeOwned + Send + Sync + 'static,
+        F: FnMut(Result<Arc<T>>) + Send + 'static,
+    {
+        watch(self, path.as_ref(), callback)
+    }
+
+    /// Deserialize some type `T` from the environment of the process
+    /// with the id `pid`, see [`crate::from_pid`]
+    ///
+    /// # Errors
+    ///
+    /// If the environment can't be read, if it contains invalid unicode
+    /// or any errors that might occur during deserialization
+    #[cfg(target_os = "linux")]
+    pub fn from_pid<T>(&self, pid: u32) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(pid::environ(pid)?)
+    }
+
+    /// Report which variable of `raw` every field of the struct `T` would be
+    /// read from with these settings, and the value it holds, see [`Explanation`].
+    /// Nothing is deserialized, so values are reported even if they're invalid.
+    ///
+    /// The field set with [`Builder::capture_rest`] is left out.
+    /// Returns an empty report for anything other than a struct with named fields,
+    /// including structs with flattened fields, whose keys can't be known in advance.
+    pub fn explain<'de, T>(&self, raw: &RawEnv) -> Explanation
+    where
+        T: de::Deserialize<'de>,
+    {
+        let rest = self.options.capture_rest.as_deref();
+
+        Explanation::new(
+            describe::<T>()
+                .into_iter()
+                .filter(|field| Some(field.name) != rest)
+                .map(|field| {
+                    // The last match with the earliest prefix wins,
+                    // as it would when deserializing
+                    let variable =
+                        raw.iter()
+                            .filter_map(|(key, value)| {
+                                let (rank, name) = self.strip_ranked(key)?;
+
+                                (field_key(name, &self.options) == field.name)
+                                    .then_some((Reverse(rank), key, value))
+                            })
+                            .max_by_key(|(rank, _, _)| *rank);
+
+                    let (key, value) = match variable {
+                        Some((_, key, value)) => {
+                            (key.to_owned(), Some(value.to_owned()))
+                        }
+                        None => (
+                            self.restore(
+                                self.options.to_key(field.name),
+                                &HashMap::new(),
+                            ),
+                            None,
+                        ),
+                    };
+
+                    FieldExplanation {
+                        field: field.name.to_owned(),
+                        key,
+                        value,
+                        optional: field.optional,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Read the env file at `path`, checking its age against [`Builder::max_age`]
+    pub(crate) fn read_file(&self, path: &Path) -> Result<String> {
+        self.check_age(path, file::read(path)?)
+    }
+
+    /// Check the age of `content`, read from the file at `path`,
+    /// against [`Builder::max_age`]
+    fn check_age(&self, path: &Path, content: String) -> Result<String> {
+        if let Some(max_age) = self.max_age {
+            let checked = file::check_age(path, file::age(path, &content)?, max_age);
+
+            match (checked, &self.on_stale) {
+                (Err(error @ Error::Stale { .. }), Some(on_stale)) => {
+                    (on_stale.0)(&error)
+                }
+                (checked, _) => checked?,
+            }
+        }
+
+        Ok(content)
+    }
+
+    /// The names of the variables the field `field` is read from, such as
+    /// `APP_DB_HOST` for `db_host`, with the prefix set with [`Builder::prefix`]
+    /// and then with each fallback prefix, followed by the postfix
+    pub(crate) fn key_names(&self, field: &str) -> Vec<String> {
+        let key = self.options.to_key(field);
+        let postfix = self.postfix.as_deref().unwrap_or_default();
+
+        match self.prefixes.is_empty() {
+            true => vec![format!("{}{}", key, postfix)],
+            false => self
+                .prefixes
+                .iter()
+                .map(|prefix| format!("{}{}{}", prefix, key, postfix))
+                .collect(),
+        }
+    }
+
+    /// Mark a field as secret, set up with [`crate::Config::secret`], which
+    /// raises [`Warning::DefaultedSecret`] if it's read from the defaults file
+    pub(crate) fn secret(mut self, field: &str) -> Self {
+        self.secrets.push(field.to_owned());
+        self
+    }
+
+    /// Whether the variable `key` belongs to a field marked with [`Builder::secret`]
+    fn is_secret(&self, key: &str) -> bool {
+        self.strip_key(key).is_some_and(|name| {
+            self.secrets
+                .iter()
+                .any(|secret| self.options.key_case.to_key(secret) == name)
+        })
+    }
+
+    /// Deserialize some type `T` from an iterator over key-value pairs.
+    ///
+    /// Like with [`crate::from_iter`], single quotes, double quotes
+    /// and whitespace will be trimmed
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_iter<T, Iter>(&self, iter: Iter) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        Iter: IntoIterator<Item = (String, String)>,
+    {
+        self.deserialize(self.trim(iter.into_iter()))
+    }
+
+    /// Same as [`Builder::from_iter`], then run the checks of [`Validate`] on the value
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization, or
+    /// [`Error::Validation`] with every constraint the value breaks
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Error, Validate, ValidationError};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Pool {
+    ///     min: u8,
+    ///     max: u8,
+    /// }
+    ///
+    /// impl Validate for Pool {
+    ///     fn validate(&self) -> Result<(), Vec<ValidationError>> {
+    ///         match self.min <= self.max {
+    ///             true => Ok(()),
+    ///             false => Err(vec![ValidationError::new("min", "must not exceed max")]),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("POOL_MIN".to_owned(), "8".to_owned()),
+    ///     ("POOL_MAX".to_owned(), "4".to_owned()),
+    /// ];
+    ///
+    /// assert_eq!(
+    ///     builder()
+    ///         .prefix("POOL_")
+    ///         .from_iter_validated::<Pool, _>(vars)
+    ///         .unwrap_err(),
+    ///     Error::Validation(vec![ValidationError::new("min", "must not exceed max")])
+    /// );
+    /// ```
+    pub fn from_iter_validated<T, Iter>(&self, iter: Iter) -> Result<T>
+    where
+        T: de::DeserializeOwned + Validate,
+        Iter: IntoIterator<Item = (String, String)>,
+    {
+        self.from_iter(iter).and_then(validate)
+    }
+
+    /// Deserialize some type `T` from variables that are already trimmed
+    pub(crate) fn deserialize<T, Iter>(&self, iter: Iter) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        Iter: Iterator<Item = (String, String)>,
+    {
+        let mut options = self.options.clone();
+
+        // Parsers can be registered for variables with their prefix and postfix
+        for (name, _) in options.parsers.iter_mut() {
+            if let Some(stripped) = self.strip_key(name) {
+                *name = stripped.to_owned();
+            }
+        }
+        let mut vars: Vec<(String, String)> = iter.collect();
+
+        if let Some(path) = &self.defaults {
+            let mut defaults: Vec<(String, String)> = self.parse(&file::read(path)?)?;
+
+            if self.require_known_defaults {
+                options.must_match = defaults
+                    .iter()
+                    .filter_map(|(key, _)| self.strip_key(key))
+                    .map(str::to_owned)
+                    .collect();
+            }
+
+            // Defaults are overridden by the variables of the same field, whatever
+            // their case or prefix, so the environment wins regardless of the
+            // `DuplicateKeyPolicy`, which only applies within a single source
+            let field_of =
+                |key: &str| field_key(self.strip_key(key).unwrap_or(key), &options);
+            let fields: HashSet<String> = vars
+                .iter()
+                .map(|(key, _)| field_of(key))
+                .collect();
+
+            defaults.retain(|(key, _)| {
+                let overridden = fields.contains(&field_of(key));
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    target: "renvar",
+                    variable = %key,
+                    defaults = %path.display(),
+                    overridden,
+                    "variable found in the defaults file"
+                );
+
+                !overridden
+            });
+
+            for (key, _) in &defaults {
+                if self.is_secret(key) {
+                    options.warn(Warning::DefaultedSecret { key: key.clone() });
+                }
+            }
+
+            vars.splice(0..0, defaults);
+        }
+
+        // Only the variables that are deserialized are expanded, the others,
+        // such as unrelated variables of the process, can still be referenced
+        if self.interpolate {
+            interpolate(&mut vars, |key| {
+                self.alias_of(key).is_some() || self.strip_ranked(key).is_some()
+            })?;
+        }
+
+        let mut stripped = Vec::with_capacity(vars.len());
+        let mut origins: HashMap<String, (usize, String)> = HashMap::new();
+
+        for (key, value) in vars {
+            let Some((rank, name)) = self.alias_of(&key).or_else(|| {
+                self.strip_ranked(&key)
+                    .map(|(rank, name)| (rank, name.to_owned()))
+            }) else {
+                continue;
+            };
+
+            let value = match &self.map_values {
+                Some(map_values) => (map_values.0)(&key, &value),
+                None => value,
+            };
+            let value = resolve(&self.resolvers, &key, value)?;
+
+            let origin = origins
+                .entry(name.clone())
+                .or_insert_with(|| (rank, key.clone()));
+            if rank < origin.0 {
+                *origin = (rank, key);
+            }
+            stripped.push((rank, name, value));
+        }
+
+        // Variables with a fallback prefix or an alias
+        // only count for keys no earlier prefix or alias sets
+        let mut stripped: Vec<(String, String)> = stripped
+            .into_iter()
+            .filter(|(rank, name, _)| origins[name].0 == *rank)
+            .map(|(_, name, value)| (name, value))
+            .collect();
+
+        let mut deprecated: Vec<Warning> = origins
+            .iter()
+            .filter_map(|(name, (rank, key))| self.deprecation(name, *rank, key))
+            .collect();
+        deprecated.sort_unstable_by_key(Warning::to_string);
+
+        for warning in deprecated {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(target: "renvar", "{}", warning);
+
+            options.warn(warning);
+        }
+        let origins = Arc::new(origins);
+
+        if let Some(profiles) = &self.profiles {
+            stripped = profiles.resolve(stripped);
+        }
+
+        if self.file_indirection {
+            let mut options = options.clone();
+            options.nest::<T>();
+            stripped = read_indirect(stripped, &options)?;
+        }
+
+        // Warnings should name the actual variables, like errors do
+        if !self.prefixes.is_empty() || self.postfix.is_some() {
+            if let Some(on_warning) = options.on_warning.take() {
+                let affixed = self.clone();
+                let origins = Arc::clone(&origins);
+                options.on_warning = Some(Hook(Arc::new(move |warning: &Warning| {
+                    (on_warning.0)(
+                        &warning
+                            .clone()
+                            .map_keys(|key| affixed.restore(key, &origins)),
+                    )
+                })));
+            }
+
+            if let Some(on_unused_key) = options.on_unused_key.take() {
+                let affixed = self.clone();
+                let origins = Arc::clone(&origins);
+                options.on_unused_key =
+                    Some(Hook(Arc::new(move |key: &str, value: &str| {
+                        (on_unused_key.0)(
+                            &affixed.restore(key.to_owned(), &origins),
+                            value,
+                        )
+                    })));
+            }
+        }
+
+        let set: HashSet<String> = match self.rules.is_empty() {
+            true => HashSet::new(),
+            false => stripped
+                .iter()
+                .filter(|(_, value)| !value.chars().all(is_quote_or_whitespace))
+                .map(|(key, _)| field_key(key, &options))
+                .collect(),
+        };
+
+        let value = T::deserialize(
+            EnvVarDeserializer::with_options(stripped.into_iter(), options)
+                .nested::<T>(),
+        )
+        .map_err(|error| self.restore_keys(error, &origins))?;
+
+        let errors = rules::check(&self.rules, &set);
+
+        match errors.is_empty() {
+            true => Ok(value),
+            false => Err(Error::Validation(errors)),
+        }
+    }
+
+    /// Split a blob of `KEY=value` lines into `(key, value)` pairs trimmed
+    /// according to [`Builder::trim_keys`] and [`Builder::trim_values`].
+    ///
+    /// Escape sequences are expanded in double quoted values, unless the values
+    /// are trimmed of whitespace only or not at all, which keeps their quotes
+    pub(crate) fn parse(&self, input: &str) -> Result<Vec<(String, String)>> {
+        let raw = !matches!(
+            self.trim_values,
+            TrimPolicy::QuotesAndWhitespace | TrimPolicy::Unquote
+        );
+
+        split_lines(input, raw)
+            .map(|line| line.and_then(|(key, value)| self.unquote(key, value)))
+            .collect()
+    }
+
+    /// Parse the line numbered `number`, written in `format`, adding its key
+    /// and value to `vars`, trimmed like [`Builder::parse`] does for [`Format::Dotenv`].
+    /// Assignments of [`Format::Systemd`] can span several lines,
+    /// so they're kept in `systemd` until they're complete.
+    fn parse_line(
+        &self,
+        format: Format,
+        number: usize,
+        line: &str,
+        systemd: &mut format::Systemd,
+        vars: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        match format {
+            Format::Systemd => systemd.feed(line, vars),
+            _ if is_blank_or_comment(line) => {}
+            Format::Dotenv => {
+                let raw = !matches!(
+                    self.trim_values,
+                    TrimPolicy::QuotesAndWhitespace | TrimPolicy::Unquote
+                );
+                let (key, value) = split_line(number, line, raw)?;
+
+                vars.push(self.unquote(key, value)?);
+            }
+            Format::Docker => vars.extend(format::docker_line(number, line)?),
+            Format::PowerShell => vars.push(format::powershell_line(number, line)?),
+        }
+
+        Ok(())
+    }
+
+    /// Trim `key` and `value` according to [`Builder::trim_keys`]
+    /// and [`Builder::trim_values`], expanding escape sequences,
+    /// or following [`Builder::shell_quoting`] for `value`
+    fn unquote(&self, key: &str, value: &str) -> Result<(String, String)> {
+        #[cfg(feature = "shellwords")]
+        if self.shell_quoting {
+            let unquoted = shellwords::unquote(value).map_err(|reason| {
+                Error::Custom(format!("{} while parsing value '{}'", reason, value))
+            })?;
+
+            return Ok((self.trim_keys.apply(key).to_owned(), unquoted));
+        }
+
+        let unescaped = match self.trim_values {
+            TrimPolicy::QuotesAndWhitespace => {
+                unescape_quoted(value).map(|value| value.trim_matches(' ').to_owned())
+            }
+            TrimPolicy::Unquote => unescape_quoted(value).map(Cow::into_owned),
+            _ => None,
+        };
+        let value =
+            unescaped.unwrap_or_else(|| self.trim_values.apply(value).to_owned());
+
+        Ok((self.trim_keys.apply(key).to_owned(), value))
+    }
+
+    /// Trim the keys and values of `vars` according to
+    /// [`Builder::trim_keys`] and [`Builder::trim_values`]
+    pub(crate) fn trim<'b, Iter>(
+        &'b self,
+        vars: Iter,
+    ) -> impl Iterator<Item = (String, String)> + 'b
+    where
+        Iter: Iterator<Item = (String, String)> + 'b,
+    {
+        vars.map(|(key, value)| {
+            (
+                self.trim_keys.apply(&key).to_owned(),
+                self.trim_values.apply(&value).to_owned(),
+            )
+        })
+    }
+
+    /// Strip the prefix and postfix from `key`, unless they're kept
+    /// with [`Builder::strip`], or `None` if it doesn't have both of them
+    pub(crate) fn strip_key<'k>(&self, key: &'k str) -> Option<&'k str> {
+        self.strip_ranked(key)
+            .map(|(_, stripped)| stripped)
+    }
+
+    /// Same as [`Builder::strip_key`], along with the position of
+    /// the prefix that was stripped among the fallback prefixes
+    fn strip_ranked<'k>(&self, key: &'k str) -> Option<(usize, &'k str)> {
+        let (rank, stripped) = match self.prefixes.is_empty() {
+            true => (0, key),
+            false => self
+                .prefixes
+                .iter()
+                .enumerate()
+                .find_map(|(rank, prefix)| {
+                    Some((rank, key.strip_prefix(prefix.as_str())?))
+                })?,
+        };
+
+        let stripped = match &self.postfix {
+            Some(postfix) => stripped.strip_suffix(postfix.as_str())?,
+            None => stripped,
+        };
+
+        match self.keep_affixes {
+            true => Some((rank, key)),
+            false => Some((rank, stripped)),
+        }
+    }
+
+    /// Put the prefix `key` was read with and the postfix back on it, unless they
+    /// were kept. `origins` holds the position of the prefix each key was read with
+    fn restore(
+        &self,
+        key: String,
+        origins: &HashMap<String, (usize, String)>,
+    ) -> String {
+        if self.keep_affixes {
+            return key;
+        }
+
+        if let Some((_, variable)) = origins.get(&key) {
+            return variable.clone();
+        }
+
+        format!(
+            "{}{}{}",
+            self.prefixes
+                .first()
+                .map(String::as_str)
+                .unwrap_or_default(),
+            key,
+            self.postfix.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// The warning to emit for the variable `key`, read for the key `name` with the
+    /// precedence `rank`, if it's an alias or a variable of a deprecated field
+    fn deprecation(&self, name: &str, rank: usize, key: &str) -> Option<Warning> {
+        let field = field_key(name, &self.options);
+        let replace =
+            |field: &str| self.restore(self.options.to_key(field), &HashMap::new());
+
+        match self
+            .deprecations
+            .iter()
+            .find(|(deprecated, _)| *deprecated == field)
+        {
+            Some((_, deprecation)) => Some(Warning::DeprecatedKey {
+                key: key.to_owned(),
+                replacement: deprecation.replacement.as_deref().map(replace),
+                removal: deprecation.removal.clone(),
+            }),
+            None if rank >= self.prefixes.len().max(1) => {
+                Some(Warning::DeprecatedKey {
+                    key: key.to_owned(),
+                    replacement: Some(self.restore(name.to_owned(), &HashMap::new())),
+                    removal: None,
+                })
+            }
+            None => None,
+        }
+    }
+
+    /// The key of the field `key` is an alias of, see [`Builder::alias`], along
+    /// with its precedence, which comes after the one of every prefix
+    fn alias_of(&self, key: &str) -> Option<(usize, String)> {
+        let first = self.prefixes.len().max(1);
+
+        self.aliases.iter().find_map(|(field, aliases)| {
+            let position = aliases.iter().position(|alias| alias == key)?;
+            let name = self.options.to_key(field);
+            let name = match self.keep_affixes {
+                true => self.restore(name, &HashMap::new()),
+                false => name,
+            };
+
+            Some((first + position, name))
+        })
+    }
+
+    /// Put the prefix and postfix back on the keys reported
+    /// by errors, so they match the names of the actual variables
+    fn restore_keys(
+        &self,
+        error: Error,
+        origins: &HashMap<String, (usize, String)>,
+    ) -> Error {
+        error.map_keys(|key| self.restore(key, origins))
+    }
+}
+
+/// Replace the variables ending in `_FILE` with the variable they stand in for,
+/// holding the contents of the file they point to, see [`Builder::file_indirection`].
+///
+/// Only variables standing in for a field of the struct set up in `options` are
+/// replaced, unless the field is named after the variable itself, such as `log_file`.
+/// Every other variable ending in `_FILE` is left as it is.
+fn read_indirect(
+    vars: Vec<(String, String)>,
+    options: &Options,
+) -> Result<Vec<(String, String)>> {
+    let is_set: HashSet<String> = vars
+        .iter()
+        .filter(|(_, value)| !value.chars().all(is_quote_or_whitespace))
+        .map(|(key, _)| key.to_ascii_uppercase())
+        .collect();
+
+    // Variables read from a file, whose empty counterparts give way to them
+    let from_file: HashSet<String> = vars
+        .iter()
+        .filter_map(|(key, _)| indirect_base(key, options))
+        .map(str::to_ascii_uppercase)
+        .filter(|base| !is_set.contains(base))
+        .collect();
+
+    let mut resolved = Vec::with_capacity(vars.len());
+
+    for (key, value) in vars {
+        match indirect_base(&key, options) {
+            // The variable itself takes precedence over the file
+            Some(base) if is_set.contains(&base.to_ascii_uppercase()) => {}
+            Some(base) => {
+                resolved.push((base.to_owned(), file::read_value(Path::new(&value))?))
+            }
+            None if from_file.contains(&key.to_ascii_uppercase()) => {}
+            None => resolved.push((key, value)),
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// The variable `key` stands in for if it ends in `_FILE`, such as `DB_PASSWORD` for
+/// `DB_PASSWORD_FILE`, see [`read_indirect`]. Without known fields, such as when
+/// reading a map, every variable ending in `_FILE` stands in for another
+fn indirect_base<'k>(key: &'k str, options: &Options) -> Option<&'k str> {
+    let len = key.len().checked_sub(FILE_SUFFIX.len())?;
+
+    let base = (len > 0
+        && key.is_char_boundary(len)
+        && key[len..].eq_ignore_ascii_case(FILE_SUFFIX))
+    .then(|| &key[..len])?;
+
+    (names_field(&field_key(base, options), options).unwrap_or(true)
+        && !names_field(&field_key(key, options), options).unwrap_or(false))
+    .then_some(base)
+}
+
+/// Suffix of the variables holding the path of a file, see [`Builder::file_indirection`]
+const FILE_SUFFIX: &str = "_FILE";
+
+/// Create a [`Builder`] with the default settings
+///
+/// # Example
+///
+/// ```
+/// use renvar::builder;
+///
+/// let builder = builder().capture_rest("extra");
+/// ```
+pub fn builder() -> Builder {
+    Builder::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::builder;
+    use crate::{
+        DuplicateKeyPolicy, EmptyStringPolicy, Error, TrimPolicy, Warning, Warnings,
+    };
+    use serde::Deserialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Passthrough {
+        name: String,
+        port: u16,
+        extra: BTreeMap<String, String>,
+    }
+
+    #[test]
+    fn test_capture_rest() {
+        let vars = vec![
+            (String::from("NAME"), String::from("proxy")),
+            (String::from("Forwarded_Header"), String::from("x-real-ip")),
+            (String::from("PORT"), String::from("8080")),
+            (String::from("EXTRA"), String::from("ignored name clash")),
+        ];
+
+        let actual = builder()
+            .capture_rest("extra")
+            .from_iter::<Passthrough, _>(vars)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            Passthrough {
+                name: String::from("proxy"),
+                port: 8080,
+                extra: BTreeMap::from([
+                    (String::from("EXTRA"), String::from("ignored name clash")),
+                    (String::from("Forwarded_Header"), String::from("x-real-ip")),
+                ])
+            }
+        )
+    }
+
+    #[test]
+    fn test_capture_rest_empty() {
+        let vars = vec![
+            (String::from("name"), String::from("proxy")),
+            (String::from("port"), String::from("8080")),
+        ];
+
+        let actual = builder()
+            .capture_rest("extra")
+            .from_iter::<Passthrough, _>(vars)
+            .unwrap();
+
+        assert!(actual.extra.is_empty())
+    }
+
+    #[test]
+    fn test_reject_control_chars() {
+        let vars = vec![
+            (String::from("name"), String::from("line\nbreak\ttab")),
+            (String::from("port"), String::from("80\u{7}8\u{1b}0")),
+            (String::from("unrelated"), String::from("\0")),
+        ];
+
+        let error = builder()
+            .reject_control_chars(true)
+            .capture_rest("extra")
+            .from_iter::<Passthrough, _>(vars)
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "control characters found in the value of port at byte offsets 2, 4"
+        )
+    }
+
+    #[test]
+    fn test_capture_rest_ignored_for_maps() {
+        let vars = vec![(String::from("NAME"), String::from("proxy"))];
+
+        let actual = builder()
+            .capture_rest("extra")
+            .from_iter::<HashMap<String, String>, _>(vars)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            HashMap::from([(String::from("name"), String::from("proxy"))])
+        )
+    }
+
+    #[test]
+    fn test_duplicate_keys_last_wins() {
+        let vars = vec![
+            (String::from("APP_key"), String::from("first")),
+            (String::from("OTHER"), String::from("value")),
+            (String::from("APP_KEY"), String::from("second")),
+        ];
+
+        let actual = builder()
+            .from_iter::<HashMap<String, String>, _>(vars)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            HashMap::from([
+                (String::from("app_key"), String::from("second")),
+                (String::from("other"), String::from("value")),
+            ])
+        )
+    }
+
+    #[test]
+    fn test_warnings_name_prefixed_variables() {
+        let warnings = Warnings::new();
+        let vars = vec![
+            (String::from("APP_NAME"), String::from("first")),
+            (String::from("APP_name"), String::from("second")),
+        ];
+
+        builder()
+            .prefix("APP_")
+            .duplicate_keys(DuplicateKeyPolicy::FirstWins)
+            .warnings(&warnings)
+            .from_iter::<HashMap<String, String>, _>(vars)
+            .unwrap();
+
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::ShadowedKey {
+                kept: String::from("APP_NAME"),
+                shadowed: String::from("APP_name"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_map_values_sees_prefixed_keys() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Credentials {
+            user: String,
+            password: String,
+        }
+
+        let vars = vec![
+            (String::from("APP_USER"), String::from("admin")),
+            (String::from("APP_PASSWORD"), String::from("hunter2")),
+        ];
+
+        let credentials: Credentials = builder()
+            .prefix("APP_")
+            .map_values(|key, value| match key {
+                "APP_PASSWORD" => value.chars().rev().collect(),
+                _ => value.to_owned(),
+            })
+            .from_iter(vars)
+            .unwrap();
+
+        assert_eq!(
+            credentials,
+            Credentials {
+                user: String::from("admin"),
+                password: String::from("2retnuh"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty_strings() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Empty {
+            name: String,
+            alias: Option<String>,
+            nick: Option<Option<String>>,
+            #[serde(default)]
+            port: Option<u16>,
+        }
+
+        let vars = vec![
+            (String::from("NAME"), String::new()),
+            (String::from("ALIAS"), String::new()),
+            (String::from("NICK"), String::new()),
+        ];
+
+        assert_eq!(
+            builder()
+                .from_iter::<Empty, _>(vars.clone())
+                .unwrap(),
+            Empty {
+                name: String::new(),
+                alias: None,
+                nick: Some(None),
+                port: None,
+            }
+        );
+        assert_eq!(
+            builder()
+                .empty_strings(EmptyStringPolicy::TreatAsEmpty)
+                .from_iter::<Empty, _>(vars.clone())
+                .unwrap(),
+            Empty {
+                name: String::new(),
+                alias: Some(String::new()),
+                nick: Some(Some(String::new())),
+                port: None,
+            }
+        );
+        assert_eq!(
+            builder()
+                .prefix("APP_")
+                .empty_strings(EmptyStringPolicy::Error)
+                .from_iter::<Empty, _>(vec![(
+                    String::from("APP_ALIAS"),
+                    String::new()
+                )]),
+            Err(Error::EmptyValue(String::from("APP_ALIAS")))
+        );
+    }
+
+    #[test]
+    fn test_trim_policies() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Padded {
+            indent: String,
+            quoted: String,
+        }
+
+        let vars = vec![
+            (String::from(" 'INDENT' "), String::from("    ")),
+            (String::from("QUOTED"), String::from("\"'a'\"")),
+        ];
+
+        assert_eq!(
+            builder()
+                .trim_values(TrimPolicy::None)
+                .from_iter::<Padded, _>(vars.clone())
+                .unwrap(),
+            Padded {
+                indent: String::from("    "),
+                quoted: String::from("\"'a'\""),
+            }
+        );
+        assert_eq!(
+            builder()
+                .trim_keys(TrimPolicy::Whitespace)
+                .trim_values(TrimPolicy::Unquote)
+                .from_iter::<Padded, _>(vars)
+                .map_err(|error| error.to_string()),
+            Err(String::from(
+                "missing `indent`; found similar variable `'INDENT'`"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_exact_keys() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        #[serde(rename_all = "PascalCase")]
+        struct Http {
+            max_body: u32,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            #[serde(rename = "HTTPPort")]
+            port: u16,
+            #[serde(rename = "Http")]
+            http: Http,
+            debug: Option<bool>,
+        }
+
+        let vars = vec![
+            (String::from("APP_HTTPPort"), String::from("8080")),
+            (String::from("APP_Http_MaxBody"), String::from("1024")),
+            (String::from("APP_DEBUG"), String::from("true")),
+        ];
+
+        assert_eq!(
+            builder()
+                .prefix("APP_")
+                .exact_keys(true)
+                .from_iter::<App, _>(vars.clone()),
+            Ok(App {
+                port: 8080,
+                http: Http { max_body: 1024 },
+                debug: None,
+            })
+        );
+        assert_eq!(
+            builder().prefix("APP_").from_iter::<App, _>(vars),
+            Err(Error::MissingValue(String::from("HTTPPort")))
+        );
+    }
+
+    #[test]
+    fn test_scope() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Redis {
+            host: String,
+            port: u16,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            redis: Redis,
+        }
+
+        let vars = vec![
+            (String::from("REDIS"), String::from("ignored")),
+            (String::from("CACHE_HOST"), String::from("localhost")),
+        ];
+
+        assert_eq!(
+            builder()
+                .scope("redis", "CACHE_")
+                .from_iter::<App, _>(vars.clone()),
+            Err(Error::MissingValue(String::from("cache_port")))
+        );
+
+        let vars = [
+            vars,
+            vec![(String::from("CACHE_PORT"), String::from("6379"))],
+        ]
+        .concat();
+
+        assert_eq!(
+            builder()
+                .scope("redis", "CACHE_")
+                .from_iter::<App, _>(vars),
+            Ok(App {
+                redis: Redis {
+                    host: String::from("localhost"),
+                    port: 6379,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_on_unused_key() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Database {
+            host: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            name: String,
+            db: Database,
+        }
+
+        let vars = vec![
+            (String::from("APP_NAME"), String::from("api")),
+            (String::from("APP_DB_HOST"), String::from("localhost")),
+            (String::from("APP_DB_PROT"), String::from("5432")),
+            (String::from("APP_DEBUG"), String::from("true")),
+            (String::from("PATH"), String::from("/usr/bin")),
+        ];
+
+        let unused = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&unused);
+
+        let app: App = builder()
+            .prefix("APP_")
+            .on_unused_key(move |key, value| {
+                collected
+                    .lock()
+                    .unwrap()
+                    .push((key.to_owned(), value.to_owned()))
+            })
+            .from_iter(vars)
+            .unwrap();
+
+        assert_eq!(app.db.host, "localhost");
+        assert_eq!(
+            *unused.lock().unwrap(),
+            vec![
+                (String::from("APP_DEBUG"), String::from("true")),
+                (String::from("APP_DB_PROT"), String::from("5432")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_indirection() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Database {
+            user: String,
+            password: String,
+        }
+
+        let path = std::env::temp_dir().join("renvar_test_file_indirection");
+        std::fs::write(&path, "from-file\n").unwrap();
+        let path = path.to_str().unwrap().to_owned();
+
+        let vars = vec![
+            (String::from("DB_USER"), String::from("admin")),
+            (String::from("DB_USER_FILE"), String::from("/nonexistent")),
+            (String::from("DB_PASSWORD_FILE"), path.clone()),
+        ];
+
+        assert_eq!(
+            builder()
+                .prefix("DB_")
+                .file_indirection(true)
+                .deny_unknown(true)
+                .from_iter::<Database, _>(vars),
+            Ok(Database {
+                user: String::from("admin"),
+                password: String::from("from-file"),
+            })
+        );
+
+        let vars = vec![
+            (String::from("DB_USER"), String::from("admin")),
+            (
+                String::from("DB_PASSWORD_FILE"),
+                String::from("/nonexistent"),
+            ),
+        ];
+
+        assert!(matches!(
+            builder()
+                .prefix("DB_")
+                .file_indirection(true)
+                .from_iter::<Database, _>(vars),
+            Err(Error::Io { .. })
+        ));
+
+        // An empty variable gives way to the file, wherever it comes
+        let vars = vec![
+            (String::from("DB_USER"), String::from("admin")),
+            (String::from("DB_PASSWORD_FILE"), path.clone()),
+            (String::from("DB_PASSWORD"), String::new()),
+        ];
+
+        assert_eq!(
+            builder()
+                .prefix("DB_")
+                .file_indirection(true)
+                .duplicate_keys(DuplicateKeyPolicy::Error)
+                .from_iter::<Database, _>(vars),
+            Ok(Database {
+                user: String::from("admin"),
+                password: String::from("from-file"),
+            })
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_interpolate_only_read_variables() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Database {
+            url: String,
+        }
+
+        // Unrelated variables can be referenced, but aren't expanded themselves
+        let vars = vec![
+            (String::from("OTHER"), String::from("${")),
+            (String::from("HOST"), String::from("localhost")),
+            (
+                String::from("APP_URL"),
+                String::from("postgres://${HOST}/app"),
+            ),
+        ];
+
+        assert_eq!(
+            builder()
+                .prefix("APP_")
+                .interpolate(true)
+                .from_iter::<Database, _>(vars),
+            Ok(Database {
+                url: String::from("postgres://localhost/app")
+            })
+        );
+    }
+
+    #[test]
+    fn test_file_indirection_of_unknown_variables() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Logging {
+            log_file: String,
+            level: String,
+        }
+
+        let vars = vec![
+            (String::from("LOG_FILE"), String::from("/var/log/app.log")),
+            (String::from("LEVEL"), String::from("info")),
+            (String::from("OTHER_FILE"), String::from("/nonexistent")),
+        ];
+
+        assert_eq!(
+            builder()
+                .file_indirection(true)
+                .from_iter::<Logging, _>(vars),
+            Ok(Logging {
+                log_file: String::from("/var/log/app.log"),
+                level: String::from("info"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_optional_section() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Sentry {
+            dsn: String,
+            environment: Option<String>,
+        }
+
+        let builder = builder().prefix("SENTRY_");
+        let vars = |vars: &[(&str, &str)]| {
+            vars.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            builder.from_iter::<Option<Sentry>, _>(vars(&[("PATH", "/usr/bin")])),
+            Ok(None)
+        );
+        assert_eq!(
+            builder.from_iter::<Option<Sentry>, _>(vars(&[
+                ("PATH", "/usr/bin"),
+                ("SENTRY_DSN", "https://key@sentry.io/1"),
+            ])),
+            Ok(Some(Sentry {
+                dsn: String::from("https://key@sentry.io/1"),
+                environment: None,
+            }))
+        );
+
+        // Variables with the prefix that aren't fields don't set the section
+        assert_eq!(
+            builder
+                .from_iter::<Option<Sentry>, _>(vars(&[("SENTRY_RELEASE", "1.0.0")])),
+            Ok(None)
+        );
+
+        // A section that's only partly set is still an error
+        assert!(builder
+            .from_iter::<Option<Sentry>, _>(vars(&[("SENTRY_ENVIRONMENT", "prod")]))
+            .is_err());
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Tracing {
+            sampler: Sampler,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Sampler {
+            ratio: String,
+        }
+
+        // The variables of nested fields do
+        assert_eq!(
+            builder.from_iter::<Option<Tracing>, _>(vars(&[(
+                "SENTRY_SAMPLER_RATIO",
+                "0.5"
+            )])),
+            Ok(Some(Tracing {
+                sampler: Sampler {
+                    ratio: String::from("0.5"),
+                },
+            }))
+        );
+    }
+
+    #[test]
+    fn test_alias() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            database_url: String,
+            port: u16,
+        }
+
+        let legacy = || {
+            builder()
+                .prefix("APP_")
+                .alias("database_url", ["DB_URL", "DATABASE_URI"])
+                .alias("port", ["APP_HTTP_PORT"])
+        };
+        let warnings = Warnings::new();
+
+        let vars = vec![
+            (String::from("DATABASE_URI"), String::from("postgres://uri")),
+            (String::from("DB_URL"), String::from("postgres://url")),
+            (String::from("APP_PORT"), String::from("8080")),
+            (String::from("APP_HTTP_PORT"), String::from("80")),
+        ];
+
+        assert_eq!(
+            legacy()
+                .warnings(&warnings)
+                .from_iter::<App, _>(vars)
+                .unwrap(),
+            App {
+                database_url: String::from("postgres://url"),
+                port: 8080,
+            }
+        );
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::DeprecatedKey {
+                key: String::from("DB_URL"),
+                replacement: Some(String::from("APP_DATABASE_URL")),
+                removal: None,
+            }]
+        );
+
+        let vars = vec![
+            (
+                String::from("APP_DATABASE_URL"),
+                String::from("postgres://app"),
+            ),
+            (String::from("APP_HTTP_PORT"), String::from("80")),
+        ];
+
+        assert_eq!(
+            legacy()
+                .warnings(&warnings)
+                .from_iter::<App, _>(vars)
+                .unwrap(),
+            App {
+                database_url: String::from("postgres://app"),
+                port: 80,
+            }
+        );
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::DeprecatedKey {
+                key: String::from("APP_HTTP_PORT"),
+                replacement: Some(String::from("APP_PORT")),
+                removal: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_deprecate() {
+        use crate::Deprecation;
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            workers: Option<u16>,
+            threads: Option<u16>,
+        }
+
+        let warnings = Warnings::new();
+        let deprecated = || {
+            builder()
+                .deprecate("threads", Deprecation::new().replacement("workers"))
+                .deprecate("workers", Deprecation::new().removal("3.0"))
+                .warnings(&warnings)
+        };
+
+        let app: App = deprecated()
+            .from_iter(vec![(String::from("THREADS"), String::from("4"))])
+            .unwrap();
+
+        assert_eq!(app.threads, Some(4));
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::DeprecatedKey {
+                key: String::from("THREADS"),
+                replacement: Some(String::from("WORKERS")),
+                removal: None,
+            }]
+        );
+
+        let _: App = deprecated().from_iter(Vec::new()).unwrap();
+
+        assert!(warnings.is_empty());
+
+        let _: App = deprecated()
+            .from_iter(vec![(String::from("WORKERS"), String::from("4"))])
+            .unwrap();
+
+        assert_eq!(
+            warnings
+                .take()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![String::from(
+                "environment variable WORKERS is deprecated, it will be removed in 3.0"
+            )]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_from_os_env_lossy() {
+        use crate::testing::with_vars;
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            name: String,
+        }
+
+        let warnings = Warnings::new();
+        let app = with_vars(
+            [("RENVAR_LOSSY_NAME", OsStr::from_bytes(b"a\xffb"))],
+            || {
+                builder()
+                    .prefix("RENVAR_LOSSY_")
+                    .warnings(&warnings)
+                    .from_os_env_lossy::<App>()
+            },
+        );
+
+        assert_eq!(
+            app.unwrap(),
+            App {
+                name: String::from("a\u{fffd}b")
+            }
+        );
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::LossyConversion {
+                key: String::from("RENVAR_LOSSY_NAME")
+            }]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! A number of bytes written with a unit, for memory limits and buffer sizes
+
+use crate::Error;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Decimal and binary units, matched ignoring case,
+/// with the `B` of bytes being optional after them
+const UNITS: &[(&str, u64)] = &[
+    ("", 1),
+    ("k", 1_000),
+    ("m", 1_000_000),
+    ("g", 1_000_000_000),
+    ("t", 1_000_000_000_000),
+    ("p", 1_000_000_000_000_000),
+    ("e", 1_000_000_000_000_000_000),
+    ("ki", 1 << 10),
+    ("mi", 1 << 20),
+    ("gi", 1 << 30),
+    ("ti", 1 << 40),
+    ("pi", 1 << 50),
+    ("ei", 1 << 60),
+];
+
+/// Units [`ByteSize`] is displayed with, the largest one first
+const DISPLAY_UNITS: &[(&str, u64)] = &[
+    ("EiB", 1 << 60),
+    ("PiB", 1 << 50),
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("EB", 1_000_000_000_000_000_000),
+    ("PB", 1_000_000_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("kB", 1_000),
+];
+
+/// A number of bytes, deserialized from a number followed by a unit,
+/// such as `512MB`, `2GiB`, `1.5 GB` or `4096`. Units are decimal, such
+/// as `kB` for 1000 bytes, or binary, such as `KiB` for 1024 bytes,
+/// matched ignoring case and with an optional `B`, so `512m` and `512M` are
+/// both 512 000 000 bytes, and `512Mi` is 512 × 2²⁰ bytes.
+///
+/// It's displayed and serialized with the largest unit that divides it exactly,
+/// binary ones first, such as `512MiB` or `1500B`, which parses back to the same size.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, ByteSize};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     memory_limit: ByteSize,
+///     buffer_size: ByteSize,
+/// }
+///
+/// let vars = vec![
+///     ("MEMORY_LIMIT".to_owned(), "2GiB".to_owned()),
+///     ("BUFFER_SIZE".to_owned(), "64kB".to_owned()),
+/// ];
+///
+/// let custom_struct: CustomStruct = from_iter(vars).unwrap();
+///
+/// assert_eq!(custom_struct.memory_limit.as_u64(), 2 * 1024 * 1024 * 1024);
+/// assert_eq!(custom_struct.buffer_size, ByteSize(64_000));
+/// assert_eq!(custom_struct.memory_limit.to_string(), "2GiB");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// The number of bytes
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Parse `value` into a number of bytes, returning why it can't be
+    fn parse(value: &str) -> Result<u64, String> {
+        let value = value.trim();
+        let number = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let unit = value[number.len()..].to_ascii_lowercase();
+        let unit = unit.strip_suffix('b').unwrap_or(&unit);
+
+        let multiplier = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, multiplier)| u128::from(*multiplier))
+            .ok_or_else(|| format!("unknown unit `{}`", &value[number.len()..]))?;
+
+        let number = number.trim_end();
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+            || fraction.len() > 18
+        {
+            return Err(String::from("expected a number of bytes"));
+        }
+
+        let parse = |digits: &str| match digits {
+            "" => Ok(0),
+            digits => digits.parse::<u128>(),
+        };
+        let too_large = || String::from("number too large to fit in target type");
+
+        let whole = parse(whole).map_err(|_| too_large())?;
+        let fraction = parse(fraction).map_err(|_| too_large())? * multiplier
+            / 10u128.pow(fraction.len() as u32);
+
+        whole
+            .checked_mul(multiplier)
+            .and_then(|bytes| u64::try_from(bytes + fraction).ok())
+            .ok_or_else(too_large)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+            .map(ByteSize)
+            .map_err(|reason| {
+                Error::Custom(format!("{} while parsing value '{}'", reason, value))
+            })
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match DISPLAY_UNITS
+            .iter()
+            .find(|(_, multiplier)| self.0 != 0 && self.0.is_multiple_of(*multiplier))
+        {
+            Some((unit, multiplier)) => write!(fmt, "{}{}", self.0 / multiplier, unit),
+            None => write!(fmt, "{}B", self.0),
+        }
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        ByteSize(bytes)
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ByteSizeVisitor;
+
+        impl de::Visitor<'_> for ByteSizeVisitor {
+            type Value = ByteSize;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt.write_str("a number of bytes, optionally followed by a unit")
+            }
+
+            fn visit_u64<E>(self, bytes: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(ByteSize(bytes))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByteSize;
+    use crate::{from_iter, to_vec, Error};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+    struct Limits {
+        memory: ByteSize,
+        buffer: ByteSize,
+    }
+
+    #[test]
+    fn test_byte_size() {
+        for (value, bytes) in [
+            ("4096", 4096),
+            ("512MB", 512_000_000),
+            ("512m", 512_000_000),
+            ("2GiB", 2 << 30),
+            ("2gi", 2 << 30),
+            ("1.5 GB", 1_500_000_000),
+            ("0.5KiB", 512),
+            ("10B", 10),
+            ("15EiB", 15 << 60),
+        ] {
+            assert_eq!(value.parse::<ByteSize>().map(ByteSize::as_u64), Ok(bytes));
+        }
+
+        assert_eq!(
+            "8XB".parse::<ByteSize>(),
+            Err(Error::Custom(String::from(
+                "unknown unit `XB` while parsing value '8XB'"
+            )))
+        );
+        assert!("GB".parse::<ByteSize>().is_err());
+        assert!("1.2.3MB".parse::<ByteSize>().is_err());
+        assert!("16EiB".parse::<ByteSize>().is_err());
+
+        let limits = Limits {
+            memory: ByteSize(512 << 20),
+            buffer: ByteSize(1500),
+        };
+        let vars = to_vec(&limits).unwrap();
+
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("MEMORY"), String::from("512MiB")),
+                (String::from("BUFFER"), String::from("1500B")),
+            ]
+        );
+        assert_eq!(from_iter::<Limits, _>(vars).unwrap(), limits);
+    }
+}
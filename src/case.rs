@@ -0,0 +1,217 @@
+//! Naming conventions for the keys of environment variables
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Naming convention of the keys of environment variables, shared by
+/// the deserializer ([`crate::Builder::key_case`]) and the serializer
+/// ([`crate::ser::EnvVarSerializer::with_key_case`], [`crate::Exporter::key_case`]),
+/// so that a single setting changes the convention in both directions.
+///
+/// Struct fields are expected to be in snake_case, as is the Rust convention,
+/// unless told otherwise with [`crate::Builder::field_case`].
+///
+/// # Example
+///
+/// ```
+/// use renvar::KeyCase;
+///
+/// assert_eq!(KeyCase::ScreamingSnake.to_key("max_connections"), "MAX_CONNECTIONS");
+/// assert_eq!(KeyCase::Kebab.to_key("max_connections"), "max-connections");
+/// assert_eq!(KeyCase::Camel.to_key("max_connections"), "maxConnections");
+///
+/// assert_eq!(KeyCase::Camel.to_field("maxConnections"), "max_connections");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyCase {
+    /// `MAX_CONNECTIONS`, the usual convention for environment variables
+    #[default]
+    ScreamingSnake,
+
+    /// `max_connections`
+    Snake,
+
+    /// `max-connections`
+    Kebab,
+
+    /// `MAX-CONNECTIONS`
+    ScreamingKebab,
+
+    /// `maxConnections`
+    Camel,
+
+    /// `MaxConnections`
+    Pascal,
+}
+
+impl KeyCase {
+    /// Write the name of a field as a key in this case
+    pub fn to_key(&self, field: &str) -> String {
+        let snake = snake(field);
+
+        match self {
+            KeyCase::ScreamingSnake => snake.to_uppercase(),
+            KeyCase::Snake => snake,
+            KeyCase::Kebab => snake.replace('_', "-"),
+            KeyCase::ScreamingKebab => snake.replace('_', "-").to_uppercase(),
+            KeyCase::Camel | KeyCase::Pascal => {
+                let mut key = String::with_capacity(snake.len());
+
+                for (i, word) in snake
+                    .split('_')
+                    .filter(|w| !w.is_empty())
+                    .enumerate()
+                {
+                    if i == 0 && *self == KeyCase::Camel {
+                        key.push_str(word);
+                    } else {
+                        key.push_str(&capitalize(word));
+                    }
+                }
+
+                key
+            }
+        }
+    }
+
+    /// Turn a key written in this case back into the
+    /// snake_case name of the field it belongs to
+    pub fn to_field(&self, key: &str) -> String {
+        match self {
+            KeyCase::ScreamingSnake | KeyCase::Snake => key.to_lowercase(),
+            KeyCase::Kebab | KeyCase::ScreamingKebab => {
+                key.to_lowercase().replace('-', "_")
+            }
+            KeyCase::Camel | KeyCase::Pascal => snake(key),
+        }
+    }
+
+    /// Whether [`KeyCase::to_field`] would return `key` as it is,
+    /// checked without allocating. Only ASCII keys are considered.
+    pub(crate) fn is_field(&self, key: &str) -> bool {
+        key.is_ascii()
+            && !key.bytes().any(|b| b.is_ascii_uppercase())
+            && (matches!(self, KeyCase::ScreamingSnake | KeyCase::Snake)
+                || !key.contains('-'))
+    }
+}
+
+/// Convert an identifier to snake_case, by lowercasing it, replacing dashes
+/// with underscores and inserting an underscore wherever a lowercase letter
+/// is followed by an uppercase one. Existing underscores are kept as they are.
+fn snake(ident: &str) -> String {
+    let mut snake = String::with_capacity(ident.len());
+    let mut prev: Option<char> = None;
+
+    for c in ident.chars() {
+        if c.is_uppercase() && prev.is_some_and(char::is_lowercase) {
+            snake.push('_');
+        }
+
+        match c {
+            '-' => snake.push('_'),
+            c => snake.extend(c.to_lowercase()),
+        }
+
+        prev = Some(c);
+    }
+
+    snake
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCase;
+    use crate::{builder, exporter};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    struct Pool {
+        max_connections: u32,
+        idle_timeout: u64,
+    }
+
+    #[test]
+    fn test_key_case_round_trip() {
+        let pool = Pool {
+            max_connections: 16,
+            idle_timeout: 30,
+        };
+
+        for case in [
+            KeyCase::ScreamingSnake,
+            KeyCase::Snake,
+            KeyCase::Kebab,
+            KeyCase::ScreamingKebab,
+            KeyCase::Camel,
+            KeyCase::Pascal,
+        ] {
+            let vars = exporter().key_case(case).to_vec(&pool).unwrap();
+
+            assert_eq!(vars[0].0, case.to_key("max_connections"));
+            assert_eq!(
+                builder()
+                    .key_case(case)
+                    .from_iter::<Pool, _>(vars)
+                    .unwrap(),
+                pool
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_key() {
+        assert_eq!(
+            KeyCase::ScreamingKebab.to_key("idle_timeout"),
+            "IDLE-TIMEOUT"
+        );
+        assert_eq!(KeyCase::Pascal.to_key("idle_timeout"), "IdleTimeout");
+        assert_eq!(KeyCase::Snake.to_key("idleTimeout"), "idle_timeout");
+        assert_eq!(KeyCase::Pascal.to_field("IdleTimeout"), "idle_timeout");
+    }
+
+    #[test]
+    fn test_is_field() {
+        for (case, key) in [
+            (KeyCase::ScreamingSnake, "max_connections"),
+            (KeyCase::Snake, "max-connections"),
+            (KeyCase::Kebab, "MAX-CONNECTIONS"),
+            (KeyCase::Camel, "maxConnections"),
+            (KeyCase::Pascal, "Ünicode"),
+        ] {
+            assert_eq!(case.is_field(key), case.to_field(key) == key, "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn test_field_case() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+        #[serde(rename_all = "kebab-case")]
+        struct Renamed {
+            max_connections: u32,
+        }
+
+        let renamed = Renamed {
+            max_connections: 16,
+        };
+
+        let vars = exporter().to_vec(&renamed).unwrap();
+
+        assert_eq!(vars[0].0, "MAX_CONNECTIONS");
+        assert_eq!(
+            builder()
+                .field_case(KeyCase::Kebab)
+                .from_iter::<Renamed, _>(vars)
+                .unwrap(),
+            renamed
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{convert::maybe_invalid_unicode_vars_os, from_iter, Result};
 use serde::de;
-use std::env;
+use std::{collections::HashMap, env};
 
 /// Deserialize environment variables with postfixes.
 /// To create an instance of [`CaseInsensitivePostfixed`], you can use the [`case_insensitive_postfixed`] function:
@@ -130,6 +130,9 @@ impl<'a> CaseInsensitivePostfixed<'a> {
     /// Deserialize some type `T` from an iterator `Iter` that is an iterator over key-value pairs,
     /// filtering only the pairs where the key ends with the specified postfix.
     ///
+    /// Only the matching ignores case, the keys themselves are left as they are,
+    /// so errors name the variables exactly as they were given.
+    ///
     /// # Errors
     ///
     /// Any errors that might occur during deserialization
@@ -176,21 +179,21 @@ impl<'a> CaseInsensitivePostfixed<'a> {
         T: de::DeserializeOwned,
         Iter: IntoIterator<Item = (String, String)>,
     {
-        from_iter(iter.into_iter().filter_map(|(key, value)| {
-            let (lowercase_postfix, lowercase_key) =
-                (self.0.to_lowercase(), key.to_lowercase());
-
-            if lowercase_key.ends_with(&lowercase_postfix) {
-                Some((
-                    lowercase_key
-                        .trim_end_matches(&lowercase_postfix)
-                        .to_owned(),
-                    value,
-                ))
-            } else {
-                None
-            }
-        }))
+        let mut originals = HashMap::new();
+
+        let vars: Vec<(String, String)> = iter
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let stripped = strip_suffix_ignore_case(&key, self.0)?.to_owned();
+                originals.entry(stripped.clone()).or_insert(key);
+
+                Some((stripped, value))
+            })
+            .collect();
+
+        from_iter(vars).map_err(|error| {
+            error.map_keys(|key| originals.get(&key).cloned().unwrap_or(key))
+        })
     }
 
     /// Retrieve the postfix specified at the time
@@ -205,7 +208,7 @@ impl<'a> CaseInsensitivePostfixed<'a> {
 /// struct by calling [`case_insensitive_postfixed`].
 ///
 /// As the name suggests, the casing of the keys for the environment variables
-/// does not matter (everything will be lowercased)
+/// does not matter, they're matched with the fields ignoring case
 ///
 /// # Example
 ///
@@ -220,10 +223,38 @@ pub fn case_insensitive_postfixed(postfix: &str) -> CaseInsensitivePostfixed<'_>
     CaseInsensitivePostfixed(postfix)
 }
 
+/// Strip `postfix` from `key` ignoring case, leaving the rest of `key` untouched.
+///
+/// Case is ignored following the full case folding of Unicode, the same way
+/// as with [`crate::case_insensitive_prefixed`]. Keys ending in as many
+/// ASCII characters as `postfix` has are compared without folding.
+fn strip_suffix_ignore_case<'k>(key: &'k str, postfix: &str) -> Option<&'k str> {
+    if let Some(start) = key.len().checked_sub(postfix.len()) {
+        if let Some(tail) = key
+            .get(start..)
+            .filter(|tail| tail.is_ascii() && postfix.is_ascii())
+        {
+            return tail
+                .eq_ignore_ascii_case(postfix)
+                .then(|| &key[..start]);
+        }
+    }
+
+    // Every character folds into at most three, so longer tails can't match
+    let max = postfix.chars().count() * 3;
+
+    std::iter::once(key.len())
+        .chain(key.char_indices().rev().map(|(i, _)| i))
+        .take(max + 1)
+        .find(|&i| unicase::eq(&key[i..], postfix))
+        .map(|i| &key[..i])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::testing::with_vars;
     use serde::Deserialize;
 
     #[derive(Debug, Deserialize, PartialEq, Eq)]
@@ -233,10 +264,11 @@ mod tests {
 
     #[test]
     fn test_case_insensitive_postfixed() {
-        env::set_var("KEY_APP", "value");
-        let postfixed = case_insensitive_postfixed("_app")
-            .from_env::<Test>()
-            .unwrap();
+        let postfixed = with_vars([("KEY_APP", "value")], || {
+            case_insensitive_postfixed("_app")
+                .from_env::<Test>()
+                .unwrap()
+        });
 
         assert_eq!(
             postfixed,
@@ -245,4 +277,42 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_strip_suffix_ignore_case() {
+        assert_eq!(strip_suffix_ignore_case("KEY_App", "_app"), Some("KEY"));
+        assert_eq!(strip_suffix_ignore_case("PP", "_app"), None);
+        assert_eq!(strip_suffix_ignore_case("KEY", ""), Some("KEY"));
+        assert_eq!(
+            strip_suffix_ignore_case("KEY_STRASSE", "_straße"),
+            Some("KEY")
+        );
+        assert_eq!(strip_suffix_ignore_case("X_\u{212A}EY", "_key"), Some("X"));
+        assert_eq!(
+            strip_suffix_ignore_case("KEY_config_i\u{307}", "_CONFIG_İ"),
+            Some("KEY")
+        );
+        assert_eq!(strip_suffix_ignore_case("KEY_CONFIG_I", "_CONFIG_İ"), None);
+    }
+
+    #[test]
+    fn test_case_insensitive_postfixed_error_keeps_key() {
+        #[derive(Debug, Deserialize)]
+        struct Port {
+            #[allow(dead_code)]
+            port: u16,
+        }
+
+        let vars = vec![(String::from("PORTT_App"), String::from("8080"))];
+
+        assert_eq!(
+            case_insensitive_postfixed("_app")
+                .from_iter::<Port, _>(vars)
+                .unwrap_err(),
+            crate::Error::SimilarKey {
+                field: String::from("port"),
+                similar: String::from("PORTT_App"),
+            }
+        );
+    }
 }
@@ -1,7 +1,7 @@
 use crate::convert::maybe_invalid_unicode_vars_os;
 use crate::{from_iter, Result};
 use serde::de;
-use std::{env, string::String};
+use std::{collections::HashMap, env, string::String};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -129,6 +129,9 @@ impl<'a> CaseInsensitivePrefixed<'a> {
     /// Deserialize some type `T` from an iterator `Iter` that is an iterator over key-value pairs,
     /// filtering only the pairs where the key ends with the specified prefix.
     ///
+    /// Only the matching ignores case, the keys themselves are left as they are,
+    /// so errors name the variables exactly as they were given.
+    ///
     /// # Errors
     ///
     /// Any errors that might occur during deserialization
@@ -169,21 +172,21 @@ impl<'a> CaseInsensitivePrefixed<'a> {
         T: de::DeserializeOwned,
         Iter: IntoIterator<Item = (String, String)>,
     {
-        from_iter(iter.into_iter().filter_map(|(k, v)| {
-            let lowercase_prefix = self.0.to_lowercase();
-            let lowercase_env_key = k.to_lowercase();
-
-            if lowercase_env_key.starts_with(&lowercase_prefix) {
-                Some((
-                    lowercase_env_key
-                        .trim_start_matches(&lowercase_prefix)
-                        .to_owned(),
-                    v,
-                ))
-            } else {
-                None
-            }
-        }))
+        let mut originals = HashMap::new();
+
+        let vars: Vec<(String, String)> = iter
+            .into_iter()
+            .filter_map(|(k, v)| {
+                let stripped = strip_prefix_ignore_case(&k, self.0)?.to_owned();
+                originals.entry(stripped.clone()).or_insert(k);
+
+                Some((stripped, v))
+            })
+            .collect();
+
+        from_iter(vars).map_err(|error| {
+            error.map_keys(|key| originals.get(&key).cloned().unwrap_or(key))
+        })
     }
 
     /// Retrieve the prefix specified at the time
@@ -198,7 +201,7 @@ impl<'a> CaseInsensitivePrefixed<'a> {
 /// struct by calling [`case_insensitive_prefixed`].
 ///
 /// As the name suggests, the casing of the keys for the environment variables
-/// does not matter (everything will be lowercased)
+/// does not matter, they're matched with the fields ignoring case
 ///
 /// # Example
 ///
@@ -213,12 +216,39 @@ pub fn case_insensitive_prefixed(prefix: &str) -> CaseInsensitivePrefixed<'_> {
     CaseInsensitivePrefixed(prefix)
 }
 
+/// Strip `prefix` from `key` ignoring case, leaving the rest of `key` untouched.
+///
+/// Case is ignored following the full case folding of Unicode, which doesn't depend
+/// on the locale: `ß` matches `SS`, and the Turkish `İ` matches `i̇`, the `i` followed
+/// by a combining dot it lowercases to, but neither `I` nor `i`. Keys starting with
+/// as many ASCII characters as `prefix` has are compared without folding.
+fn strip_prefix_ignore_case<'k>(key: &'k str, prefix: &str) -> Option<&'k str> {
+    if let Some(head) = key
+        .get(..prefix.len())
+        .filter(|head| head.is_ascii() && prefix.is_ascii())
+    {
+        return head
+            .eq_ignore_ascii_case(prefix)
+            .then(|| &key[prefix.len()..]);
+    }
+
+    // Every character folds into at most three, so longer heads can't match
+    let max = prefix.chars().count() * 3;
+
+    key.char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(key.len()))
+        .take(max + 1)
+        .find(|&i| unicase::eq(&key[..i], prefix))
+        .map(|i| &key[i..])
+}
+
 #[cfg(test)]
 mod test_case_insensitive_prefixed {
 
-    use super::case_insensitive_prefixed;
+    use super::{case_insensitive_prefixed, strip_prefix_ignore_case};
+    use crate::testing::with_vars;
     use serde::Deserialize;
-    use std::env;
 
     #[derive(Debug, Deserialize, PartialEq, Eq)]
     struct Test {
@@ -227,10 +257,11 @@ mod test_case_insensitive_prefixed {
 
     #[test]
     fn test_case_insensitive_prefixed() {
-        env::set_var("APP_KEY", "value");
-        let prefixed = case_insensitive_prefixed("app_")
-            .from_env::<Test>()
-            .unwrap();
+        let prefixed = with_vars([("APP_KEY", "value")], || {
+            case_insensitive_prefixed("app_")
+                .from_env::<Test>()
+                .unwrap()
+        });
 
         assert_eq!(
             prefixed,
@@ -239,4 +270,44 @@ mod test_case_insensitive_prefixed {
             }
         )
     }
+
+    #[test]
+    fn test_strip_prefix_ignore_case() {
+        assert_eq!(strip_prefix_ignore_case("App_KEY", "app_"), Some("KEY"));
+        assert_eq!(strip_prefix_ignore_case("AP", "app_"), None);
+        assert_eq!(strip_prefix_ignore_case("KEY", ""), Some("KEY"));
+        assert_eq!(strip_prefix_ignore_case("ÄPP_KEY", "äpp_"), Some("KEY"));
+        assert_eq!(
+            strip_prefix_ignore_case("STRASSE_KEY", "straße_"),
+            Some("KEY")
+        );
+        assert_eq!(strip_prefix_ignore_case("\u{212A}EY_X", "key_"), Some("X"));
+        assert_eq!(
+            strip_prefix_ignore_case("config_i\u{307}_KEY", "CONFIG_İ_"),
+            Some("KEY")
+        );
+        assert_eq!(strip_prefix_ignore_case("CONFIG_I_KEY", "CONFIG_İ_"), None);
+        assert_eq!(strip_prefix_ignore_case("config_i_KEY", "CONFIG_İ_"), None);
+    }
+
+    #[test]
+    fn test_case_insensitive_prefixed_error_keeps_key() {
+        #[derive(Debug, Deserialize)]
+        struct Port {
+            #[allow(dead_code)]
+            port: u16,
+        }
+
+        let vars = vec![(String::from("App_PORTT"), String::from("8080"))];
+
+        assert_eq!(
+            case_insensitive_prefixed("app_")
+                .from_iter::<Port, _>(vars)
+                .unwrap_err(),
+            crate::Error::SimilarKey {
+                field: String::from("port"),
+                similar: String::from("App_PORTT"),
+            }
+        );
+    }
 }
@@ -0,0 +1,685 @@
+//! Times of day, timestamps and cron schedules, for scheduling-related variables
+//! such as `BACKUP_AT=03:00` or `BACKUP_CRON="0 3 * * *"`
+
+use crate::{file, log_filter::deserialize_from_str, Error};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A time of day, written as `HH:MM` or `HH:MM:SS` on a 24-hour clock, such as `03:00`
+/// or `23:59:30`. It's displayed and serialized as `HH:MM:SS`.
+///
+/// With the `time` feature, it converts into `time::Time`.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, TimeOfDay};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     backup_at: TimeOfDay,
+/// }
+///
+/// let vars = vec![("BACKUP_AT".to_owned(), "03:30".to_owned())];
+///
+/// let custom_struct: CustomStruct = from_iter(vars).unwrap();
+///
+/// assert_eq!(custom_struct.backup_at.hour(), 3);
+/// assert_eq!(custom_struct.backup_at.minute(), 30);
+/// assert_eq!(custom_struct.backup_at.to_string(), "03:30:00");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl TimeOfDay {
+    /// The time `hour:minute:second`, `None` if it doesn't exist
+    pub fn new(hour: u8, minute: u8, second: u8) -> Option<Self> {
+        (hour < 24 && minute < 60 && second < 60).then_some(TimeOfDay {
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// The hour, from 0 to 23
+    pub fn hour(self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from 0 to 59
+    pub fn minute(self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from 0 to 59
+    pub fn second(self) -> u8 {
+        self.second
+    }
+
+    /// The time elapsed since midnight
+    pub fn since_midnight(self) -> Duration {
+        Duration::from_secs(
+            u64::from(self.hour) * 3600
+                + u64::from(self.minute) * 60
+                + u64::from(self.second),
+        )
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| {
+            Error::Custom(format!("{} while parsing value '{}'", reason, value))
+        };
+
+        let parts: Vec<&str> = value.trim().split(':').collect();
+
+        if !(2..=3).contains(&parts.len()) {
+            return Err(error("expected `HH:MM` or `HH:MM:SS`"));
+        }
+
+        let part = |i: usize, name: &str, max: u8| match parts.get(i).copied() {
+            Some(part)
+                if part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                part.parse::<u8>()
+                    .ok()
+                    .filter(|number| *number <= max)
+                    .ok_or_else(|| {
+                        error(&format!("{} `{}` is out of range 0-{}", name, part, max))
+                    })
+            }
+            Some(part) => Err(error(&format!(
+                "expected two digits for the {}, found `{}`",
+                name, part
+            ))),
+            None => Ok(0),
+        };
+
+        Ok(TimeOfDay {
+            hour: part(0, "hour", 23)?,
+            minute: part(1, "minute", 59)?,
+            second: part(2, "second", 59)?,
+        })
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{:02}:{:02}:{:02}",
+            self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<TimeOfDay> for time::Time {
+    fn from(time: TimeOfDay) -> Self {
+        time::Time::from_hms(time.hour, time.minute, time.second)
+            .expect("times of day are checked when they're parsed")
+    }
+}
+
+/// A point in time after the unix epoch, written as an RFC 3339 timestamp,
+/// such as `2024-05-01T12:00:00Z` or `2024-05-01T14:00:00.5+02:00`, or as
+/// a number of seconds since the unix epoch. It's displayed and serialized
+/// as an RFC 3339 timestamp in UTC.
+///
+/// With the `time` feature, it converts into `time::OffsetDateTime`.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, Timestamp};
+/// use serde::Deserialize;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     maintenance_from: Timestamp,
+/// }
+///
+/// let vars = vec![("MAINTENANCE_FROM".to_owned(), "2024-05-01T14:00:00+02:00".to_owned())];
+///
+/// let custom_struct: CustomStruct = from_iter(vars).unwrap();
+///
+/// assert_eq!(
+///     custom_struct.maintenance_from.as_system_time(),
+///     UNIX_EPOCH + Duration::from_secs(1714564800)
+/// );
+/// assert_eq!(custom_struct.maintenance_from.to_string(), "2024-05-01T12:00:00Z");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub SystemTime);
+
+impl Timestamp {
+    /// The point in time as a [`SystemTime`]
+    pub fn as_system_time(self) -> SystemTime {
+        self.0
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        file::parse_timestamp(value.trim())
+            .map(Timestamp)
+            .ok_or_else(|| {
+                Error::Custom(format!(
+                    "expected an RFC 3339 timestamp, such as `2024-05-01T12:00:00Z`, while parsing value '{}'",
+                    value
+                ))
+            })
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        // Seconds since the epoch, negative before it, and the nanoseconds after them
+        let (secs, nanos) = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+            Err(error) => match error.duration() {
+                before if before.subsec_nanos() == 0 => (-(before.as_secs() as i64), 0),
+                before => (
+                    -(before.as_secs() as i64) - 1,
+                    1_000_000_000 - before.subsec_nanos(),
+                ),
+            },
+        };
+        let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+        let secs = secs.rem_euclid(86400);
+
+        write!(
+            fmt,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )?;
+
+        match nanos {
+            0 => fmt.write_str("Z"),
+            nanos => write!(fmt, ".{}Z", format!("{:09}", nanos).trim_end_matches('0')),
+        }
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Timestamp(time)
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<Timestamp> for time::OffsetDateTime {
+    fn from(timestamp: Timestamp) -> Self {
+        time::OffsetDateTime::from(timestamp.0)
+    }
+}
+
+/// The date of the proleptic Gregorian calendar `days` days after the unix epoch,
+/// see <http://howardhinnant.github.io/date_algorithms.html>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// The fields of a crontab schedule: their name, range and the names of their values
+const CRON_FIELDS: [(&str, u8, u8, &[&str]); 5] = [
+    ("minute", 0, 59, &[]),
+    ("hour", 0, 23, &[]),
+    ("day of month", 1, 31, &[]),
+    (
+        "month",
+        1,
+        12,
+        &[
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct",
+            "nov", "dec",
+        ],
+    ),
+    (
+        "day of week",
+        0,
+        7,
+        &["sun", "mon", "tue", "wed", "thu", "fri", "sat"],
+    ),
+];
+
+/// The schedules the `@` shortcuts stand for
+const CRON_SHORTCUTS: &[(&str, &str)] = &[
+    ("@yearly", "0 0 1 1 *"),
+    ("@annually", "0 0 1 1 *"),
+    ("@monthly", "0 0 1 * *"),
+    ("@weekly", "0 0 * * 0"),
+    ("@daily", "0 0 * * *"),
+    ("@midnight", "0 0 * * *"),
+    ("@hourly", "0 * * * *"),
+];
+
+/// A schedule written like the ones of crontab, with five fields: the minute,
+/// the hour, the day of the month, the month and the day of the week, such as
+/// `0 3 * * *` for every day at 03:00 or `*/15 9-17 * * mon-fri` for every
+/// 15 minutes during working hours. Fields are `*`, numbers, ranges such as
+/// `1-5`, lists such as `1,15` and steps such as `*/15` or `0-30/10`. Months and
+/// days of the week can be written with their first three letters, and `0` and `7`
+/// are both Sunday. `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly`
+/// are accepted too.
+///
+/// Errors name the field that's invalid and the range it accepts, and the
+/// expression is kept as it was written, available with [`CronSchedule::as_str`].
+///
+/// With the `cron` feature, [`CronSchedule::schedule`] converts it into
+/// a `cron::Schedule`, which computes the upcoming times.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, CronSchedule};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     backup_cron: CronSchedule,
+/// }
+///
+/// let vars = vec![("BACKUP_CRON".to_owned(), "0 3 * * *".to_owned())];
+///
+/// let custom_struct: CustomStruct = from_iter(vars).unwrap();
+///
+/// assert_eq!(custom_struct.backup_cron.as_str(), "0 3 * * *");
+///
+/// let vars = vec![("BACKUP_CRON".to_owned(), "0 25 * * *".to_owned())];
+///
+/// assert_eq!(
+///     from_iter::<CustomStruct, _>(vars).unwrap_err().to_string(),
+///     "hour `25` is out of range 0-23 while parsing value '0 25 * * *'"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    /// The values every field matches, as bits
+    fields: [u64; 5],
+}
+
+impl CronSchedule {
+    /// The schedule as it was written
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+
+    /// Whether the schedule runs at `minute` past `hour`, on the day `day` of
+    /// the month `month`, from 1 to 12, being the day `weekday` of the week,
+    /// from 0 for Sunday to 6. When both days are restricted, either
+    /// matching is enough, like crontab does.
+    pub fn includes(
+        &self,
+        minute: u8,
+        hour: u8,
+        day: u8,
+        month: u8,
+        weekday: u8,
+    ) -> bool {
+        let has = |field: usize, value: u8| {
+            value < 64 && self.fields[field] & 1 << value != 0
+        };
+
+        let [_, _, days, _, weekdays] = self.fields;
+        let day_matches = match (days == full(2), weekdays == full(4)) {
+            (false, false) => has(2, day) || has(4, weekday % 7),
+            _ => has(2, day) && has(4, weekday % 7),
+        };
+
+        has(0, minute) && has(1, hour) && has(3, month) && day_matches
+    }
+
+    /// The schedule as a `cron::Schedule`, which computes the upcoming times.
+    ///
+    /// `cron::Schedule` requires both the day of the month and the day of the week
+    /// to match, unlike crontab, so schedules restricting both match less often.
+    #[cfg(feature = "cron")]
+    pub fn schedule(&self) -> cron::Schedule {
+        let values = |field: usize, name: fn(u8) -> String| {
+            if self.fields[field] == full(field) {
+                return String::from("*");
+            }
+
+            (0..64)
+                .filter(|value| self.fields[field] & 1 << value != 0)
+                .map(name)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        // cron numbers days of the week from 1, so their names are used instead
+        let expression = format!(
+            "0 {} {} {} {} {}",
+            values(0, |value| value.to_string()),
+            values(1, |value| value.to_string()),
+            values(2, |value| value.to_string()),
+            values(3, |value| value.to_string()),
+            values(4, |value| CRON_FIELDS[4].3[usize::from(value)].to_owned()),
+        );
+
+        expression
+            .parse()
+            .expect("cron schedules are checked when they're parsed")
+    }
+
+    /// Parse the field numbered `field`, returning the values it matches
+    /// as bits, or why it can't be
+    fn parse_field(field: usize, value: &str) -> Result<u64, String> {
+        let (name, min, max, names) = CRON_FIELDS[field];
+
+        let number = |part: &str| {
+            names
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(part))
+                .map(|i| i as u8 + min)
+                .or_else(|| {
+                    part.parse::<u8>()
+                        .ok()
+                        .filter(|n| (min..=max).contains(n))
+                })
+                .ok_or_else(|| {
+                    format!("{} `{}` is out of range {}-{}", name, part, min, max)
+                })
+        };
+
+        let mut bits = 0;
+
+        for item in value.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u8>()
+                        .ok()
+                        .filter(|step| *step > 0)
+                        .ok_or_else(|| {
+                            format!("invalid step `{}` in the {}", step, name)
+                        })?,
+                ),
+                None => (item, 1),
+            };
+
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (number(start)?, number(end)?),
+                // A single value with a step runs until the end of the range
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            };
+
+            if start > end {
+                return Err(format!(
+                    "{} range `{}` ends before it starts",
+                    name, range
+                ));
+            }
+
+            for value in (start..=end).step_by(usize::from(step)) {
+                bits |= 1 << value;
+            }
+        }
+
+        // Sunday is both 0 and 7
+        if field == 4 && bits & 1 << 7 != 0 {
+            bits = (bits | 1) & !(1 << 7);
+        }
+
+        Ok(bits)
+    }
+}
+
+/// The bits of every value of the field numbered `field`
+fn full(field: usize) -> u64 {
+    let (_, min, max, _) = CRON_FIELDS[field];
+    // Sunday is only kept as 0
+    let max = if field == 4 { max - 1 } else { max };
+
+    (min..=max).fold(0, |bits, value| bits | 1 << value)
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| {
+            Error::Custom(format!("{} while parsing value '{}'", reason, value))
+        };
+
+        let expression = value.trim();
+        let expanded = match expression.strip_prefix('@') {
+            Some(_) => CRON_SHORTCUTS
+                .iter()
+                .find(|(shortcut, _)| shortcut.eq_ignore_ascii_case(expression))
+                .map(|(_, expanded)| *expanded)
+                .ok_or_else(|| error(format!("unknown shortcut `{}`", expression)))?,
+            None => expression,
+        };
+
+        let parts: Vec<&str> = expanded.split_whitespace().collect();
+
+        if parts.len() != CRON_FIELDS.len() {
+            return Err(error(format!(
+                "expected 5 fields, minute, hour, day of month, month and day of week, found {}",
+                parts.len()
+            )));
+        }
+
+        let mut fields = [0; 5];
+
+        for (i, part) in parts.into_iter().enumerate() {
+            fields[i] = CronSchedule::parse_field(i, part).map_err(error)?;
+        }
+
+        Ok(CronSchedule {
+            expression: expression.to_owned(),
+            fields,
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.expression)
+    }
+}
+
+deserialize_from_str! {
+    TimeOfDay => "a time of day, such as `03:00`",
+    Timestamp => "an RFC 3339 timestamp, such as `2024-05-01T12:00:00Z`",
+    CronSchedule => "a cron schedule, such as `0 3 * * *`",
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CronSchedule, TimeOfDay, Timestamp};
+    use crate::{from_iter, to_vec, Error};
+    use serde::{Deserialize, Serialize};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+    struct Backup {
+        at: TimeOfDay,
+        cron: CronSchedule,
+        since: Timestamp,
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        assert_eq!(
+            "23:59:30".parse::<TimeOfDay>().ok(),
+            TimeOfDay::new(23, 59, 30)
+        );
+        assert_eq!(
+            "7:00".parse::<TimeOfDay>(),
+            Err(Error::Custom(String::from(
+                "expected two digits for the hour, found `7` while parsing value '7:00'"
+            )))
+        );
+        assert_eq!(
+            "12:60".parse::<TimeOfDay>(),
+            Err(Error::Custom(String::from(
+                "minute `60` is out of range 0-59 while parsing value '12:60'"
+            )))
+        );
+        assert!("12".parse::<TimeOfDay>().is_err());
+        assert!("12:00:00:00".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp: Timestamp = "2024-02-29T23:59:59.25-01:00".parse().unwrap();
+
+        assert_eq!(
+            timestamp.as_system_time(),
+            UNIX_EPOCH + Duration::from_millis(1709254799250)
+        );
+        assert_eq!(timestamp.to_string(), "2024-03-01T00:59:59.25Z");
+        assert_eq!(timestamp.to_string().parse::<Timestamp>(), Ok(timestamp));
+        assert!("yesterday".parse::<Timestamp>().is_err());
+
+        let timestamp: Timestamp = "1969-07-20T20:17:40.5Z".parse().unwrap();
+
+        assert_eq!(
+            timestamp.as_system_time(),
+            UNIX_EPOCH - Duration::from_millis(14182939500)
+        );
+        assert_eq!(timestamp.to_string(), "1969-07-20T20:17:40.5Z");
+        assert_eq!(timestamp.to_string().parse::<Timestamp>(), Ok(timestamp));
+        assert!("2024-02-31T00:00:00Z"
+            .parse::<Timestamp>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        let schedule: CronSchedule = "*/15 9-17 * * mon-fri".parse().unwrap();
+
+        assert!(schedule.includes(45, 17, 3, 5, 5));
+        assert!(!schedule.includes(50, 9, 3, 5, 5));
+        assert!(!schedule.includes(0, 12, 4, 5, 6));
+
+        let schedule: CronSchedule = "0 0 1,15 * 7".parse().unwrap();
+
+        // Either day is enough when both are restricted
+        assert!(schedule.includes(0, 0, 15, 2, 3));
+        assert!(schedule.includes(0, 0, 9, 2, 0));
+        assert!(!schedule.includes(0, 0, 9, 2, 3));
+
+        let schedule: CronSchedule = "@weekly".parse().unwrap();
+
+        assert!(schedule.includes(0, 0, 9, 2, 0));
+        assert_eq!(schedule.as_str(), "@weekly");
+
+        for (value, reason) in [
+            ("0 3 * *", "expected 5 fields, minute, hour, day of month, month and day of week, found 4"),
+            ("0 3 32 * *", "day of month `32` is out of range 1-31"),
+            ("0 3 * foo *", "month `foo` is out of range 1-12"),
+            ("*/0 3 * * *", "invalid step `0` in the minute"),
+            ("0 5-3 * * *", "hour range `5-3` ends before it starts"),
+            ("@reboot", "unknown shortcut `@reboot`"),
+        ] {
+            assert_eq!(
+                value.parse::<CronSchedule>(),
+                Err(Error::Custom(format!(
+                    "{} while parsing value '{}'",
+                    reason, value
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn test_schedule_fields() {
+        let backup = Backup {
+            at: TimeOfDay::new(3, 0, 0).unwrap(),
+            cron: "0 3 * * sun".parse().unwrap(),
+            since: Timestamp(UNIX_EPOCH + Duration::from_secs(1714564800)),
+        };
+        let vars = to_vec(&backup).unwrap();
+
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("AT"), String::from("03:00:00")),
+                (String::from("CRON"), String::from("0 3 * * sun")),
+                (String::from("SINCE"), String::from("2024-05-01T12:00:00Z")),
+            ]
+        );
+        assert_eq!(from_iter::<Backup, _>(vars).unwrap(), backup);
+    }
+
+    #[cfg(feature = "cron")]
+    #[test]
+    fn test_cron_crate_schedule() {
+        use chrono::{TimeZone, Utc};
+
+        let schedule: CronSchedule = "30 3 * * 7".parse().unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            schedule.schedule().after(&after).next(),
+            Some(
+                Utc.with_ymd_and_hms(2024, 5, 5, 3, 30, 0)
+                    .unwrap()
+            )
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_conversions() {
+        let time = time::Time::from(TimeOfDay::new(3, 30, 15).unwrap());
+        let timestamp: Timestamp = "2024-05-01T12:00:00Z".parse().unwrap();
+
+        assert_eq!(time, time::Time::from_hms(3, 30, 15).unwrap());
+        assert_eq!(
+            time::OffsetDateTime::from(timestamp).unix_timestamp(),
+            1714564800
+        );
+    }
+}
@@ -0,0 +1,664 @@
+//! Batteries-included entry point tying the builder, layered sources,
+//! reloading on changes and redaction of secrets together
+
+use crate::{
+    convert::maybe_invalid_unicode_vars_os, exporter, Builder, KeyCase, Result,
+};
+use serde::{de, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+#[cfg(feature = "watch")]
+use std::sync::Mutex;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Placeholder written instead of the values of secret fields
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Names of the variables mapped to the source they were read from
+type Provenance = BTreeMap<String, String>;
+
+/// Where the variables of a [`Config`] are read from
+#[derive(Debug, Clone)]
+enum Layer {
+    /// An env file, read with [`Builder::read_file`]
+    File(PathBuf),
+
+    /// The environment of the running process
+    Env,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Layer::File(path) => write!(fmt, "{}", path.display()),
+            Layer::Env => fmt.write_str("environment"),
+        }
+    }
+}
+
+/// A difference between the loaded configuration and the
+/// one its sources currently describe, see [`Config::diff`],
+/// or between two captures of the environment, see [`crate::EnvSnapshot::diff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The key of the variable
+    pub key: String,
+
+    /// The value currently loaded, `None` if the variable is new
+    pub old: Option<String>,
+
+    /// The value the sources currently hold, `None` if the variable is gone
+    pub new: Option<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Owns everything needed to load some type `T`: the settings of a [`Builder`],
+/// the layered sources it's read from and which of its fields are secret.
+/// Users are meant to obtain this struct by calling [`Config::new`].
+///
+/// Sources are layered in the order they're added, later ones overriding
+/// earlier ones, and every source remembers which variables it provided.
+/// Without any sources, the environment of the running process is used.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, Config};
+/// use serde::{Deserialize, Serialize};
+/// use std::env;
+///
+/// #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// struct App {
+///     port: u16,
+///     password: String,
+/// }
+///
+/// env::set_var("CONFIG_DOC_PORT", "8080");
+/// env::set_var("CONFIG_DOC_PASSWORD", "hunter2");
+///
+/// let config: Config<App> = Config::new(builder().prefix("CONFIG_DOC_"))
+///     .env()
+///     .secret("password");
+///
+/// let app = config.load().unwrap();
+///
+/// assert_eq!(app.port, 8080);
+/// assert_eq!(config.get().unwrap(), app);
+/// assert_eq!(
+///     config.describe().unwrap(),
+///     "CONFIG_DOC_PORT=8080 # environment\nCONFIG_DOC_PASSWORD=<redacted> # environment\n"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Config<T> {
+    builder: Builder,
+    layers: Vec<Layer>,
+    secrets: Vec<String>,
+    current: RwLock<Option<Arc<T>>>,
+    provenance: RwLock<Provenance>,
+    #[cfg(feature = "watch")]
+    watchers: Mutex<Vec<notify::RecommendedWatcher>>,
+}
+
+impl<T> Config<T> {
+    /// Construct a [`Config`] that deserializes with the settings of `builder`
+    pub fn new(builder: Builder) -> Self {
+        Self {
+            builder,
+            layers: Vec::new(),
+            secrets: Vec::new(),
+            current: RwLock::new(None),
+            provenance: RwLock::new(BTreeMap::new()),
+            #[cfg(feature = "watch")]
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add the env file at `path` as a source, which follows
+    /// the same format as described in [`crate::from_str`]
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.layers.push(Layer::File(path.into()));
+        self
+    }
+
+    /// Add the environment of the running process as a source
+    pub fn env(mut self) -> Self {
+        self.layers.push(Layer::Env);
+        self
+    }
+
+    /// Mark a field as secret, which redacts its value in [`Config::describe`]
+    /// and [`Config::diff`], and raises [`crate::Warning::DefaultedSecret`] if
+    /// it's read from the defaults file set with [`Builder::defaults`]
+    pub fn secret(mut self, field: &str) -> Self {
+        self.secrets.push(field.to_owned());
+        self.builder = self.builder.secret(field);
+        self
+    }
+
+    /// The value loaded by the last successful [`Config::load`]
+    /// or [`Config::reload`], if any
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Collect the variables of every source, later sources
+    /// overriding earlier ones, noting where each of them came from
+    fn collect(&self) -> Result<(Vec<(String, String)>, Provenance)> {
+        let layers = match self.layers.is_empty() {
+            true => &[Layer::Env][..],
+            false => &self.layers[..],
+        };
+
+        let mut vars = BTreeMap::new();
+        let mut provenance = BTreeMap::new();
+
+        for layer in layers {
+            let layer_vars: Vec<(String, String)> = match layer {
+                Layer::File(path) => self
+                    .builder
+                    .parse(&self.builder.read_file(path)?)?,
+                Layer::Env => self
+                    .builder
+                    .trim(maybe_invalid_unicode_vars_os()?)
+                    .collect(),
+            };
+
+            for (key, value) in layer_vars {
+                #[cfg(feature = "tracing")]
+                if let Some(previous) = provenance.get(&key) {
+                    tracing::debug!(
+                        target: "renvar",
+                        variable = %key,
+                        layer = %layer,
+                        overrides = %previous,
+                        value = %self.redact(&key, &value),
+                        "variable overridden by a later layer"
+                    );
+                }
+
+                provenance.insert(key.clone(), layer.to_string());
+                vars.insert(key, value);
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        for (key, layer) in &provenance {
+            if self.is_prefixed(key) {
+                tracing::debug!(
+                    target: "renvar",
+                    variable = %key,
+                    layer = %layer,
+                    value = %self.redact(key, &vars[key]),
+                    "variable taken from layer"
+                );
+            }
+        }
+
+        Ok((vars.into_iter().collect(), provenance))
+    }
+
+    /// Whether `key` has the prefix, or one of the fallback prefixes, and the postfix of the builder
+    #[cfg(feature = "tracing")]
+    fn is_prefixed(&self, key: &str) -> bool {
+        self.builder.strip_key(key).is_some()
+    }
+
+    /// Replace `value` with a placeholder if `key` belongs to a secret field
+    fn redact(&self, key: &str, value: &str) -> String {
+        // Fallback prefixes hold the same secrets under their old names
+        let is_secret = self.secrets.iter().any(|secret| {
+            self.builder
+                .key_names(secret)
+                .iter()
+                .any(|name| name == key)
+        });
+
+        match is_secret {
+            true => REDACTED.to_owned(),
+            false => value.to_owned(),
+        }
+    }
+
+    /// Serialize `value` into the variables it would be read from, named like the
+    /// builder names them. A field read through a fallback prefix, according to
+    /// `provenance`, keeps the name it was read from.
+    fn to_vars(
+        &self,
+        value: &T,
+        provenance: &Provenance,
+    ) -> Result<Vec<(String, String)>>
+    where
+        T: Serialize,
+    {
+        let vars = exporter()
+            .key_case(KeyCase::Snake)
+            .to_vec(value)?;
+
+        Ok(vars
+            .into_iter()
+            .map(|(field, value)| {
+                let mut names = self.builder.key_names(&field);
+                let read = names
+                    .iter()
+                    .position(|name| provenance.contains_key(name))
+                    .unwrap_or_default();
+
+                (names.swap_remove(read), value)
+            })
+            .collect())
+    }
+}
+
+impl<T> Config<T>
+where
+    T: de::DeserializeOwned,
+{
+    /// Read the sources and deserialize `T`, replacing the current value
+    ///
+    /// # Errors
+    ///
+    /// If any of the sources can't be read, [`crate::Error::InvalidUnicode`] if a variable
+    /// of the environment isn't valid unicode, or any errors that might occur during deserialization
+    pub fn load(&self) -> Result<Arc<T>> {
+        let (vars, provenance) = self.collect()?;
+        let value = Arc::new(
+            self.builder
+                .deserialize::<T, _>(vars.into_iter())?,
+        );
+
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some(Arc::clone(&value));
+        *self
+            .provenance
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = provenance;
+
+        Ok(value)
+    }
+
+    /// Read the sources again, for when they might have changed. On failure,
+    /// the previously loaded value is kept and [`Config::get`] keeps returning it.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Config::load`]
+    pub fn reload(&self) -> Result<Arc<T>> {
+        self.load()
+    }
+}
+
+#[cfg(feature = "watch")]
+impl<T> Config<T>
+where
+    T: de::DeserializeOwned + Send + Sync + 'static,
+{
+    /// Call [`Config::reload`] whenever one of the env files added with
+    /// [`Config::file`] changes, handing every new value or error to `callback`.
+    /// Changes to the environment of the process can't be watched.
+    ///
+    /// The files are watched until the [`Config`] is dropped or `watch` is called
+    /// again. Like with [`crate::watch_file`], `callback` runs on a background thread.
+    ///
+    /// # Errors
+    ///
+    /// If any of the files can't be watched
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{builder, Config};
+    /// use serde::Deserialize;
+    /// use std::{env, fs, sync::Arc};
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct App {
+    ///     workers: u8,
+    /// }
+    ///
+    /// let path = env::temp_dir().join("renvar_doc_config_watch.env");
+    /// fs::write(&path, "WORKERS=4").unwrap();
+    ///
+    /// let config: Arc<Config<App>> = Arc::new(Config::new(builder()).file(&path));
+    ///
+    /// config.load().unwrap();
+    /// config
+    ///     .watch(|reloaded| {
+    ///         if let Err(error) = reloaded {
+    ///             eprintln!("keeping the previous configuration: {}", error);
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(config.get().unwrap().workers, 4);
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn watch<F>(self: &Arc<Self>, callback: F) -> Result<()>
+    where
+        F: FnMut(Result<Arc<T>>) + Send + 'static,
+    {
+        let callback = Arc::new(Mutex::new(callback));
+        let mut watchers = Vec::new();
+
+        for layer in &self.layers {
+            let Layer::File(path) = layer else {
+                continue;
+            };
+
+            // The watchers are owned by the config, which they mustn't keep alive
+            let config = Arc::downgrade(self);
+            let callback = Arc::clone(&callback);
+
+            watchers.push(crate::watch::watch_path(path, move |changed| {
+                if let Some(config) = config.upgrade() {
+                    let reloaded = changed.and_then(|()| config.reload());
+                    (callback
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner()))(
+                        reloaded
+                    );
+                }
+            })?);
+        }
+
+        *self
+            .watchers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = watchers;
+
+        Ok(())
+    }
+}
+
+impl<T> Config<T>
+where
+    T: de::DeserializeOwned + Serialize,
+{
+    /// Compare the loaded value with the one the sources currently describe,
+    /// without replacing it. The values of secret fields are redacted.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Config::load`], or if `T` can't be serialized, see [`crate::to_vec`]
+    pub fn diff(&self) -> Result<Vec<Change>> {
+        let (vars, provenance) = self.collect()?;
+        let new: BTreeMap<_, _> = self
+            .to_vars(
+                &self
+                    .builder
+                    .deserialize::<T, _>(vars.into_iter())?,
+                &provenance,
+            )?
+            .into_iter()
+            .collect();
+        let old: BTreeMap<_, _> = match self.get() {
+            Some(current) => {
+                let provenance = self
+                    .provenance
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+                self.to_vars(&current, &provenance)?
+                    .into_iter()
+                    .collect()
+            }
+            None => BTreeMap::new(),
+        };
+
+        let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys
+            .into_iter()
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| Change {
+                key: key.clone(),
+                old: old.get(key).map(|value| self.redact(key, value)),
+                new: new.get(key).map(|value| self.redact(key, value)),
+            })
+            .collect())
+    }
+
+    /// Describe the loaded value as `KEY=value` lines, each followed by the
+    /// source the variable was read from, or `default` if none of them had it.
+    /// The values of secret fields are redacted.
+    ///
+    /// Returns an empty string if nothing was loaded yet.
+    ///
+    /// # Errors
+    ///
+    /// If `T` can't be serialized, see [`crate::to_vec`]
+    pub fn describe(&self) -> Result<String> {
+        let current = match self.get() {
+            Some(current) => current,
+            None => return Ok(String::new()),
+        };
+
+        let provenance = self
+            .provenance
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        Ok(self
+            .to_vars(&current, &provenance)?
+            .into_iter()
+            .map(|(key, value)| {
+                let source = provenance
+                    .get(&key)
+                    .map(String::as_str)
+                    .unwrap_or("default");
+
+                format!("{}={} # {}\n", key, self.redact(&key, &value), source)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Config};
+    use crate::{builder, testing::with_vars, KeyCase, Warning, Warnings};
+    use serde::{Deserialize, Serialize};
+    use std::{env, fs};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    struct Database {
+        host: String,
+        port: u16,
+        password: String,
+    }
+
+    #[test]
+    fn test_layers_and_diff() {
+        with_vars([("DB_PORT", "6432")], || {
+            let path = env::temp_dir().join("renvar_test_config_layers.env");
+            fs::write(
+                &path,
+                "DB_HOST=localhost\nDB_PORT=5432\nDB_PASSWORD=hunter2\n",
+            )
+            .unwrap();
+
+            let config: Config<Database> = Config::new(builder().prefix("DB_"))
+                .file(&path)
+                .env()
+                .secret("password");
+
+            let database = config.load().unwrap();
+
+            assert_eq!(database.host, "localhost");
+            assert_eq!(database.port, 6432);
+            assert_eq!(
+                config.describe().unwrap(),
+                format!(
+                    "DB_HOST=localhost # {path}\nDB_PORT=6432 # environment\nDB_PASSWORD=<redacted> # {path}\n",
+                    path = path.display()
+                )
+            );
+
+            fs::write(&path, "DB_HOST=remote\nDB_PORT=5432\nDB_PASSWORD=hunter3\n")
+                .unwrap();
+
+            let changes = config.diff();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(
+                changes.unwrap(),
+                vec![
+                    Change {
+                        key: String::from("DB_HOST"),
+                        old: Some(String::from("localhost")),
+                        new: Some(String::from("remote")),
+                    },
+                    Change {
+                        key: String::from("DB_PASSWORD"),
+                        old: Some(String::from("<redacted>")),
+                        new: Some(String::from("<redacted>")),
+                    },
+                ]
+            );
+            assert_eq!(config.get().unwrap().host, "localhost");
+        });
+    }
+
+    #[test]
+    fn test_describe_names_variables_like_the_builder() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+        #[serde(rename_all = "camelCase")]
+        struct Service {
+            db_host: String,
+            db_port: u16,
+            password: String,
+        }
+
+        let vars = [
+            ("CFGNEW_DB_HOST_PROD", "localhost"),
+            ("CFGOLD_DB_PORT_PROD", "5432"),
+            ("CFGNEW_PASSWORD_PROD", "hunter2"),
+        ];
+
+        with_vars(vars, || {
+            let config: Config<Service> = Config::new(
+                builder()
+                    .prefix("CFGNEW_")
+                    .fallback_prefix("CFGOLD_")
+                    .postfix("_PROD")
+                    .field_case(KeyCase::Camel),
+            )
+            .env()
+            .secret("password");
+
+            config.load().unwrap();
+
+            assert_eq!(
+                config.describe().unwrap(),
+                "CFGNEW_DB_HOST_PROD=localhost # environment\n\
+                 CFGOLD_DB_PORT_PROD=5432 # environment\n\
+                 CFGNEW_PASSWORD_PROD=<redacted> # environment\n"
+            );
+            assert_eq!(config.diff().unwrap(), vec![]);
+        });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_invalid_unicode_environment() {
+        use crate::Error;
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let loaded = with_vars(
+            [("RENVAR_CONFIG_INVALID", OsStr::from_bytes(b"a\xffb"))],
+            || Config::<Database>::new(builder()).env().load(),
+        );
+
+        assert!(matches!(loaded, Err(Error::InvalidUnicode(_))));
+    }
+
+    #[test]
+    fn test_defaulted_secret() {
+        let defaults = env::temp_dir().join("renvar_test_config_defaults.env");
+        let layer = env::temp_dir().join("renvar_test_config_defaults_layer.env");
+        fs::write(
+            &defaults,
+            "SECRETS_HOST=localhost\nSECRETS_PORT=5432\nSECRETS_PASSWORD=hunter2\n",
+        )
+        .unwrap();
+        fs::write(&layer, "SECRETS_PORT=6432\n").unwrap();
+
+        let warnings = Warnings::new();
+        let config: Config<Database> = Config::new(
+            builder()
+                .prefix("SECRETS_")
+                .defaults(&defaults)
+                .warnings(&warnings),
+        )
+        .file(&layer)
+        .secret("password");
+
+        let database = config.load();
+
+        fs::write(&layer, "SECRETS_PASSWORD=hunter3\n").unwrap();
+        let overridden = config.reload();
+
+        fs::remove_file(&defaults).unwrap();
+        fs::remove_file(&layer).unwrap();
+
+        assert_eq!(database.unwrap().port, 6432);
+        assert_eq!(overridden.unwrap().password, "hunter3");
+        assert_eq!(
+            warnings.take(),
+            vec![Warning::DefaultedSecret {
+                key: String::from("SECRETS_PASSWORD")
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "watch")]
+    fn test_watch() {
+        use std::{sync::mpsc, sync::Arc, time::Duration};
+
+        let path = env::temp_dir().join("renvar_test_config_watch.env");
+        fs::write(&path, "HOST=localhost\nPORT=5432\nPASSWORD=hunter2\n").unwrap();
+
+        let config: Arc<Config<Database>> =
+            Arc::new(Config::new(builder()).file(&path));
+        config.load().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        config
+            .watch(move |reloaded| {
+                let _ = sender.send(reloaded);
+            })
+            .unwrap();
+
+        fs::write(&path, "HOST=remote\nPORT=6432\nPASSWORD=hunter2\n").unwrap();
+
+        let reloaded = loop {
+            match receiver
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap()
+            {
+                Ok(reloaded) if reloaded.port == 6432 => break reloaded,
+                _ => continue,
+            }
+        };
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.host, "remote");
+        assert_eq!(config.get().unwrap().host, "remote");
+    }
+}
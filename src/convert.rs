@@ -1,6 +1,12 @@
-use crate::{de::EnvVarDeserializer, sanitize::is_quote_or_whitespace, Error, Result};
-use serde::de;
-use std::env;
+use crate::de::{
+    BorrowedEnvVarDeserializer, EnvVarDeserializer, EnvVarValue, OsEnvVarDeserializer,
+};
+use crate::{
+    builder, file, options::Options, sanitize::is_quote_or_whitespace, Error, Format,
+    Result,
+};
+use serde::de::{self, Deserialize};
+use std::{borrow::Cow, env, ffi::OsString, sync::Arc};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -27,14 +33,24 @@ use std::env;
 /// ```
 ///
 /// Note that the values will **not** be lowercased, but **will** be trimmed,
-/// removing the afformentioned prefixes and suffixes. Another thing to note is that
+/// removing the afformentioned prefixes and suffixes. Within double quotes,
+/// the `\n`, `\r`, `\t`, `\"` and `\\` escape sequences are expanded, like
+/// dotenv does, so `KEY="line\nline"` holds two lines and `KEY="say \"hi\""`
+/// keeps its quotes. Another thing to note is that
 /// if you define a [`String`] in your `struct`, but the input is `key=`, then
 /// your result will be an empty [`String`]. This means an allocation, so unless
 /// you want this behaviour, you're encouraged to instead define it as an `Option<String>`
 ///
+/// Blank lines and comments, which are lines starting with `#`, are skipped
+///
+/// Values are borrowed from `input` rather than copied, so `T` can hold `&str`
+/// fields, or `Cow<str>` fields marked with `#[serde(borrow)]`, as long as
+/// their values don't contain escape sequences, see [`BorrowedEnvVarDeserializer`]
+///
 /// # Errors
 ///
-/// Any errors that might occur during deserialization
+/// [`Error::Parse`] with the line and column of the first malformed line, one without
+/// a `=` or with an unterminated quote, or any errors that might occur during deserialization
 ///
 /// # Example
 ///
@@ -89,23 +105,359 @@ use std::env;
 ///     }
 /// );
 /// ```
-pub fn from_str<'de, T>(input: &str) -> Result<T>
+pub fn from_str<'de, T>(input: &'de str) -> Result<T>
 where
     T: de::Deserialize<'de>,
 {
-    let iter = input
-        .lines()
-        .filter_map(|line| {
-            line.split_once('=').map(|(key, value)| {
-                (
-                    String::from(key.trim_matches(is_quote_or_whitespace)),
-                    String::from(value.trim_matches(is_quote_or_whitespace)),
-                )
-            })
+    // Lines are parsed as the deserializer asks for them, stopping at the first
+    // malformed one, whose error takes precedence over the deserialization's.
+    // Since the last of repeated keys wins, the deserializer reads every line
+    // before the first field, holding them borrowed. Other duplicate policies,
+    // see `BorrowedEnvVarDeserializer::duplicate_keys`, don't hold them at all
+    let mut error = None;
+    let iter =
+        parse_str(input).map_while(|line| line.map_err(|e| error = Some(e)).ok());
+
+    let value = T::deserialize(BorrowedEnvVarDeserializer::new(iter).nested::<T>());
+
+    match error {
+        Some(error) => Err(error),
+        None => value,
+    }
+}
+
+/// Which of the variables of the process and the ones of a [`str`] are kept
+/// when both set the same key, see [`crate::Builder::from_str_with_env`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EnvPrecedence {
+    /// The variables of the process override the ones of the [`str`],
+    /// which only provides defaults
+    #[default]
+    Env,
+
+    /// The variables of the [`str`] override the ones of the process
+    Input,
+}
+
+/// Deserialize some type `T` from a [`str`], which follows the same format as
+/// described in [`from_str`], overlaid with the variables of the currently running
+/// process, which take precedence. A common pattern for `.env` files providing
+/// defaults that the real environment overrides.
+///
+/// # Errors
+///
+/// Any errors that might occur during deserialization
+///
+/// # Panics
+/// if any of the environment variables contain invalid unicode
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_str_with_env_override;
+/// use serde::Deserialize;
+/// use std::env;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     database_host: String,
+///     database_port: u16,
+/// }
+///
+/// env::set_var("DATABASE_PORT", "6543");
+///
+/// let input = "DATABASE_HOST=localhost\nDATABASE_PORT=5432\n";
+/// let custom_struct = from_str_with_env_override::<CustomStruct>(input).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         database_host: "localhost".to_owned(),
+///         database_port: 6543
+///     }
+/// );
+/// ```
+pub fn from_str_with_env_override<T>(input: &str) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    builder().from_str_with_env(input, EnvPrecedence::Env)
+}
+
+/// Deserialize some type `T` from the variables read out of `reader`, such as
+/// a file, `stdin().lock()` or a socket, written in `format`: `.env` lines, as described
+/// in [`from_str`], `docker run --env-file` files, systemd `EnvironmentFile=` files
+/// or PowerShell scripts setting `$env:` variables. The input is parsed line by line
+/// as it's read, without holding all of it in memory, so the output of `printenv`
+/// can be piped in directly.
+///
+/// # Errors
+///
+/// If `reader` fails, [`Error::InvalidUnicode`] if a line isn't valid UTF-8,
+/// if the variables can't be parsed or any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_reader, Format};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     greeting: String,
+///     port: u16,
+/// }
+///
+/// let script = "$env:GREETING = \"Hello`tWorld\"\n$env:PORT = 8080\n";
+/// let custom_struct: CustomStruct =
+///     from_reader(script.as_bytes(), Format::PowerShell).unwrap();
+///
+/// assert_eq!(custom_struct.greeting, "Hello\tWorld");
+///
+/// let env_file = "GREETING='Hello'\nPORT=8080\n";
+/// let custom_struct: CustomStruct =
+///     from_reader(env_file.as_bytes(), Format::Docker).unwrap();
+///
+/// assert_eq!(custom_struct.greeting, "'Hello'");
+///
+/// let unit_env = "; set by the installer\nGREETING=\"Hello \\\n World\"\nPORT=8080\n";
+/// let custom_struct: CustomStruct =
+///     from_reader(unit_env.as_bytes(), Format::Systemd).unwrap();
+///
+/// assert_eq!(custom_struct.greeting, "Hello  World");
+/// ```
+pub fn from_reader<T, R>(reader: R, format: Format) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    R: std::io::BufRead,
+{
+    builder().from_reader(reader, format)
+}
+
+/// Deserialize some type `T` from NUL separated `KEY=value` entries, such as
+/// the output of `env -0` or the contents of `/proc/<pid>/environ`. Since
+/// no entry can contain a NUL, values are taken as they're written, newlines,
+/// quotes and whitespace included. Entries without a `=` are skipped.
+///
+/// # Errors
+///
+/// [`Error::InvalidUnicode`] if a key or value isn't valid UTF-8,
+/// or any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_nul_delimited;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     certificate: String,
+///     port: u16,
+/// }
+///
+/// let output = b"CERTIFICATE=-----BEGIN-----\nMIIB\n-----END-----\n\0PORT=8080\0";
+/// let custom_struct: CustomStruct = from_nul_delimited(output).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         certificate: "-----BEGIN-----\nMIIB\n-----END-----\n".to_owned(),
+///         port: 8080,
+///     }
+/// );
+/// ```
+pub fn from_nul_delimited<T>(bytes: &[u8]) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    builder().from_nul_delimited(bytes)
+}
+
+/// Split a blob of `KEY=value` lines into trimmed `(key, value)` pairs,
+/// expanding escape sequences in double quoted values, see [`split_lines`]
+///
+/// Values are borrowed from `input`, unless they contain escape sequences
+pub(crate) fn parse_str(
+    input: &str,
+) -> impl Iterator<Item = Result<(&str, Cow<'_, str>)>> + '_ {
+    split_lines(input, false).map(|line| {
+        line.map(|(key, value)| {
+            (
+                key.trim_matches(is_quote_or_whitespace),
+                match unescape_quoted(value) {
+                    Some(Cow::Borrowed(value)) => {
+                        Cow::Borrowed(value.trim_matches(' '))
+                    }
+                    Some(Cow::Owned(value)) => {
+                        Cow::Owned(value.trim_matches(' ').to_owned())
+                    }
+                    None => Cow::Borrowed(value.trim_matches(is_quote_or_whitespace)),
+                },
+            )
         })
-        .collect::<Vec<_>>();
+    })
+}
+
+/// Remove the double quotes around `value` and expand the `\n`, `\r`, `\t`,
+/// `\"` and `\\` escape sequences between them, like dotenv does.
+/// Other backslashes are kept as they are. Values without any backslashes are borrowed.
+///
+/// Returns `None` if the value, ignoring surrounding whitespace, isn't double quoted
+pub(crate) fn unescape_quoted(value: &str) -> Option<Cow<'_, str>> {
+    let inner = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))?;
+
+    if !inner.contains('\\') {
+        return Some(Cow::Borrowed(inner));
+    }
+
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(c @ ('"' | '\\')) => unescaped.push(c),
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    Some(Cow::Owned(unescaped))
+}
+
+/// Split a blob of `KEY=value` lines into `(key, value)` pairs, as they're written,
+/// skipping blank lines and comments, which are lines starting with `#`
+///
+/// Lines without a key and a `=` are reported as [`Error::Parse`], as are values
+/// that open a quote without closing it, unless `raw` is set, where quotes mean nothing
+pub(crate) fn split_lines(
+    input: &str,
+    raw: bool,
+) -> impl Iterator<Item = Result<(&str, &str)>> + '_ {
+    // `lines` leaves the `\r` of a last line without `\n`
+    input
+        .strip_prefix(file::BOM)
+        .unwrap_or(input)
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .enumerate()
+        .filter(|(_, line)| !is_blank_or_comment(line))
+        .map(move |(i, line)| split_line(i + 1, line, raw))
+}
+
+/// Whether `line` is skipped, being blank or a comment
+pub(crate) fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Split the line numbered `number` into its key and value, see [`split_lines`]
+pub(crate) fn split_line(number: usize, line: &str, raw: bool) -> Result<(&str, &str)> {
+    let error = |column: usize, reason: &str| Error::Parse {
+        line: number,
+        column,
+        reason: reason.to_owned(),
+    };
+
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| error(line.chars().count() + 1, "expected `=` after the key"))?;
+
+    if key.trim().is_empty() {
+        return Err(error(1, "expected a key before `=`"));
+    }
+
+    if !raw {
+        let quoted = value.trim_start();
+
+        if let Some(quote) = quoted
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+        {
+            if !quoted[1..].contains(quote) {
+                let column = key.chars().count() + value.chars().count()
+                    - quoted.chars().count()
+                    + 2;
+
+                return Err(error(column, "unterminated quote"));
+            }
+        }
+    }
+
+    Ok((key, value))
+}
+
+/// Deserialize some type `T` from a [`str`], without any sanitization
+///
+/// Unlike [`from_str`], nothing is stripped from the keys and values, which are
+/// everything before and after the first `=` of each line, and keys are matched
+/// with struct fields exactly as they're written, without any case folding.
+/// Meant for values that are opaque tokens, where any silent mutation is unacceptable.
+/// Values are borrowed from `input`, so `T` can hold `&str` fields.
+///
+/// Fields holding structs or maps are read from the variables prefixed with
+/// their name as it's written, such as `db_host` for the `host` field of `db`.
+///
+/// # Errors
+///
+/// Any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_str_raw;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     token: String,
+///     list: Vec<String>,
+/// }
+///
+/// let input = "token=' abc= '\nlist= a, b\n";
+///
+/// let custom_struct = from_str_raw::<CustomStruct>(input).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         token: "' abc= '".to_owned(),
+///         list: vec![" a".to_owned(), " b".to_owned()]
+///     }
+/// );
+/// ```
+pub fn from_str_raw<'de, T>(input: &'de str) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    // Same as `from_str`, the first malformed line takes precedence
+    let mut error = None;
+    let iter = split_lines(input, true)
+        .map(|line| line.map(|(key, value)| (key, Cow::Borrowed(value))))
+        .map_while(|line| line.map_err(|e| error = Some(e)).ok());
+
+    let value =
+        T::deserialize(EnvVarDeserializer::with_options(iter, raw()).nested::<T>());
 
-    T::deserialize(EnvVarDeserializer::new(iter.into_iter()))
+    match error {
+        Some(error) => Err(error),
+        None => value,
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -151,14 +503,76 @@ where
     Iter: IntoIterator<Item = (String, String)>,
     T: de::DeserializeOwned,
 {
-    T::deserialize(EnvVarDeserializer::new(iter.into_iter().map(
-        |(key, value)| {
-            (
-                String::from(key.trim_matches(is_quote_or_whitespace)),
-                String::from(value.trim_matches(is_quote_or_whitespace)),
-            )
-        },
-    )))
+    T::deserialize(EnvVarDeserializer::new(sanitize(iter)).nested::<T>())
+}
+
+/// Deserialize some type `T` from an iterator of key-value pairs,
+/// without any sanitization
+///
+/// Like with [`from_str_raw`], keys and values are left untouched,
+/// keys are matched with struct fields exactly as they're written
+/// and nested fields are read from the variables prefixed with their name
+///
+/// # Errors
+///
+/// Any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_iter_raw;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     token: String,
+///     other: Option<String>,
+/// }
+///
+/// let vars = vec![
+///     ("token".to_owned(), "\"secret \"".to_owned()),
+///     ("OTHER".to_owned(), "ignored, since the case doesn't match".to_owned()),
+/// ];
+///
+/// let custom_struct: CustomStruct = from_iter_raw(vars).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         token: "\"secret \"".to_owned(),
+///         other: None
+///     }
+/// )
+/// ```
+pub fn from_iter_raw<T, Iter>(iter: Iter) -> Result<T>
+where
+    Iter: IntoIterator<Item = (String, String)>,
+    T: de::DeserializeOwned,
+{
+    T::deserialize(
+        EnvVarDeserializer::with_options(iter.into_iter(), raw()).nested::<T>(),
+    )
+}
+
+/// Settings of the `_raw` entry points
+fn raw() -> Options {
+    Options {
+        raw: true,
+        ..Options::default()
+    }
+}
+
+/// Strip quotes and whitespace from both ends of every key and value
+pub(crate) fn sanitize<Iter>(iter: Iter) -> impl Iterator<Item = (String, String)>
+where
+    Iter: IntoIterator<Item = (String, String)>,
+{
+    iter.into_iter().map(|(key, value)| {
+        (
+            String::from(key.trim_matches(is_quote_or_whitespace)),
+            String::from(value.trim_matches(is_quote_or_whitespace)),
+        )
+    })
 }
 
 #[cfg(feature = "with_trimmer")]
@@ -224,14 +638,15 @@ pub mod with_trimmer {
         T: de::DeserializeOwned,
         Trimmer: Fn(char) -> bool + Copy,
     {
-        T::deserialize(EnvVarDeserializer::new(iter.into_iter().map(
-            |(key, value)| {
+        T::deserialize(
+            EnvVarDeserializer::new(iter.into_iter().map(|(key, value)| {
                 (
                     String::from(key.trim_matches(trimmer)),
                     String::from(value.trim_matches(trimmer)),
                 )
-            },
-        )))
+            }))
+            .nested::<T>(),
+        )
     }
 
     // todo: replace Fn with Pattern once it's stable
@@ -346,14 +761,17 @@ pub mod with_trimmer {
         T: de::DeserializeOwned,
         Trimmer: Fn(char) -> bool + Copy,
     {
-        T::deserialize(EnvVarDeserializer::new(
-            maybe_invalid_unicode_vars_os()?.map(|(key, value)| {
-                (
-                    String::from(key.trim_matches(trimmer)),
-                    String::from(value.trim_matches(trimmer)),
-                )
-            }),
-        ))
+        T::deserialize(
+            EnvVarDeserializer::new(maybe_invalid_unicode_vars_os()?.map(
+                |(key, value)| {
+                    (
+                        String::from(key.trim_matches(trimmer)),
+                        String::from(value.trim_matches(trimmer)),
+                    )
+                },
+            ))
+            .nested::<T>(),
+        )
     }
 }
 
@@ -460,7 +878,151 @@ pub fn from_os_env<T>() -> Result<T>
 where
     T: de::DeserializeOwned,
 {
-    T::deserialize(EnvVarDeserializer::new(maybe_invalid_unicode_vars_os()?))
+    T::deserialize(
+        EnvVarDeserializer::new(maybe_invalid_unicode_vars_os()?).nested::<T>(),
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserialize some type `T` from a snapshot of the processes environment variables
+/// at the time of invocation, without losing values that aren't valid unicode.
+///
+/// Contrary to [`crate::from_os_env`], values that aren't valid unicode don't fail
+/// the whole deserialization: fields typed `OsString` receive them as they are,
+/// and only other fields consuming such a value fail with [`Error::InvalidUnicode`].
+/// Values that are valid unicode are trimmed the same way as in [`crate::from_env`].
+///
+/// See [`crate::de::OsEnvVarDeserializer`] for more details
+///
+/// # Errors
+///
+/// Any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_os_env_lossless;
+/// use serde::Deserialize;
+/// use std::env;
+/// use std::ffi::OsString;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     lossless_key: OsString,
+/// }
+///
+/// env::set_var("LOSSLESS_KEY", "value");
+///
+/// let custom_struct: CustomStruct = from_os_env_lossless().unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         lossless_key: OsString::from("value")
+///     }
+/// );
+/// ```
+pub fn from_os_env_lossless<T>() -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    let trim = |raw: OsString| match raw.into_string() {
+        Ok(valid) => OsString::from(valid.trim_matches(is_quote_or_whitespace)),
+        Err(invalid) => invalid,
+    };
+
+    T::deserialize(
+        OsEnvVarDeserializer::new(
+            env::vars_os().map(|(key, value)| (trim(key), trim(value))),
+        )
+        .nested::<T>(),
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserialize some type `T` from the value of the environment variable `key`,
+/// for when a single typed value is needed rather than a whole struct.
+///
+/// The value is trimmed the same way as in [`crate::from_env`] and parsed
+/// the same way as a struct field would be, so `T` can be a number,
+/// a sequence such as `Vec<String>`, an enum or anything deserialized from a string.
+///
+/// # Errors
+///
+/// [`Error::MissingValue`] if the variable isn't set, [`Error::InvalidUnicode`]
+/// if its value isn't valid unicode, or any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::var;
+/// use std::env;
+///
+/// env::set_var("VAR_PORT", "8080");
+/// env::set_var("VAR_HOSTS", "'localhost, example.com'");
+///
+/// assert_eq!(var::<u16>("VAR_PORT").unwrap(), 8080);
+/// assert_eq!(
+///     var::<Vec<String>>("VAR_HOSTS").unwrap(),
+///     vec!["localhost".to_owned(), "example.com".to_owned()]
+/// );
+/// assert!(var::<u16>("VAR_UNSET").is_err());
+/// ```
+pub fn var<T>(key: &str) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    match env::var_os(key) {
+        Some(value) => T::deserialize(var_value(value)?.expecting::<T>()),
+        None => Err(Error::MissingValue(key.to_owned())),
+    }
+}
+
+/// Deserialize some type `T` from the value of the environment variable `key`,
+/// see [`crate::var`], or `None` if the variable isn't set or is empty
+///
+/// # Errors
+///
+/// [`Error::InvalidUnicode`] if the value of the variable isn't valid unicode,
+/// or any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::var_opt;
+/// use std::env;
+///
+/// env::set_var("VAR_OPT_WORKERS", "4");
+/// env::set_var("VAR_OPT_EMPTY", "");
+///
+/// assert_eq!(var_opt::<u8>("VAR_OPT_WORKERS").unwrap(), Some(4));
+/// assert_eq!(var_opt::<u8>("VAR_OPT_EMPTY").unwrap(), None);
+/// assert_eq!(var_opt::<u8>("VAR_OPT_UNSET").unwrap(), None);
+/// ```
+pub fn var_opt<T>(key: &str) -> Result<Option<T>>
+where
+    T: de::DeserializeOwned,
+{
+    match env::var_os(key) {
+        Some(value) => Option::<T>::deserialize(var_value(value)?),
+        None => Ok(None),
+    }
+}
+
+/// Trim the value of a single variable for [`crate::var`] and [`crate::var_opt`]
+fn var_value(value: OsString) -> Result<EnvVarValue<'static>> {
+    let value = value
+        .into_string()
+        .map_err(Error::InvalidUnicode)?;
+
+    Ok(EnvVarValue::new(
+        value
+            .trim_matches(is_quote_or_whitespace)
+            .to_owned(),
+        Arc::new(Options::default()),
+    ))
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -494,7 +1056,9 @@ pub(crate) fn maybe_invalid_unicode_vars_os(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::with_vars;
     use serde::Deserialize;
+    use std::collections::BTreeMap;
 
     #[derive(Debug, Deserialize, PartialEq)]
     struct Unit;
@@ -599,6 +1163,168 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_str_escapes() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Escapes {
+            cert: String,
+            quoted: String,
+            literal: String,
+            unquoted: String,
+        }
+
+        let input = r#"
+        CERT="-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n"
+        QUOTED="say \"hi\"\t\\o/ \d"
+        LITERAL='a\nb'
+        UNQUOTED=a\nb
+        "#;
+
+        assert_eq!(
+            from_str::<Escapes>(input).unwrap(),
+            Escapes {
+                cert: String::from(
+                    "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n"
+                ),
+                quoted: String::from("say \"hi\"\t\\o/ \\d"),
+                literal: String::from("a\\nb"),
+                unquoted: String::from("a\\nb"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_str_comments_and_parse_errors() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Commented {
+            host: String,
+            port: u16,
+        }
+
+        let input = "# the host\n\n  # and the port\nHOST=localhost\nPORT=8080\n";
+
+        assert_eq!(
+            from_str::<Commented>(input).unwrap(),
+            Commented {
+                host: String::from("localhost"),
+                port: 8080
+            }
+        );
+
+        let parse_error = |line, column, reason: &str| Error::Parse {
+            line,
+            column,
+            reason: String::from(reason),
+        };
+
+        assert_eq!(
+            from_str::<Commented>("# comment\nHOST=localhost\nPORT\n").unwrap_err(),
+            parse_error(3, 5, "expected `=` after the key")
+        );
+        assert_eq!(
+            from_str::<Commented>("HOST= \"localhost\nPORT=8080\n").unwrap_err(),
+            parse_error(1, 7, "unterminated quote")
+        );
+        assert_eq!(
+            from_str::<Commented>("=localhost\n").unwrap_err(),
+            parse_error(1, 1, "expected a key before `=`")
+        );
+    }
+
+    #[test]
+    fn test_from_str_borrowed() {
+        use std::borrow::Cow;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Borrowed<'a> {
+            name: &'a str,
+            quoted: &'a str,
+            hosts: Vec<&'a str>,
+            pair: (&'a str, &'a str),
+            #[serde(borrow)]
+            escaped: Cow<'a, str>,
+            #[serde(borrow)]
+            plain: Cow<'a, str>,
+        }
+
+        let input = String::from(
+            "NAME=renvar\n\
+             QUOTED=\" spaced \"\n\
+             HOSTS='localhost', example.com\n\
+             PAIR=key,value\n\
+             ESCAPED=\"a\\nb\"\n\
+             PLAIN=c\n",
+        );
+
+        let borrowed = from_str::<Borrowed>(&input).unwrap();
+
+        assert_eq!(
+            borrowed,
+            Borrowed {
+                name: "renvar",
+                quoted: "spaced",
+                hosts: vec!["localhost", "example.com"],
+                pair: ("key", "value"),
+                escaped: Cow::Owned(String::from("a\nb")),
+                plain: Cow::Borrowed("c"),
+            }
+        );
+        assert!(matches!(borrowed.plain, Cow::Borrowed(_)));
+        assert!(matches!(borrowed.escaped, Cow::Owned(_)));
+        assert!(input
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&borrowed.name.as_ptr()));
+    }
+
+    #[test]
+    fn test_raw() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Database<'a> {
+            host: &'a str,
+            port: u16,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App<'a> {
+            token: &'a str,
+            #[serde(borrow)]
+            db: Database<'a>,
+        }
+
+        let input = String::from("token= 'abc' \ndb_host=localhost\ndb_port=5432\n");
+
+        assert_eq!(
+            from_str_raw::<App>(&input),
+            Ok(App {
+                token: " 'abc' ",
+                db: Database {
+                    host: "localhost",
+                    port: 5432,
+                },
+            })
+        );
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Owned {
+            token: String,
+            db: BTreeMap<String, String>,
+        }
+
+        let vars = vec![
+            (String::from("token"), String::from(" abc")),
+            (String::from("db_host"), String::from("localhost")),
+        ];
+
+        assert_eq!(
+            from_iter_raw::<Owned, _>(vars),
+            Ok(Owned {
+                token: String::from(" abc"),
+                db: BTreeMap::from([(String::from("host"), String::from("localhost"))]),
+            })
+        );
+    }
+
     #[test]
     fn test_from_env() {
         let input_str = r#"
@@ -614,19 +1340,19 @@ mod tests {
         enumeration=A
         "#;
 
-        for line in input_str.lines().filter_map(|l| {
-            let line = l.trim();
-            if !line.is_empty() {
-                Some(line)
-            } else {
-                None
-            }
-        }) {
-            let (key, value) = line.split_once('=').unwrap();
-            env::set_var(key, value);
-        }
+        let vars = input_str
+            .lines()
+            .filter_map(|l| {
+                let line = l.trim();
+                if !line.is_empty() {
+                    Some(line)
+                } else {
+                    None
+                }
+            })
+            .map(|line| line.split_once('=').unwrap());
 
-        let actual = from_env::<Test>().unwrap();
+        let actual = with_vars(vars, || from_env::<Test>().unwrap());
 
         assert_eq!(
             actual,
@@ -648,4 +1374,146 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_var() {
+        let vars = [
+            ("RENVAR_TEST_VAR_ENUM", " \"A\" "),
+            ("RENVAR_TEST_VAR_PORT", "port"),
+        ];
+
+        with_vars(vars, || {
+            assert_eq!(
+                var::<Enumeration>("RENVAR_TEST_VAR_ENUM"),
+                Ok(Enumeration::A)
+            );
+            assert_eq!(
+                var_opt::<Enumeration>("RENVAR_TEST_VAR_ENUM"),
+                Ok(Some(Enumeration::A))
+            );
+            assert_eq!(
+                var::<u16>("RENVAR_TEST_VAR_UNSET"),
+                Err(Error::MissingValue(String::from("RENVAR_TEST_VAR_UNSET")))
+            );
+            assert_eq!(var_opt::<u16>("RENVAR_TEST_VAR_UNSET"), Ok(None));
+            assert_eq!(
+                var::<u16>("RENVAR_TEST_VAR_PORT")
+                    .unwrap_err()
+                    .to_string(),
+                "invalid digit found in string while parsing value 'port'"
+            );
+        });
+    }
+
+    #[test]
+    fn test_from_str_with_env_override() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Override {
+            override_host: String,
+            override_port: u16,
+            override_debug: bool,
+        }
+
+        let input =
+            "OVERRIDE_HOST=localhost\nOVERRIDE_PORT=5432\nOVERRIDE_DEBUG=false\n";
+
+        with_vars(
+            [("OVERRIDE_PORT", "6543"), ("OVERRIDE_DEBUG", "true")],
+            || {
+                assert_eq!(
+                    from_str_with_env_override::<Override>(input).unwrap(),
+                    Override {
+                        override_host: String::from("localhost"),
+                        override_port: 6543,
+                        override_debug: true,
+                    }
+                );
+                assert_eq!(
+                    builder()
+                        .from_str_with_env::<Override>(input, EnvPrecedence::Input)
+                        .unwrap(),
+                    Override {
+                        override_host: String::from("localhost"),
+                        override_port: 5432,
+                        override_debug: false,
+                    }
+                );
+                assert!(
+                    from_str_with_env_override::<Override>("OVERRIDE_HOST\n").is_err()
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn test_from_reader() {
+        use std::io::{BufReader, Read};
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Streamed {
+            name: String,
+            port: u16,
+        }
+
+        // Lines arrive in small chunks, as they would through a pipe
+        let input = "\u{feff}# printenv\r\nNAME=\"api\"\r\n\nPORT=8080";
+        let reader = BufReader::with_capacity(4, input.as_bytes().chain(&b"\n"[..]));
+
+        assert_eq!(
+            from_reader::<Streamed, _>(reader, Format::Dotenv).unwrap(),
+            Streamed {
+                name: String::from("api"),
+                port: 8080,
+            }
+        );
+        assert_eq!(
+            from_reader::<Streamed, _>("NAME=api\n\nPORT\n".as_bytes(), Format::Dotenv),
+            Err(Error::Parse {
+                line: 3,
+                column: 5,
+                reason: String::from("expected `=` after the key"),
+            })
+        );
+        assert!(matches!(
+            from_reader::<Streamed, _>(&b"NAME=\xff\n"[..], Format::Dotenv),
+            Err(Error::InvalidUnicode(_))
+        ));
+
+        // Only the `\r` of the line ending is stripped, the value keeps its own
+        assert_eq!(
+            from_reader::<Streamed, _>(
+                "NAME=api\r\r\nPORT=8080\r\n".as_bytes(),
+                Format::Docker
+            ),
+            Ok(Streamed {
+                name: String::from("api\r"),
+                port: 8080,
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_nul_delimited() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Script {
+            body: String,
+            user: String,
+        }
+
+        let environ = b"APP_BODY=echo 'a'\necho \"b\"\n\0APP_USER= root \0HOME=/root\0";
+
+        assert_eq!(
+            builder()
+                .prefix("APP_")
+                .from_nul_delimited::<Script>(environ),
+            Ok(Script {
+                body: String::from("echo 'a'\necho \"b\"\n"),
+                user: String::from(" root "),
+            })
+        );
+        assert!(matches!(
+            from_nul_delimited::<Script>(b"BODY=\xff\0USER=root\0"),
+            Err(Error::InvalidUnicode(_))
+        ));
+    }
 }
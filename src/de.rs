@@ -55,25 +55,205 @@
 //! )
 //! ```
 
-use std::iter::empty;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::iter::{empty, once};
+use std::marker::PhantomData;
+use std::sync::Arc;
 
-use serde::de::value::{MapDeserializer, SeqDeserializer};
-use serde::{
-    de::{self, IntoDeserializer},
-    Deserialize,
+use serde::de::value::{
+    BorrowedStrDeserializer, MapAccessDeserializer, MapDeserializer, SeqDeserializer,
 };
+use serde::de::{self, IntoDeserializer};
 
-use crate::{forward_parsed_values, sanitize::is_quote_or_whitespace, Error, Result};
+use sealed::Sealed;
+
+#[cfg(feature = "json")]
+use crate::json;
+use crate::{
+    forward_parsed_values,
+    options::{DuplicateKeyPolicy, EmptyStringPolicy, Options},
+    parse::parse_lenient_bool,
+    presence::{self, Presence},
+    repr,
+    sanitize::{control_char_offsets, is_quote_or_whitespace},
+    suggest,
+    value::Value,
+    Error, Result, Warning,
+};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Represents the value of an environment variable
 ///
-/// In other words, everything *after* `=`
+/// In other words, everything *after* `=`, borrowed from the input when possible
 #[derive(Debug)]
-pub(crate) struct EnvVarValue(String);
+pub(crate) struct EnvVarValue<'de> {
+    value: Cow<'de, str>,
+    options: Arc<Options>,
+
+    /// The original value, if it isn't valid unicode, in which case
+    /// `value` holds its lossy conversion
+    raw: Option<OsString>,
+
+    /// Whether an empty value is still `Some`, since the variable is set,
+    /// see [`EnvVarValue::expecting`]
+    set: bool,
+}
+
+impl<'de> EnvVarValue<'de> {
+    pub(crate) fn new(value: impl Into<Cow<'de, str>>, options: Arc<Options>) -> Self {
+        Self {
+            value: value.into(),
+            options,
+            raw: None,
+            set: false,
+        }
+    }
+
+    /// Learn from the type `T` the value is deserialized into whether an empty
+    /// value is `Some`, such as `Some(None)` for an `Option<Option<T>>`
+    pub(crate) fn expecting<T>(mut self) -> Self
+    where
+        T: de::Deserialize<'de>,
+    {
+        if self.value.is_empty() {
+            self.set = match presence::presence::<T>() {
+                Presence::NestedOption => true,
+                Presence::OptionOfSeq => !self.options.empty_seqs_as_none,
+                Presence::Other => false,
+            };
+        }
+
+        self
+    }
+
+    /// Create another value that shares the same [`Options`]
+    fn with_value(&self, value: impl Into<Cow<'de, str>>) -> Self {
+        Self::new(value, Arc::clone(&self.options))
+    }
+
+    /// Trim an element of a sequence or tuple, unless the raw entry points are used
+    fn trim_element<'v>(&self, value: &'v str) -> &'v str {
+        if let Some(trim_elements) = &self.options.trim_elements {
+            trim_elements.apply(value)
+        } else if self.options.raw {
+            value
+        } else if self.options.preserve_quotes {
+            value.trim()
+        } else {
+            value.trim_matches(is_quote_or_whitespace)
+        }
+    }
+
+    /// Split the value at the first `delimiter` into the part before it and
+    /// a value holding the rest, both trimmed like the elements of a sequence.
+    /// The rest keeps borrowing from the input if the value does
+    fn split_once(&self, delimiter: char) -> Option<(&str, Self)> {
+        let (head, rest) = self.value.split_once(delimiter)?;
+        let head = self.trim_element(head);
+
+        match &self.value {
+            Cow::Borrowed(value) => {
+                let rest = &value[value.len() - rest.len()..];
+                Some((head, self.with_value(self.trim_element(rest))))
+            }
+            Cow::Owned(_) => {
+                Some((head, self.with_value(self.trim_element(rest).to_owned())))
+            }
+        }
+    }
+
+    /// Split `value` into at most `limit` elements at each `delimiter`, honouring
+    /// escapes and quotes as described in [`repr::split_elements`], unless the raw
+    /// entry points are used
+    fn split<'v>(
+        &self,
+        value: &'v str,
+        delimiter: char,
+        limit: usize,
+    ) -> Vec<Cow<'v, str>> {
+        match self.options.raw {
+            true => value
+                .splitn(limit, delimiter)
+                .map(Cow::Borrowed)
+                .collect(),
+            false => repr::split_elements(value, delimiter, limit),
+        }
+    }
+
+    /// Split the value into at most `limit` trimmed elements of a sequence or tuple,
+    /// which keep borrowing from the input if the value does, unless they had escapes
+    fn elements(&self, delimiter: char, limit: usize) -> Vec<Self> {
+        match &self.value {
+            Cow::Borrowed(value) => self
+                .split(value, delimiter, limit)
+                .into_iter()
+                .map(|element| match element {
+                    Cow::Borrowed(element) => {
+                        self.with_value(self.trim_element(element))
+                    }
+                    Cow::Owned(element) => {
+                        self.with_value(self.trim_element(&element).to_owned())
+                    }
+                })
+                .collect(),
+            Cow::Owned(value) => self
+                .split(value, delimiter, limit)
+                .iter()
+                .map(|element| self.with_value(self.trim_element(element).to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Fail with [`Error::InvalidUnicode`] if the original value isn't valid unicode
+    ///
+    /// Only `OsString` fields are able to hold such values
+    fn check_unicode(&self) -> Result<()> {
+        match &self.raw {
+            Some(raw) => Err(Error::InvalidUnicode(raw.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Hand the original value to the `OsString` visitor, which
+    /// serde represents as an enum of the platform specific encodings
+    fn deserialize_os_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let raw = self
+            .raw
+            .unwrap_or_else(|| OsString::from(self.value.into_owned()));
+
+        #[cfg(unix)]
+        let variant = ("Unix", {
+            use std::os::unix::ffi::OsStringExt;
+            raw.into_vec()
+        });
+
+        #[cfg(windows)]
+        let variant = ("Windows", {
+            use std::os::windows::ffi::OsStrExt;
+            raw.encode_wide().collect::<Vec<u16>>()
+        });
+
+        #[cfg(not(any(unix, windows)))]
+        let variant: (&str, Vec<u8>) = {
+            let _ = raw;
+            return Err(Error::Custom(String::from(
+                "OsString is not supported on this platform",
+            )));
+        };
 
-impl<'de> de::IntoDeserializer<'de, Error> for EnvVarValue {
+        visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(once(
+            variant,
+        ))))
+    }
+}
+
+impl<'de> de::IntoDeserializer<'de, Error> for EnvVarValue<'de> {
     type Deserializer = Self;
 
     fn into_deserializer(self) -> Self::Deserializer {
@@ -81,36 +261,126 @@ impl<'de> de::IntoDeserializer<'de, Error> for EnvVarValue {
     }
 }
 
-impl<'de> de::Deserializer<'de> for EnvVarValue {
+impl<'de> de::Deserializer<'de> for EnvVarValue<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.0
-            .into_deserializer()
-            .deserialize_any(visitor)
+        self.check_unicode()?;
+
+        match self.value {
+            Cow::Borrowed(value) => {
+                BorrowedStrDeserializer::new(value).deserialize_any(visitor)
+            }
+            Cow::Owned(value) => value.into_deserializer().deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        match self.value {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_str(&value),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        visitor.visit_string(self.value.into_owned())
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    /// Values are always text, such as `2024-01-01T00:00:00Z` rather than
+    /// a number of seconds, which types like `chrono::DateTime` rely on
+    fn is_human_readable(&self) -> bool {
+        true
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        if self.0.is_empty() || self.0.chars().all(is_quote_or_whitespace) {
+        self.check_unicode()?;
+
+        #[cfg(feature = "json")]
+        if let Some(json) = json::parse(&self.value, '[')? {
+            return de::Deserializer::deserialize_seq(json, visitor)
+                .map_err(json::error);
+        }
+
+        if self.value.is_empty()
+            || !self.options.raw
+                && !self.options.preserve_quotes
+                && self.value.chars().all(is_quote_or_whitespace)
+        {
             SeqDeserializer::new(empty::<Self>()).deserialize_seq(visitor)
         } else {
-            let values = self.0.split(',').map(|value| {
-                Self(
-                    value
-                        .trim_matches(is_quote_or_whitespace)
-                        .to_owned(),
-                )
-            });
-            SeqDeserializer::new(values).deserialize_seq(visitor)
+            let values = self.elements(repr::SEQ_DELIMITER, usize::MAX);
+
+            if self.options.deny_duplicate_elements {
+                let mut seen = HashSet::new();
+
+                if let Some(duplicate) = values
+                    .iter()
+                    .find(|value| !seen.insert(&value.value))
+                {
+                    return Err(Error::DuplicateElement(duplicate.value.to_string()));
+                }
+            }
+
+            if self.options.track_paths {
+                return visitor.visit_seq(IndexedSeqAccess {
+                    values: values.into_iter(),
+                    index: 0,
+                });
+            }
+
+            SeqDeserializer::new(values.into_iter()).deserialize_seq(visitor)
         }
     }
 
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        // The last element keeps any further delimiters, such as the colons of an IPv6 address
+        let delimiter = self.options.tuple_delimiter;
+        let values = self.elements(delimiter, len);
+        SeqDeserializer::new(values.into_iter()).deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let _ = name;
+
+        self.deserialize_tuple(len, visitor)
+    }
+
     fn deserialize_enum<V>(
         self,
         name: &'static str,
@@ -120,21 +390,103 @@ impl<'de> de::Deserializer<'de> for EnvVarValue {
     where
         V: de::Visitor<'de>,
     {
-        let _ = name;
-        let _ = variants;
+        // The shape std deserializes an `OsString` from, unlike user enums of the same name
+        if name == "OsString" && variants == ["Unix", "Windows"] {
+            return self.deserialize_os_string(visitor);
+        }
+
+        self.check_unicode()?;
+
+        #[cfg(feature = "json")]
+        if let Some(json) = json::parse(&self.value, '{')? {
+            return de::Deserializer::deserialize_enum(json, name, variants, visitor)
+                .map_err(json::error);
+        }
+
+        let lenient = self.options.lenient_variants;
 
-        visitor.visit_enum(self.0.into_deserializer())
+        // Unit variants are written as they are, variants with data as `variant:data`.
+        // A value naming no variant is handed over as it is, which a variant
+        // marked with `#[serde(other)]` accepts
+        let parsed = match repr::parse_unit_variant(&self.value, variants, lenient) {
+            Ok(variant) => Ok((variant, None)),
+            Err(error) => match self.split_once(repr::VARIANT_DELIMITER) {
+                Some((variant, data)) => {
+                    repr::parse_unit_variant(variant, variants, lenient)
+                        .map(|variant| (variant, Some(data)))
+                }
+                None => Err(error),
+            },
+        };
+
+        let access = match parsed {
+            Ok((variant, data)) => EnvVarEnum {
+                variant: Cow::Borrowed(variant),
+                data,
+                unknown: None,
+            },
+            Err(error) => EnvVarEnum {
+                variant: self.value,
+                data: None,
+                unknown: Some(error),
+            },
+        };
+
+        visitor.visit_enum(access)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        if self.0.is_empty() {
+        // An empty `Option<Option<T>>` is `Some(None)` and an empty `Option<Vec<T>>`
+        // is `Some(vec![])`, unless told otherwise, since the variable is set
+        let is_set =
+            self.set || self.options.empty_strings == EmptyStringPolicy::TreatAsEmpty;
+
+        if self.value.is_empty() && !is_set {
             visitor.visit_none()
         } else {
-            visitor.visit_some(self.0.into_deserializer())
+            visitor.visit_some(Self { set: false, ..self })
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        #[cfg(feature = "json")]
+        if let Some(json) = json::parse(&self.value, '{')? {
+            return de::Deserializer::deserialize_struct(json, name, fields, visitor)
+                .map_err(json::error);
+        }
+
+        let _ = name;
+        let _ = fields;
+
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        #[cfg(feature = "json")]
+        if let Some(json) = json::parse(&self.value, '{')? {
+            return de::Deserializer::deserialize_map(json, visitor)
+                .map_err(json::error);
         }
+
+        self.deserialize_any(visitor)
     }
 
     fn deserialize_newtype_struct<V>(
@@ -158,16 +510,11 @@ impl<'de> de::Deserializer<'de> for EnvVarValue {
     where
         V: de::Visitor<'de>,
     {
-        String::deserialize(self.0.into_deserializer()).and_then(|unit_name| {
-            if unit_name == name {
-                visitor.visit_unit()
-            } else {
-                Err(Error::Custom(format!(
-                    "expected unit struct with name '{}', found '{}'",
-                    name, unit_name
-                )))
-            }
-        })
+        self.check_unicode()?;
+
+        repr::parse_unit_struct(name, &self.value)?;
+
+        visitor.visit_unit()
     }
 
     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
@@ -177,8 +524,49 @@ impl<'de> de::Deserializer<'de> for EnvVarValue {
         visitor.visit_unit()
     }
 
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        if !self.options.lenient_bools {
+            return match self.value.parse::<bool>() {
+                Ok(val) => visitor.visit_bool(val),
+                Err(e) => Err(de::Error::custom(format_args!(
+                    "{} while parsing value '{}'",
+                    e, self.value
+                ))),
+            };
+        }
+
+        match parse_lenient_bool(&self.value) {
+            Some(val) => visitor.visit_bool(val),
+            None => Err(de::Error::custom(format_args!(
+                "expected one of 1/0, yes/no, on/off or true/false while parsing value '{}'",
+                self.value
+            ))),
+        }
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.check_unicode()?;
+
+        let mut chars = self.value.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(de::Error::custom(format_args!(
+                "expected a single character while parsing value '{}'",
+                self.value
+            ))),
+        }
+    }
+
     forward_parsed_values! {
-        bool => deserialize_bool,
         u8 => deserialize_u8,
         u16 => deserialize_u16,
         u32 => deserialize_u32,
@@ -187,130 +575,1471 @@ impl<'de> de::Deserializer<'de> for EnvVarValue {
         i16 => deserialize_i16,
         i32 => deserialize_i32,
         i64 => deserialize_i64,
+        u128 => deserialize_u128,
+        i128 => deserialize_i128,
         f32 => deserialize_f32,
         f64 => deserialize_f64,
     }
 
     serde::forward_to_deserialize_any! {
-        char str string bytes byte_buf
-        map tuple tuple_struct
-        struct identifier ignored_any
+        bytes byte_buf ignored_any
     }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-/// An iterator over environment variables of `(key, value)` pairs
-///
-/// Note: Calling [`Iterator::next`] will lowercase all keys
-/// before returning them
-#[derive(Debug)]
-struct EnvVars<Iter>(Iter)
-where
-    Iter: IntoIterator<Item = (String, String)>;
+/// Hands out the elements of a sequence like [`SeqDeserializer`] does, putting
+/// their errors under their index, see [`crate::Builder::track_paths`]
+struct IndexedSeqAccess<'de> {
+    values: std::vec::IntoIter<EnvVarValue<'de>>,
+    index: usize,
+}
 
-impl<Iter> Iterator for EnvVars<Iter>
-where
-    Iter: Iterator<Item = (String, String)>,
-{
-    type Item = (String, EnvVarValue);
+impl<'de> de::SeqAccess<'de> for IndexedSeqAccess<'de> {
+    type Error = Error;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .next()
-            .map(|(key, value)| (key.to_lowercase(), EnvVarValue(value)))
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let Some(value) = self.values.next() else {
+            return Ok(None);
+        };
+
+        let index = self.index;
+        self.index += 1;
+
+        seed.deserialize(value)
+            .map(Some)
+            .map_err(|error| error.at(&format!("[{}]", index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
     }
 }
 
-////////////////////////////////////////////////////////////////////////////////////////////////////////
+/// [`de::EnumAccess`] over the value of an enum field, which is either a unit
+/// variant or a variant followed by its data, such as `redis:host=localhost,port=6379`
+/// for a struct variant, `file:/tmp/cache` for a newtype variant or
+/// `pair:a,b` for a tuple variant
+struct EnvVarEnum<'de> {
+    variant: Cow<'de, str>,
+    data: Option<EnvVarValue<'de>>,
+    /// Why `variant` names no variant, reported unless the enum accepts it anyway
+    unknown: Option<Error>,
+}
 
-/// Deserializer for environment variables
-///
-/// Can be constructred from a type that implements [`Iterator`]
-/// over `(String, String)` tuples
-///
-/// Alternatively, can be constructed from a [`str`] using [`crate::from_str`]
-#[derive(Debug)]
-pub struct EnvVarDeserializer<'de, Iter>
-where
-    Iter: Iterator<Item = (String, String)>,
-{
-    inner: MapDeserializer<'de, EnvVars<Iter>, Error>,
+impl<'de> EnvVarEnum<'de> {
+    /// The data of the variant, failing if there's none
+    fn data(self) -> Result<EnvVarValue<'de>> {
+        self.data.ok_or_else(|| {
+            Error::Custom(format!(
+                "expected data for variant `{}`, written as `{}{}...`",
+                self.variant,
+                self.variant,
+                repr::VARIANT_DELIMITER
+            ))
+        })
+    }
 }
 
-impl<'de, Iter> EnvVarDeserializer<'de, Iter>
-where
-    Iter: Iterator<Item = (String, String)>,
-{
-    /// Construct an [`EnvVarDeserializer`] from an [`Iterator`] over tuples of [`String`]s
-    pub fn new(iter: Iter) -> Self {
-        Self {
-            inner: MapDeserializer::new(EnvVars(iter)),
-        }
+impl<'de> de::EnumAccess<'de> for EnvVarEnum<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed
+            .deserialize(self.variant.clone().into_deserializer())
+            .map_err(|error| self.unknown.take().unwrap_or(error))?;
+
+        Ok((variant, self))
     }
 }
 
-impl<'de, Iter> de::Deserializer<'de> for EnvVarDeserializer<'de, Iter>
-where
-    Iter: Iterator<Item = (String, String)>,
-{
+impl<'de> de::VariantAccess<'de> for EnvVarEnum<'de> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    fn unit_variant(self) -> Result<()> {
+        match self.data {
+            Some(data) => Err(Error::Custom(format!(
+                "unexpected data '{}' for unit variant `{}`",
+                data.value, self.variant
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.data()?)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        de::Deserializer::deserialize_tuple(self.data()?, len, visitor)
     }
 
-    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_map(self.inner)
+        let _ = fields;
+
+        let data = self.data()?;
+        let mut entries = Vec::new();
+
+        for entry in data.elements(repr::SEQ_DELIMITER, usize::MAX) {
+            let (field, value) = entry
+                .split_once(repr::FIELD_DELIMITER)
+                .ok_or_else(|| {
+                    Error::Custom(format!(
+                        "expected `field{}value` while parsing '{}'",
+                        repr::FIELD_DELIMITER,
+                        entry.value
+                    ))
+                })?;
+
+            entries.push((field_key(field.trim(), &data.options), value));
+        }
+
+        visitor.visit_map(MapDeserializer::new(entries.into_iter()))
     }
+}
 
-    serde::forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
-        bytes byte_buf unit_struct tuple_struct
-        identifier tuple ignored_any option newtype_struct enum
-        struct
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A single environment variable, as handed out by [`EnvVars`]
+#[derive(Debug)]
+pub(crate) struct EnvVar<'de> {
+    /// The key as it was given, used when forwarding the variable untouched
+    name: Cow<'de, str>,
+
+    /// The key converted according to [`crate::KeyCase::to_field`],
+    /// used to match struct fields
+    key: Cow<'de, str>,
+
+    value: EnvVarValue<'de>,
+}
+
+impl<'de> EnvVar<'de> {
+    /// Drop the first `len` bytes of the key, the prefix of the scope it belongs to
+    fn scoped(self, len: usize) -> Self {
+        let key = match self.key {
+            Cow::Borrowed(key) => Cow::Borrowed(&key[len..]),
+            Cow::Owned(key) => Cow::Owned(key[len..].to_owned()),
+        };
+
+        Self { key, ..self }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use serde::Deserialize;
+/// The `(key, value)` pairs accepted by [`EnvVarDeserializer`]: `(String, String)`,
+/// `(OsString, OsString)` and `(&str, Cow<str>)`. It can't be implemented outside of this crate
+pub trait RawEnvVar<'de>: sealed::Sealed<'de> {}
 
-    use crate::from_iter;
+// `Sealed` can't be named outside of this crate, so the types of its methods don't leak
+#[allow(private_interfaces)]
+mod sealed {
+    use super::{borrowed_field_key, field_key, EnvVar, EnvVarValue, Options};
+    use crate::Warning;
+    use std::{borrow::Cow, ffi::OsString, sync::Arc};
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct Unit;
+    /// The methods of [`super::RawEnvVar`], out of reach of other crates
+    pub trait Sealed<'de> {
+        fn into_env_var(self, options: &Arc<Options>) -> EnvVar<'de>;
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct NewType(u64);
+        /// The name of the variable, to look at it before converting it
+        fn name(&self) -> Cow<'_, str>;
+    }
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct Test {
-        string_field: String,
-        empty_string_field: String,
-        sequence: Vec<String>,
-        empty_sequence_doublequote: Vec<String>,
-        empty_sequence_singlequote: Vec<String>,
-        empty_sequence_whitespace: Vec<String>,
-        unit: Unit,
-        newtype: NewType,
-        optional_field: Option<String>,
+    impl<'de> Sealed<'de> for (String, String) {
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed(&self.0)
+        }
+
+        fn into_env_var(self, options: &Arc<Options>) -> EnvVar<'de> {
+            let (name, value) = self;
+
+            EnvVar {
+                key: Cow::Owned(field_key(&name, options)),
+                name: Cow::Owned(name),
+                value: EnvVarValue::new(value, Arc::clone(options)),
+            }
+        }
     }
 
-    #[test]
-    fn test_from_iter() {
-        let iter = vec![
-            (String::from("string_field"), String::from("hello")),
-            (String::from("empty_string_field"), String::from("")),
-            (String::from("sequence"), String::from("first,second,third")),
-            (
-                String::from("empty_sequence_doublequote"),
+    /// Keys and values are kept borrowed, unless keys have to be converted to match the fields
+    impl<'de> Sealed<'de> for (&'de str, Cow<'de, str>) {
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed(self.0)
+        }
+
+        fn into_env_var(self, options: &Arc<Options>) -> EnvVar<'de> {
+            let (name, value) = self;
+
+            EnvVar {
+                key: borrowed_field_key(name, options),
+                name: Cow::Borrowed(name),
+                value: EnvVarValue::new(value, Arc::clone(options)),
+            }
+        }
+    }
+
+    /// Keys that aren't valid unicode are converted lossily, since they
+    /// can't match any field anyway, raising [`Warning::LossyConversion`].
+    /// Values that aren't valid unicode are kept as they are, and only raise
+    /// an error if they're deserialized into anything other than an `OsString`
+    impl<'de> Sealed<'de> for (OsString, OsString) {
+        fn name(&self) -> Cow<'_, str> {
+            self.0.to_string_lossy()
+        }
+
+        fn into_env_var(self, options: &Arc<Options>) -> EnvVar<'de> {
+            let (name, value) = self;
+            let lossy = name.to_str().is_none();
+            let name = name.to_string_lossy().into_owned();
+
+            if lossy {
+                options.warn(Warning::LossyConversion { key: name.clone() });
+            }
+
+            let value = match value.into_string() {
+                Ok(value) => EnvVarValue::new(value, Arc::clone(options)),
+                Err(raw) => {
+                    let lossy = raw.to_string_lossy().into_owned();
+
+                    EnvVarValue {
+                        raw: Some(raw),
+                        ..EnvVarValue::new(lossy, Arc::clone(options))
+                    }
+                }
+            };
+
+            EnvVar {
+                key: Cow::Owned(field_key(&name, options)),
+                name: Cow::Owned(name),
+                value,
+            }
+        }
+    }
+}
+
+impl<'de> RawEnvVar<'de> for (String, String) {}
+impl<'de> RawEnvVar<'de> for (&'de str, Cow<'de, str>) {}
+impl<'de> RawEnvVar<'de> for (OsString, OsString) {}
+
+/// Same as [`field_key`], borrowing `name` when it already is the key
+fn borrowed_field_key<'de>(name: &'de str, options: &Options) -> Cow<'de, str> {
+    if options.raw
+        || options.exact_keys
+        || options.field_case.is_none() && options.key_case.is_field(name)
+    {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(field_key(name, options))
+    }
+}
+
+/// The key used to match the variable `name` with struct fields
+pub(crate) fn field_key(name: &str, options: &Options) -> String {
+    if options.raw || options.exact_keys {
+        return name.to_owned();
+    }
+
+    let field = options.key_case.to_field(name);
+
+    match options.field_case {
+        Some(field_case) => field_case.to_key(&field),
+        None => field,
+    }
+}
+
+/// An iterator over environment variables of `(key, value)` pairs
+///
+/// Note: Calling [`Iterator::next`] converts all keys to the naming convention
+/// of the fields before returning them, see [`field_key`], which lowercases them
+/// unless [`crate::Builder::exact_keys`] is enabled
+#[derive(Debug)]
+struct EnvVars<'de, Iter> {
+    iter: Iter,
+    options: Arc<Options>,
+    marker: PhantomData<&'de ()>,
+}
+
+impl<'de, Iter> EnvVars<'de, Iter>
+where
+    Iter: Iterator,
+{
+    /// Read the fields of `T` that hold structs or maps from the
+    /// variables prefixed with their name, see [`Options::nest`]
+    fn nest<T>(&mut self)
+    where
+        T: de::Deserialize<'de>,
+    {
+        Arc::make_mut(&mut self.options).nest::<T>();
+    }
+}
+
+impl<'de, Iter> EnvVars<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    /// Whether any of the variables belongs to the struct being deserialized,
+    /// see [`belongs`], which tells whether an `Option<T>` is set.
+    /// The variables are buffered to look at them, and handed back.
+    fn any_belongs(self) -> (bool, EnvVars<'de, std::vec::IntoIter<Iter::Item>>) {
+        let vars: Vec<Iter::Item> = self.iter.collect();
+        let any = vars
+            .iter()
+            .any(|var| belongs(&field_key(&var.name(), &self.options), &self.options));
+
+        let vars = EnvVars {
+            iter: vars.into_iter(),
+            options: self.options,
+            marker: PhantomData,
+        };
+
+        (any, vars)
+    }
+}
+
+/// Whether the variable `key` belongs to one of the fields of the struct being
+/// deserialized, set up with [`Options::nest`], either directly or through the
+/// prefix of a nested field. Any variable does if the fields aren't known.
+fn belongs(key: &str, options: &Options) -> bool {
+    options.capture_rest.is_some() || names_field(key, options).unwrap_or(true)
+}
+
+/// Whether the variable `key` is read by one of the fields of the struct set up
+/// with [`Options::nest`], either directly or through the prefix of a nested field,
+/// leaving out the `capture_rest` field. `None` if the fields aren't known.
+pub(crate) fn names_field(key: &str, options: &Options) -> Option<bool> {
+    let fields = options.fields.as_ref()?;
+
+    let is_nested = |field: &str| {
+        options
+            .nested
+            .as_ref()
+            .is_some_and(|nested| nested.iter().any(|nested| nested == field))
+    };
+
+    Some(fields.iter().any(|field| {
+        let prefix = match options.scope(field) {
+            Some(prefix) => Some(field_key(prefix, options)),
+            None => is_nested(field).then(|| format!("{}_", field)),
+        };
+
+        key == *field || prefix.is_some_and(|prefix| key.starts_with(&prefix))
+    }))
+}
+
+impl<'de, Iter> Iterator for EnvVars<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    type Item = EnvVar<'de>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|var| var.into_env_var(&self.options))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// [`de::MapAccess`] over [`EnvVars`]
+///
+/// Variables whose keys collide once lowercased are resolved according to the
+/// [`DuplicateKeyPolicy`] set up in [`Options`], up front unless the variables
+/// can be read as the visitor asks for them, see [`streams`].
+///
+/// When deserializing a struct with a `capture_rest` field set up in [`Options`],
+/// the variables that don't match any other field are held back and
+/// handed to that field as a map once every other variable is exhausted.
+///
+/// A field holding a struct or a map that no variable is named after receives
+/// the variables prefixed with its name, such as `DB_HOST` and `DB_PORT` for a `db`
+/// field, or with the prefix set up with [`crate::Builder::scope`], see [`ScopeDeserializer`]
+#[derive(Debug)]
+struct EnvVarMapAccess<'de, Iter = std::iter::Empty<(String, String)>> {
+    vars: Vars<'de, Iter>,
+    scopes: std::vec::IntoIter<Scope<'de>>,
+    options: Arc<Options>,
+    /// Fields of the struct being deserialized, `None` when deserializing a map
+    fields: Option<&'static [&'static str]>,
+    rest: Option<Vec<(String, EnvVarValue<'de>)>>,
+    capture_rest: Option<String>,
+    value: Option<Pending<'de>>,
+
+    /// The last key returned, kept to put errors under it when tracking paths
+    current: Option<String>,
+
+    /// Variables that didn't match any field, collected when `deny_unknown`
+    /// is set or when they're among the ones that must match a field
+    unknown: Vec<String>,
+
+    /// `(name, key)` of the variables left out by [`lookup`], kept for suggestions
+    skipped: Vec<(String, String)>,
+
+    /// Keys handed to the visitor, to report the fields that were left unset
+    #[cfg(feature = "tracing")]
+    matched: Vec<String>,
+}
+
+/// The value belonging to the last key returned by [`EnvVarMapAccess`]
+#[derive(Debug)]
+enum Pending<'de> {
+    Value(EnvVarValue<'de>),
+    Parsed(Value),
+    Rest(Vec<(String, EnvVarValue<'de>)>),
+    Scope(Scope<'de>),
+}
+
+/// The variables of a field that's read from prefixed variables, see [`ScopeDeserializer`]
+#[derive(Debug)]
+struct Scope<'de> {
+    /// Path of the field from the top-level struct, such as `db.primary`
+    path: String,
+
+    /// The prefix that was stripped from the keys of the variables, such as `primary_`
+    prefix: String,
+
+    vars: Vec<EnvVar<'de>>,
+}
+
+/// The variables handed out by [`EnvVarMapAccess`]
+#[derive(Debug)]
+enum Vars<'de, Iter> {
+    /// Gathered and deduplicated up front, see [`dedup`]
+    Buffered(std::vec::IntoIter<EnvVar<'de>>),
+
+    /// Read one at a time as the visitor asks for keys, see [`streams`],
+    /// along with the names of the variables already read, by their key
+    Streamed {
+        vars: EnvVars<'de, Iter>,
+        seen: HashMap<Cow<'de, str>, Cow<'de, str>>,
+    },
+}
+
+impl<'de, Iter> Iterator for Vars<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    type Item = Result<EnvVar<'de>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (vars, seen) = match self {
+            Vars::Buffered(vars) => return vars.next().map(Ok),
+            Vars::Streamed { vars, seen } => (vars, seen),
+        };
+        let options = Arc::clone(&vars.options);
+
+        for var in vars {
+            let Some(first) = seen.get(&var.key) else {
+                seen.insert(var.key.clone(), var.name.clone());
+                return Some(Ok(var));
+            };
+
+            match options.duplicate_keys {
+                DuplicateKeyPolicy::FirstWins => options.warn(Warning::ShadowedKey {
+                    kept: first.to_string(),
+                    shadowed: var.name.into_owned(),
+                }),
+                // `LastWins` needs every variable, so it's never streamed
+                DuplicateKeyPolicy::LastWins | DuplicateKeyPolicy::Error => {
+                    return Some(Err(Error::DuplicateKey {
+                        first: first.to_string(),
+                        second: var.name.into_owned(),
+                    }));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Whether the variables of a struct with `fields`, or of a map if it's `None`, can
+/// be read as the visitor asks for them instead of being gathered up front. They're
+/// gathered when the last of several variables with the same key wins, when some
+/// fields are read from prefixed variables, see [`scopes`], and when the unused ones
+/// are handed to [`crate::Builder::on_unused_key`], which [`lookup`] does in order
+fn streams(fields: Option<&[&str]>, options: &Options) -> bool {
+    let is_nested = |field: &&str| {
+        options.scope(field).is_some()
+            || options
+                .nested
+                .as_ref()
+                .is_some_and(|nested| nested.iter().any(|nested| nested == field))
+    };
+    let has_scopes = options.field_case.is_none()
+        && fields.is_some_and(|fields| fields.iter().any(is_nested));
+
+    options.duplicate_keys != DuplicateKeyPolicy::LastWins
+        && options.on_unused_key.is_none()
+        && !has_scopes
+}
+
+impl<'de, Iter> EnvVarMapAccess<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    fn new(
+        vars: EnvVars<'de, Iter>,
+        fields: Option<&'static [&'static str]>,
+    ) -> Result<Self> {
+        let options = Arc::clone(&vars.options);
+
+        if !streams(fields, &options) {
+            return Ok(Self::with_vars(dedup(vars)?, options, fields, ""));
+        }
+
+        Ok(Self {
+            vars: Vars::Streamed {
+                vars,
+                seen: HashMap::new(),
+            },
+            ..Self::with_vars(Vec::new(), options, fields, "")
+        })
+    }
+
+    /// Same as [`EnvVarMapAccess::new`], for variables that were already deduplicated,
+    /// which belong to the field at `path`, the top-level struct if it's empty
+    fn with_vars(
+        mut vars: Vec<EnvVar<'de>>,
+        options: Arc<Options>,
+        fields: Option<&'static [&'static str]>,
+        path: &str,
+    ) -> Self {
+        let capture_rest = options.capture_rest.clone().filter(|field| {
+            fields.is_some_and(|fields| fields.contains(&field.as_str()))
+        });
+
+        let scopes = match fields {
+            Some(fields) if options.field_case.is_none() => {
+                scopes(&mut vars, fields, capture_rest.as_deref(), path, &options)
+            }
+            _ => Vec::new(),
+        };
+        let mut skipped = Vec::new();
+
+        // Unless every variable is needed, look up the fields of the
+        // struct instead of handing the whole environment to the visitor
+        if let (Some(fields), None, false) =
+            (fields, &capture_rest, options.deny_unknown)
+        {
+            (vars, skipped) = lookup(vars, fields, &options);
+        }
+
+        let unknown = skipped
+            .iter()
+            .filter(|(name, _)| options.must_match.contains(name))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Self {
+            vars: Vars::Buffered(vars.into_iter()),
+            scopes: scopes.into_iter(),
+            options,
+            fields,
+            rest: capture_rest.as_ref().map(|_| Vec::new()),
+            capture_rest,
+            value: None,
+            current: None,
+            unknown,
+            skipped,
+            #[cfg(feature = "tracing")]
+            matched: Vec::new(),
+        }
+    }
+}
+
+impl<'de, Iter> EnvVarMapAccess<'de, Iter> {
+    /// Report the fields of the struct no variable was found for,
+    /// which fall back to their defaults, if they have any
+    #[cfg(feature = "tracing")]
+    fn trace_unset(&self) {
+        let rest = self.options.capture_rest.as_deref();
+
+        for field in self.fields.unwrap_or_default() {
+            if Some(*field) != rest && !self.matched.iter().any(|key| key == field) {
+                tracing::debug!(
+                    target: "renvar",
+                    field,
+                    "no variable found for field, falling back to its default"
+                );
+            }
+        }
+    }
+
+    /// Whether the variable will be handed to the visitor, either as a field,
+    /// as an entry of a map or as part of the `capture_rest` field
+    fn is_consumed(&self, key: &str) -> bool {
+        match self.fields {
+            Some(fields) => self.capture_rest.is_some() || fields.contains(&key),
+            None => true,
+        }
+    }
+
+    /// Validate the variable according to the settings in [`Options`]
+    fn check(&self, var: &EnvVar<'de>) -> Result<()> {
+        if !self.is_consumed(&var.key) {
+            return Ok(());
+        }
+
+        if self.options.empty_strings == EmptyStringPolicy::Error
+            && var.value.value.is_empty()
+        {
+            return Err(Error::EmptyValue(var.name.to_string()));
+        }
+
+        if self.options.reject_control_chars {
+            let offsets = control_char_offsets(&var.value.value);
+
+            if !offsets.is_empty() {
+                return Err(Error::ControlCharacters {
+                    key: var.name.to_string(),
+                    offsets,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Point out a variable with a similar name when a field is missing,
+    /// since it's likely a misspelling of it
+    fn suggest(&self, error: Error) -> Error {
+        let error = match error {
+            Error::MissingValue(field) => {
+                let similar = suggest::closest(
+                    &field,
+                    self.skipped.iter().map(|(_, key)| key.as_str()),
+                )
+                .and_then(|similar| {
+                    self.skipped
+                        .iter()
+                        .find(|(_, key)| key == similar)
+                });
+
+                match similar {
+                    Some((name, _)) => Error::SimilarKey {
+                        field,
+                        similar: name.clone(),
+                    },
+                    None => Error::MissingValue(field),
+                }
+            }
+            error => error,
+        };
+
+        // Missing fields belong to the field itself rather than to the key it was read from
+        match error {
+            Error::MissingValue(ref field) | Error::SimilarKey { ref field, .. }
+                if self.options.track_paths =>
+            {
+                let field = field.clone();
+                error.at(&field)
+            }
+            error => error,
+        }
+    }
+
+    /// Whether the variable should be held back for the `capture_rest` field
+    fn is_rest(&self, key: &str) -> bool {
+        match &self.capture_rest {
+            Some(capture_rest) => {
+                key == capture_rest
+                    || !self
+                        .fields
+                        .is_some_and(|fields| fields.contains(&key))
+            }
+            None => false,
+        }
+    }
+}
+
+impl<'de, Iter> de::MapAccess<'de> for EnvVarMapAccess<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        while let Some(var) = self.vars.next().transpose()? {
+            self.check(&var)?;
+
+            let EnvVar { name, key, value } = var;
+
+            // Variables that weren't left out by `lookup`, either because
+            // they're reported as unknown or because they're streamed
+            if !self.is_consumed(&key) {
+                if self.options.deny_unknown
+                    || self.options.must_match.contains(name.as_ref())
+                {
+                    self.unknown.push(name.to_string());
+                }
+
+                if !self.options.deny_unknown {
+                    self.skipped
+                        .push((name.into_owned(), key.into_owned()));
+                }
+                continue;
+            }
+
+            if self.is_rest(&key) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    target: "renvar",
+                    variable = %name,
+                    field = ?self.capture_rest,
+                    "variable captured by the rest field"
+                );
+
+                if let Some(rest) = self.rest.as_mut() {
+                    rest.push((name.into_owned(), value));
+                }
+                continue;
+            }
+
+            #[cfg(feature = "tracing")]
+            {
+                tracing::debug!(
+                    target: "renvar",
+                    variable = %name,
+                    key = %key,
+                    "variable matched"
+                );
+                self.matched.push(key.to_string());
+            }
+
+            if self.options.track_paths {
+                self.current = Some(key.to_string());
+            }
+
+            self.value = Some(match self.options.parser(&key, &name) {
+                Some(parser) => Pending::Parsed((parser.0)(&value.value)?),
+                None => Pending::Value(value),
+            });
+
+            // Keys are parsed like values, so maps can have keys such as integers
+            return seed
+                .deserialize(EnvVarValue::new(key, Arc::clone(&self.options)))
+                .map(Some);
+        }
+
+        if let Some(scope) = self.scopes.next() {
+            let field = scope
+                .path
+                .rsplit('.')
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+
+            #[cfg(feature = "tracing")]
+            self.matched.push(field.clone());
+
+            if self.options.track_paths {
+                self.current = Some(field.clone());
+            }
+
+            self.value = Some(Pending::Scope(scope));
+            return seed
+                .deserialize(field.into_deserializer())
+                .map(Some);
+        }
+
+        if !self.unknown.is_empty() {
+            return Err(Error::UnknownKeys(std::mem::take(&mut self.unknown)));
+        }
+
+        match (self.capture_rest.take(), self.rest.take()) {
+            (Some(field), Some(rest)) => {
+                if self.options.track_paths {
+                    self.current = Some(field.clone());
+                }
+
+                self.value = Some(Pending::Rest(rest));
+                seed.deserialize(field.into_deserializer())
+                    .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let value = match self.value.take() {
+            Some(Pending::Value(value)) => seed.deserialize(value),
+            Some(Pending::Parsed(value)) => seed.deserialize(value),
+            Some(Pending::Rest(rest)) => {
+                seed.deserialize(MapDeserializer::new(rest.into_iter()))
+            }
+            Some(Pending::Scope(scope)) => seed.deserialize(ScopeDeserializer {
+                scope,
+                options: Arc::clone(&self.options),
+            }),
+            None => Err(de::Error::custom(
+                "value is missing, next_value_seed called before next_key_seed",
+            )),
+        };
+
+        match self.current.take() {
+            Some(key) => value.map_err(|error| error.at(&key)),
+            None => value,
+        }
+    }
+
+    // The type of the value is known here, unlike in `next_value_seed`,
+    // which tells whether an empty variable deserializes as `Some`
+    fn next_value<V>(&mut self) -> Result<V>
+    where
+        V: de::Deserialize<'de>,
+    {
+        self.value = match self.value.take() {
+            Some(Pending::Value(value)) => Some(Pending::Value(value.expecting::<V>())),
+            pending => pending,
+        };
+
+        self.next_value_seed(PhantomData)
+    }
+
+    fn next_entry<K, V>(&mut self) -> Result<Option<(K, V)>>
+    where
+        K: de::Deserialize<'de>,
+        V: de::Deserialize<'de>,
+    {
+        match self.next_key()? {
+            Some(key) => Ok(Some((key, self.next_value()?))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Resolve the variables whose keys collide once lowercased, such as
+/// `APP_key` and `APP_KEY` or a key repeated in a `.env` file
+fn dedup<'de, Iter>(vars: EnvVars<'de, Iter>) -> Result<Vec<EnvVar<'de>>>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    let options = Arc::clone(&vars.options);
+    let mut deduped: Vec<EnvVar<'de>> = Vec::new();
+    let mut seen: HashMap<Cow<'de, str>, usize> = HashMap::new();
+
+    for var in vars {
+        match seen.get(&var.key) {
+            Some(&index) => match options.duplicate_keys {
+                DuplicateKeyPolicy::FirstWins => options.warn(Warning::ShadowedKey {
+                    kept: deduped[index].name.to_string(),
+                    shadowed: var.name.into_owned(),
+                }),
+                DuplicateKeyPolicy::LastWins => {
+                    options.warn(Warning::ShadowedKey {
+                        kept: var.name.to_string(),
+                        shadowed: deduped[index].name.to_string(),
+                    });
+                    deduped[index] = var;
+                }
+                DuplicateKeyPolicy::Error => {
+                    let first: &EnvVar<'de> = &deduped[index];
+
+                    return Err(Error::DuplicateKey {
+                        first: first.name.to_string(),
+                        second: var.name.into_owned(),
+                    });
+                }
+            },
+            None => {
+                seen.insert(var.key.clone(), deduped.len());
+                deduped.push(var);
+            }
+        }
+    }
+
+    Ok(deduped)
+}
+
+/// Take the variables of the fields no variable is named after out of `vars`, which are
+/// the ones prefixed with the name of the field, such as `DB_HOST` for a `db` field,
+/// unless they match another field. The prefix is stripped from their keys.
+/// Only the fields listed in [`Options::nested`] are considered, so that the
+/// variables of an unset scalar field, such as `TERM_PROGRAM` for a `term`
+/// field, are left alone.
+///
+/// Fields given a prefix with [`crate::Builder::scope`] take the variables
+/// with that prefix instead, and the variable named after them is ignored.
+fn scopes<'de>(
+    vars: &mut Vec<EnvVar<'de>>,
+    fields: &'static [&'static str],
+    capture_rest: Option<&str>,
+    parent: &str,
+    options: &Options,
+) -> Vec<Scope<'de>> {
+    let mut scopes = Vec::new();
+
+    for field in fields {
+        let path = match parent {
+            "" => field.to_string(),
+            parent => format!("{}.{}", parent, field),
+        };
+        let explicit = options
+            .scope(&path)
+            .map(|prefix| field_key(prefix, options));
+
+        let implicit = options
+            .nested
+            .as_ref()
+            .is_some_and(|nested| nested.contains(&path));
+
+        if Some(*field) == capture_rest
+            || explicit.is_none() && !implicit
+            || explicit.is_none() && vars.iter().any(|var| var.key == *field)
+        {
+            continue;
+        }
+
+        if explicit.is_some() {
+            vars.retain(|var| var.key != *field);
+        }
+
+        let prefix = explicit
+            .clone()
+            .unwrap_or_else(|| format!("{}_", field));
+        let (scoped, rest): (Vec<_>, Vec<_>) =
+            std::mem::take(vars).into_iter().partition(|var| {
+                var.key.starts_with(&prefix)
+                    && (explicit.is_some() || !fields.contains(&var.key.as_ref()))
+            });
+        *vars = rest;
+
+        if !scoped.is_empty() {
+            let vars = scoped
+                .into_iter()
+                .map(|var| var.scoped(prefix.len()))
+                .collect();
+
+            scopes.push(Scope { path, prefix, vars });
+        }
+    }
+
+    scopes
+}
+
+/// Pick the variables that belong to the `fields` of a struct, in the order of
+/// the fields. Since `fields` includes the aliases declared with `#[serde(alias)]`,
+/// every variable the struct could consume is kept.
+///
+/// Also returns the `(name, key)` of the variables that were left out,
+/// after handing them to the callback set with [`crate::Builder::on_unused_key`].
+fn lookup<'de>(
+    vars: Vec<EnvVar<'de>>,
+    fields: &[&str],
+    options: &Options,
+) -> (Vec<EnvVar<'de>>, Vec<(String, String)>) {
+    let mut by_key: HashMap<Cow<'de, str>, EnvVar<'de>> = vars
+        .into_iter()
+        .map(|var| (var.key.clone(), var))
+        .collect();
+
+    let found = fields
+        .iter()
+        .filter_map(|field| by_key.remove(*field))
+        .collect();
+
+    let mut skipped: Vec<EnvVar<'de>> = by_key.into_values().collect();
+    skipped.sort_by(|a, b| (&a.name, &a.key).cmp(&(&b.name, &b.key)));
+
+    if let Some(on_unused_key) = &options.on_unused_key {
+        for var in &skipped {
+            (on_unused_key.0)(&var.name, &var.value.value);
+        }
+    }
+
+    let skipped = skipped
+        .into_iter()
+        .map(|var| (var.name.into_owned(), var.key.into_owned()))
+        .collect();
+
+    (found, skipped)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserializer for a field made of the variables prefixed with its name,
+/// such as `DB_HOST` and `DB_PORT` for a `db` field, which is expected
+/// to be a struct or a map. Scopes nest, so `DB_PRIMARY_HOST` reaches
+/// the `host` field of the `primary` field of `db`.
+///
+/// Fields that are missing from the scope are reported with the
+/// prefix of the scope, such as `db_port` for the `port` field of `db`
+#[derive(Debug)]
+struct ScopeDeserializer<'de> {
+    scope: Scope<'de>,
+    options: Arc<Options>,
+}
+
+impl ScopeDeserializer<'_> {
+    /// Name the missing fields after the scope they belong to
+    fn scope_error(&self, error: Error) -> Error {
+        match error {
+            Error::AtPath { path, error } => Error::AtPath {
+                path,
+                error: Box::new(self.scope_error(*error)),
+            },
+            Error::MissingValue(field) => {
+                Error::MissingValue(format!("{}{}", self.scope.prefix, field))
+            }
+            Error::SimilarKey { field, similar } => Error::SimilarKey {
+                field: format!("{}{}", self.scope.prefix, field),
+                similar,
+            },
+            error => error,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ScopeDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let access: EnvVarMapAccess<'de> = EnvVarMapAccess::with_vars(
+            self.scope.vars,
+            self.options,
+            None,
+            &self.scope.path,
+        );
+
+        visitor.visit_map(access)
+    }
+
+    fn deserialize_struct<V>(
+        mut self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let _ = name;
+
+        let vars = std::mem::take(&mut self.scope.vars);
+        let mut access: EnvVarMapAccess<'de> = EnvVarMapAccess::with_vars(
+            vars,
+            Arc::clone(&self.options),
+            Some(fields),
+            &self.scope.path,
+        );
+
+        let value = visitor
+            .visit_map(&mut access)
+            .map_err(|error| self.scope_error(access.suggest(error)))?;
+
+        #[cfg(feature = "tracing")]
+        access.trace_unset();
+
+        Ok(value)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let _ = name;
+
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf unit_struct tuple_struct
+        identifier tuple ignored_any enum
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserializer for environment variables
+///
+/// Can be constructed from a type that implements [`Iterator`] over:
+///
+/// - `(String, String)` tuples, such as [`std::env::vars`]
+/// - `(OsString, OsString)` tuples, such as [`std::env::vars_os`],
+///   see [`OsEnvVarDeserializer`]
+/// - `(&str, Cow<str>)` tuples borrowed from their input, see [`BorrowedEnvVarDeserializer`]
+///
+/// Alternatively, can be constructed from a [`str`] using [`crate::from_str`]
+#[derive(Debug)]
+pub struct EnvVarDeserializer<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    vars: EnvVars<'de, Iter>,
+}
+
+impl<'de, Iter> EnvVarDeserializer<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    /// Construct an [`EnvVarDeserializer`] from an [`Iterator`] over `(key, value)` tuples
+    pub fn new(iter: Iter) -> Self {
+        Self::with_options(iter, Options::default())
+    }
+
+    /// Construct an [`EnvVarDeserializer`] that honours the settings
+    /// collected by a [`crate::Builder`]
+    pub(crate) fn with_options(iter: Iter, options: Options) -> Self {
+        Self {
+            vars: EnvVars {
+                iter,
+                options: Arc::new(options),
+                marker: PhantomData,
+            },
+        }
+    }
+
+    /// What to do when several variables have the same key once lowercased,
+    /// which keeps the last of them by default. With [`DuplicateKeyPolicy::FirstWins`]
+    /// and [`DuplicateKeyPolicy::Error`], the variables of a struct without nested
+    /// fields are taken from `iter` as they're deserialized instead of all at once
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{de::EnvVarDeserializer, DuplicateKeyPolicy};
+    /// use serde::Deserialize;
+    /// use std::borrow::Cow;
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq, Eq)]
+    /// struct CustomStruct<'a> {
+    ///     host: &'a str,
+    /// }
+    ///
+    /// let iter = vec![
+    ///     ("HOST", Cow::Borrowed("localhost")),
+    ///     ("host", Cow::Borrowed("example.com")),
+    /// ];
+    ///
+    /// let de = EnvVarDeserializer::new(iter.into_iter())
+    ///     .duplicate_keys(DuplicateKeyPolicy::FirstWins);
+    ///
+    /// assert_eq!(
+    ///     CustomStruct::deserialize(de).unwrap(),
+    ///     CustomStruct { host: "localhost" }
+    /// );
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        Arc::make_mut(&mut self.vars.options).duplicate_keys = policy;
+        self
+    }
+
+    /// Read the fields of `T` that hold structs or maps, such as `db`,
+    /// from the variables prefixed with their name, such as `DB_HOST`
+    pub(crate) fn nested<T>(mut self) -> Self
+    where
+        T: de::Deserialize<'de>,
+    {
+        self.vars.nest::<T>();
+        self
+    }
+}
+
+impl<'de, Iter> de::Deserializer<'de> for EnvVarDeserializer<'de, Iter>
+where
+    Iter: Iterator,
+    Iter::Item: RawEnvVar<'de>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_map(EnvVarMapAccess::new(self.vars, None)?)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let _ = name;
+
+        let mut access = EnvVarMapAccess::new(self.vars, Some(fields))?;
+
+        let value = visitor
+            .visit_map(&mut access)
+            .map_err(|error| access.suggest(error))?;
+
+        #[cfg(feature = "tracing")]
+        access.trace_unset();
+
+        Ok(value)
+    }
+
+    /// `None` if there are no variables, such as when none of them has
+    /// the prefix of an optional section, `Some` of the rest otherwise
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.vars.any_belongs() {
+            (true, vars) => visitor.visit_some(EnvVarDeserializer { vars }),
+            (false, _) => visitor.visit_none(),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf unit_struct tuple_struct
+        identifier tuple ignored_any newtype_struct enum
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// [`EnvVarDeserializer`] over variables that may contain invalid unicode,
+/// such as the ones of [`std::env::vars_os`]
+///
+/// Contrary to `(String, String)` tuples, values that aren't valid unicode are
+/// kept intact and handed to `OsString` fields as they are. Deserializing
+/// such a value into any other type fails with [`Error::InvalidUnicode`].
+///
+/// Note that `PathBuf` is always deserialized from a unicode string by serde,
+/// so paths that aren't valid unicode should be read into an `OsString` first.
+///
+/// # Example
+///
+/// ```
+/// use renvar::de::OsEnvVarDeserializer;
+/// use serde::Deserialize;
+/// use std::ffi::OsString;
+///
+/// #[derive(Deserialize, Debug, PartialEq, Eq)]
+/// struct CustomStruct {
+///     path: OsString,
+/// }
+///
+/// let iter = vec![(OsString::from("path"), OsString::from("/tmp"))];
+///
+/// let de = OsEnvVarDeserializer::new(iter.into_iter());
+///
+/// let custom_struct = CustomStruct::deserialize(de).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         path: OsString::from("/tmp")
+///     }
+/// );
+/// ```
+pub type OsEnvVarDeserializer<'de, Iter> = EnvVarDeserializer<'de, Iter>;
+
+/// [`EnvVarDeserializer`] over variables borrowed from their input,
+/// which is what [`crate::from_str`] uses
+///
+/// Contrary to `(String, String)` tuples, values are handed to the visitor as they are
+/// borrowed, so structs can hold `&str` fields, or `Cow<str>` fields marked with
+/// `#[serde(borrow)]`, that point into the input instead of allocating a [`String`]
+/// for each of them. Elements of sequences and tuples are borrowed as well.
+///
+/// # Example
+///
+/// ```
+/// use renvar::de::BorrowedEnvVarDeserializer;
+/// use serde::Deserialize;
+/// use std::borrow::Cow;
+///
+/// #[derive(Deserialize, Debug, PartialEq, Eq)]
+/// struct CustomStruct<'a> {
+///     name: &'a str,
+///     hosts: Vec<&'a str>,
+/// }
+///
+/// let input = String::from("renvar,localhost,example.com");
+/// let (name, hosts) = input.split_once(',').unwrap();
+///
+/// let iter = vec![("name", Cow::Borrowed(name)), ("hosts", Cow::Borrowed(hosts))];
+///
+/// let de = BorrowedEnvVarDeserializer::new(iter.into_iter());
+///
+/// let custom_struct = CustomStruct::deserialize(de).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         name: "renvar",
+///         hosts: vec!["localhost", "example.com"]
+///     }
+/// );
+/// ```
+pub type BorrowedEnvVarDeserializer<'de, Iter> = EnvVarDeserializer<'de, Iter>;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserialize a value of some type `T` from a string, parsing it with [`std::str::FromStr`],
+/// for use with `#[serde(deserialize_with = "renvar::de::parse")]`.
+///
+/// Serde buffers the values of `#[serde(flatten)]` fields, internally tagged enums
+/// and untagged enums before knowing their types, and those values are always
+/// strings, since that's all a variable holds. Fields holding anything other than
+/// strings there, such as numbers or booleans, are parsed from them with this function.
+///
+/// # Errors
+///
+/// If the value isn't a string, or `T` can't be parsed from it
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_iter;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct Database {
+///     host: String,
+///     #[serde(deserialize_with = "renvar::de::parse")]
+///     port: u16,
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct App {
+///     name: String,
+///     #[serde(flatten)]
+///     database: Database,
+/// }
+///
+/// let vars = vec![
+///     (String::from("NAME"), String::from("api")),
+///     (String::from("HOST"), String::from("localhost")),
+///     (String::from("PORT"), String::from("5432")),
+/// ];
+///
+/// assert_eq!(
+///     from_iter::<App, _>(vars).unwrap(),
+///     App {
+///         name: String::from("api"),
+///         database: Database {
+///             host: String::from("localhost"),
+///             port: 5432,
+///         },
+///     }
+/// );
+/// ```
+pub fn parse<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = <Cow<'de, str> as de::Deserialize>::deserialize(deserializer)?;
+
+    value.parse().map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+    use std::path::PathBuf;
+
+    use crate::{from_iter, Error};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Unit;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct NewType(u64);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Test {
+        string_field: String,
+        empty_string_field: String,
+        sequence: Vec<String>,
+        empty_sequence_doublequote: Vec<String>,
+        empty_sequence_singlequote: Vec<String>,
+        empty_sequence_whitespace: Vec<String>,
+        unit: Unit,
+        newtype: NewType,
+        optional_field: Option<String>,
+    }
+
+    #[test]
+    #[allow(clippy::useless_conversion)]
+    fn test_from_iter() {
+        let iter = vec![
+            (String::from("string_field"), String::from("hello")),
+            (String::from("empty_string_field"), String::from("")),
+            (String::from("sequence"), String::from("first,second,third")),
+            (
+                String::from("empty_sequence_doublequote"),
                 String::from("\"\""),
             ),
             (
@@ -323,89 +2052,1059 @@ mod tests {
             (String::from("optional_field"), String::from("")),
         ];
 
-        let test_struct = from_iter::<Test, _>(iter.into_iter()).unwrap();
+        let test_struct = from_iter::<Test, _>(iter.into_iter()).unwrap();
+
+        assert_eq!(
+            test_struct,
+            Test {
+                string_field: String::from("hello"),
+                empty_string_field: String::from(""),
+                sequence: vec![
+                    String::from("first"),
+                    String::from("second"),
+                    String::from("third")
+                ],
+                empty_sequence_doublequote: vec![],
+                empty_sequence_singlequote: vec![],
+                empty_sequence_whitespace: vec![],
+                unit: Unit,
+                newtype: NewType(62875),
+                optional_field: None
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_os_env_invalid_unicode() {
+        use super::OsEnvVarDeserializer;
+        use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Paths {
+            path: OsString,
+            name: String,
+        }
+
+        let invalid = OsString::from_vec(vec![b'/', b't', 0xff, b'p']);
+
+        let iter = vec![
+            (OsString::from("PATH"), invalid.clone()),
+            (OsString::from("NAME"), OsString::from("renvar")),
+        ];
+
+        let actual = Paths::deserialize(OsEnvVarDeserializer::new(iter.into_iter()));
+
+        assert_eq!(
+            actual.unwrap(),
+            Paths {
+                path: invalid.clone(),
+                name: String::from("renvar"),
+            }
+        );
+
+        let iter = vec![
+            (OsString::from("PATH"), OsString::from("/tmp")),
+            (OsString::from("NAME"), invalid.clone()),
+        ];
+
+        let actual = Paths::deserialize(OsEnvVarDeserializer::new(iter.into_iter()));
+
+        assert_eq!(actual.unwrap_err(), Error::InvalidUnicode(invalid));
+    }
+
+    #[test]
+    fn test_enum_named_os_string() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum OsString {
+            Linux,
+            Mac,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Target {
+            os: OsString,
+        }
+
+        let vars = vec![(String::from("OS"), String::from("Mac"))];
+
+        assert_eq!(
+            from_iter::<Target, _>(vars),
+            Ok(Target { os: OsString::Mac })
+        );
+    }
+
+    #[test]
+    #[allow(clippy::cmp_owned)]
+    fn test_failed_unit_struct_parsing() {
+        let iter = vec![
+            (String::from("string_field"), String::from("hello")),
+            (String::from("empty_string_field"), String::from("")),
+            (String::from("sequence"), String::from("first,second,third")),
+            (
+                String::from("empty_sequence_doublequote"),
+                String::from("\"\""),
+            ),
+            (
+                String::from("empty_sequence_singlequote"),
+                String::from("\'\'"),
+            ),
+            (String::from("empty_sequence_whitespace"), String::from(" ")),
+            (String::from("unit"), String::from("Uni")),
+            (String::from("newtype"), String::from("62875")),
+            (String::from("optional_field"), String::from("")),
+        ];
+
+        let actual = from_iter::<Test, _>(iter);
+
+        if let Err(error) = actual {
+            assert!(
+                error.to_string()
+                    == String::from(
+                        "expected unit struct with name 'Unit', found 'Uni'"
+                    )
+            )
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cmp_owned)]
+    fn test_failed_newtype_struct_parsing() {
+        let iter = vec![
+            (String::from("string_field"), String::from("hello")),
+            (String::from("empty_string_field"), String::from("")),
+            (String::from("sequence"), String::from("first,second,third")),
+            (
+                String::from("empty_sequence_doublequote"),
+                String::from("\"\""),
+            ),
+            (
+                String::from("empty_sequence_singlequote"),
+                String::from("\'\'"),
+            ),
+            (String::from("empty_sequence_whitespace"), String::from(" ")),
+            (String::from("unit"), String::from("Unit")),
+            (String::from("newtype"), String::from("62875abc")),
+            (String::from("optional_field"), String::from("")),
+        ];
+
+        let actual = from_iter::<Test, _>(iter);
+
+        if let Err(error) = actual {
+            assert!(
+                error.to_string()
+                    == String::from(
+                        "invalid digit found in string while parsing value '62875abc'",
+                    )
+            )
+        }
+    }
+
+    #[test]
+    fn test_struct_field_lookup() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        #[serde(deny_unknown_fields)]
+        struct Database {
+            #[serde(alias = "database_url")]
+            url: String,
+            pool_size: Option<u32>,
+        }
+
+        let iter = vec![
+            (String::from("HOME"), String::from("/root")),
+            (
+                String::from("DATABASE_URL"),
+                String::from("postgres://localhost"),
+            ),
+            (String::from("PATH"), String::from("/usr/bin")),
+        ];
+
+        assert_eq!(
+            from_iter::<Database, _>(iter).unwrap(),
+            Database {
+                url: String::from("postgres://localhost"),
+                pool_size: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_tuple() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point(f64, f64);
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Shapes {
+            origin: Point,
+            range: (u8, u8),
+        }
+
+        let iter = vec![
+            (String::from("origin"), String::from("1.5, -2")),
+            (String::from("range"), String::from("1,10")),
+        ];
+
+        assert_eq!(
+            from_iter::<Shapes, _>(iter).unwrap(),
+            Shapes {
+                origin: Point(1.5, -2.0),
+                range: (1, 10)
+            }
+        );
+
+        let iter = vec![
+            (String::from("origin"), String::from("1.5")),
+            (String::from("range"), String::from("1,10")),
+        ];
+
+        assert!(from_iter::<Shapes, _>(iter).is_err());
+    }
+
+    #[test]
+    fn test_sets_and_ordered_collections() {
+        use crate::builder;
+        use std::collections::{BTreeSet, HashSet, VecDeque};
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Collections {
+            tags: HashSet<String>,
+            ports: BTreeSet<u16>,
+            queue: VecDeque<String>,
+            hosts: Option<HashSet<String>>,
+        }
+
+        let vars = vec![
+            (String::from("TAGS"), String::from("web,api,web")),
+            (String::from("PORTS"), String::from("443, 80, 443")),
+            (String::from("QUEUE"), String::from("b,a,b")),
+        ];
+
+        assert_eq!(
+            from_iter::<Collections, _>(vars.clone()).unwrap(),
+            Collections {
+                tags: HashSet::from([String::from("web"), String::from("api")]),
+                ports: BTreeSet::from([80, 443]),
+                queue: VecDeque::from([
+                    String::from("b"),
+                    String::from("a"),
+                    String::from("b"),
+                ]),
+                hosts: None,
+            }
+        );
+
+        let builder = builder().deny_duplicate_elements(true);
+
+        assert_eq!(
+            builder.from_iter::<Collections, _>(vars),
+            Err(Error::DuplicateElement(String::from("web")))
+        );
+        assert_eq!(
+            builder.from_iter::<Collections, _>(vec![
+                (String::from("TAGS"), String::from("web")),
+                (String::from("PORTS"), String::from("80")),
+                (String::from("QUEUE"), String::from("b,a")),
+                (String::from("HOSTS"), String::from("a,a")),
+            ]),
+            Err(Error::DuplicateElement(String::from("a")))
+        );
+        assert_eq!(
+            builder.from_iter::<Collections, _>(vec![
+                (String::from("TAGS"), String::from("web")),
+                (String::from("PORTS"), String::from("80")),
+                (String::from("QUEUE"), String::from("b,a,b")),
+            ]),
+            Err(Error::DuplicateElement(String::from("b")))
+        );
+
+        // Elements are compared as they're written, not once parsed
+        assert_eq!(
+            builder
+                .from_iter::<Collections, _>(vec![
+                    (String::from("TAGS"), String::from("web")),
+                    (String::from("PORTS"), String::from("80,080")),
+                    (String::from("QUEUE"), String::from("b")),
+                ])
+                .unwrap()
+                .ports,
+            BTreeSet::from([80])
+        );
+    }
+
+    #[test]
+    fn test_enum_variants_with_data() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Cache {
+            Memory,
+            File(PathBuf),
+            Redis { host: IpAddr, port: u16 },
+            Tiered(u32, u32),
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Caches {
+            primary: Cache,
+            secondary: Cache,
+            fallback: Cache,
+            local: Cache,
+        }
+
+        let iter = vec![
+            (
+                String::from("primary"),
+                String::from("redis: host=127.0.0.1, PORT=6379"),
+            ),
+            (String::from("secondary"), String::from("file:/tmp/cache")),
+            (String::from("fallback"), String::from("memory")),
+            (String::from("local"), String::from("tiered:64,1024")),
+        ];
+
+        assert_eq!(
+            from_iter::<Caches, _>(iter).unwrap(),
+            Caches {
+                primary: Cache::Redis {
+                    host: IpAddr::from([127, 0, 0, 1]),
+                    port: 6379
+                },
+                secondary: Cache::File(PathBuf::from("/tmp/cache")),
+                fallback: Cache::Memory,
+                local: Cache::Tiered(64, 1024),
+            }
+        );
+
+        let error = |value: &str| {
+            from_iter::<HashMap<String, Cache>, _>(vec![(
+                String::from("cache"),
+                value.to_owned(),
+            )])
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert_eq!(
+            error("file"),
+            "expected data for variant `file`, written as `file:...`"
+        );
+        assert_eq!(
+            error("memory:1GB"),
+            "unexpected data '1GB' for unit variant `memory`"
+        );
+        assert_eq!(
+            error("redis:localhost"),
+            "expected `field=value` while parsing 'localhost'"
+        );
+        assert_eq!(
+            error("disk:/tmp"),
+            "unknown variant `disk`, expected one of `memory`, `file`, `redis`, `tiered`"
+        );
+    }
+
+    #[test]
+    fn test_other_variant() {
+        #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+        #[serde(rename_all = "lowercase")]
+        enum Level {
+            Debug,
+            Info,
+            #[serde(other)]
+            Unknown,
+        }
+
+        let level = |value: &str, lenient: bool| {
+            crate::builder()
+                .lenient_variants(lenient)
+                .from_iter::<HashMap<String, Level>, _>(vec![(
+                    String::from("level"),
+                    value.to_owned(),
+                )])
+                .map(|levels| levels["level"].clone())
+        };
+
+        assert_eq!(level("info", false), Ok(Level::Info));
+        assert_eq!(level("trace", false), Ok(Level::Unknown));
+        assert_eq!(level("trace:1", false), Ok(Level::Unknown));
+        assert_eq!(level("DEBUG", true), Ok(Level::Debug));
+        assert_eq!(level("Debug", false), Ok(Level::Unknown));
+    }
+
+    #[test]
+    fn test_from_str_types() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        #[serde(try_from = "String")]
+        struct Host(String);
+
+        impl TryFrom<String> for Host {
+            type Error = String;
+
+            fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+                match value.contains('.') {
+                    true => Ok(Host(value)),
+                    false => Err(format!("'{}' is not a fully qualified host", value)),
+                }
+            }
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Listener {
+            separator: char,
+            ip: IpAddr,
+            addr: SocketAddr,
+            socket: PathBuf,
+            workers: usize,
+            offset: isize,
+            host: Host,
+        }
+
+        let vars = |separator: &str, host: &str| {
+            vec![
+                (String::from("SEPARATOR"), String::from(separator)),
+                (String::from("IP"), String::from("::1")),
+                (String::from("ADDR"), String::from("127.0.0.1:8080")),
+                (String::from("SOCKET"), String::from("/run/app.sock")),
+                (String::from("WORKERS"), String::from("8")),
+                (String::from("OFFSET"), String::from("-2")),
+                (String::from("HOST"), String::from(host)),
+            ]
+        };
 
         assert_eq!(
-            test_struct,
-            Test {
-                string_field: String::from("hello"),
-                empty_string_field: String::from(""),
-                sequence: vec![
-                    String::from("first"),
-                    String::from("second"),
-                    String::from("third")
-                ],
-                empty_sequence_doublequote: vec![],
-                empty_sequence_singlequote: vec![],
-                empty_sequence_whitespace: vec![],
-                unit: Unit,
-                newtype: NewType(62875),
-                optional_field: None
+            from_iter::<Listener, _>(vars(";", "example.com")).unwrap(),
+            Listener {
+                separator: ';',
+                ip: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+                socket: PathBuf::from("/run/app.sock"),
+                workers: 8,
+                offset: -2,
+                host: Host(String::from("example.com")),
             }
         );
+
+        assert_eq!(
+            from_iter::<Listener, _>(vars(";;", "example.com")),
+            Err(Error::Custom(String::from(
+                "expected a single character while parsing value ';;'"
+            )))
+        );
+        assert_eq!(
+            from_iter::<Listener, _>(vars(";", "localhost")),
+            Err(Error::Custom(String::from(
+                "'localhost' is not a fully qualified host"
+            )))
+        );
     }
 
     #[test]
-    fn test_failed_unit_struct_parsing() {
-        let iter = vec![
-            (String::from("string_field"), String::from("hello")),
-            (String::from("empty_string_field"), String::from("")),
-            (String::from("sequence"), String::from("first,second,third")),
+    fn test_128_bit_integers() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Counters {
+            total: u128,
+            delta: i128,
+        }
+
+        let vars = vec![
             (
-                String::from("empty_sequence_doublequote"),
-                String::from("\"\""),
+                String::from("TOTAL"),
+                String::from("340282366920938463463374607431768211455"),
             ),
             (
-                String::from("empty_sequence_singlequote"),
-                String::from("\'\'"),
+                String::from("DELTA"),
+                String::from("-170141183460469231731687303715884105728"),
             ),
-            (String::from("empty_sequence_whitespace"), String::from(" ")),
-            (String::from("unit"), String::from("Uni")),
-            (String::from("newtype"), String::from("62875")),
-            (String::from("optional_field"), String::from("")),
         ];
 
-        let actual = from_iter::<Test, _>(iter);
+        assert_eq!(
+            from_iter::<Counters, _>(vars).unwrap(),
+            Counters {
+                total: u128::MAX,
+                delta: i128::MIN,
+            }
+        );
 
-        if let Err(error) = actual {
-            assert!(
-                error.to_string()
-                    == String::from(
-                        "expected unit struct with name 'Unit', found 'Uni'"
-                    )
-            )
-        }
+        let vars = vec![(
+            String::from("340282366920938463463374607431768211455"),
+            String::from("max"),
+        )];
+
+        assert_eq!(
+            from_iter::<HashMap<u128, String>, _>(vars).unwrap(),
+            HashMap::from([(u128::MAX, String::from("max"))])
+        );
     }
 
     #[test]
-    fn test_failed_newtype_struct_parsing() {
-        let iter = vec![
-            (String::from("string_field"), String::from("hello")),
-            (String::from("empty_string_field"), String::from("")),
-            (String::from("sequence"), String::from("first,second,third")),
+    fn test_human_readable_types() {
+        use chrono::{DateTime, Utc};
+        use url::Url;
+        use uuid::Uuid;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Deployment {
+            started_at: DateTime<Utc>,
+            id: Uuid,
+            endpoint: Url,
+            ids: Vec<Uuid>,
+        }
+
+        let vars = vec![
             (
-                String::from("empty_sequence_doublequote"),
-                String::from("\"\""),
+                String::from("STARTED_AT"),
+                String::from("2024-01-02T03:04:05Z"),
             ),
             (
-                String::from("empty_sequence_singlequote"),
-                String::from("\'\'"),
+                String::from("ID"),
+                String::from("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            ),
+            (
+                String::from("ENDPOINT"),
+                String::from("https://example.com:8443/api"),
+            ),
+            (
+                String::from("IDS"),
+                String::from("67e55044-10b1-426f-9247-bb680e5fe0c8"),
             ),
-            (String::from("empty_sequence_whitespace"), String::from(" ")),
-            (String::from("unit"), String::from("Unit")),
-            (String::from("newtype"), String::from("62875abc")),
-            (String::from("optional_field"), String::from("")),
         ];
 
-        let actual = from_iter::<Test, _>(iter);
+        let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let expected = Deployment {
+            started_at: "2024-01-02T03:04:05Z".parse().unwrap(),
+            id,
+            endpoint: Url::parse("https://example.com:8443/api").unwrap(),
+            ids: vec![id],
+        };
 
-        if let Err(error) = actual {
-            assert!(
-                error.to_string()
-                    == String::from(
-                        "invalid digit found in string while parsing value '62875abc'",
-                    )
-            )
+        assert_eq!(from_iter::<Deployment, _>(vars.clone()).unwrap(), expected);
+
+        let input = vars
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect::<String>();
+
+        assert_eq!(crate::from_str::<Deployment>(&input).unwrap(), expected);
+
+        let error = from_iter::<Deployment, _>(vec![(
+            String::from("STARTED_AT"),
+            String::from("yesterday"),
+        )])
+        .unwrap_err();
+
+        assert!(matches!(error, Error::Custom(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn test_str_visitors() {
+        use serde::de;
+        use std::fmt;
+
+        /// Accepts only the names of the log levels, through `visit_str`
+        #[derive(Debug, PartialEq, Eq)]
+        struct Level(String);
+
+        impl<'de> Deserialize<'de> for Level {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                struct LevelVisitor;
+
+                impl de::Visitor<'_> for LevelVisitor {
+                    type Value = Level;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("a log level")
+                    }
+
+                    fn visit_str<E>(self, value: &str) -> std::result::Result<Level, E>
+                    where
+                        E: de::Error,
+                    {
+                        match value {
+                            "debug" | "info" => Ok(Level(value.to_owned())),
+                            _ => {
+                                Err(E::invalid_value(de::Unexpected::Str(value), &self))
+                            }
+                        }
+                    }
+                }
+
+                deserializer.deserialize_identifier(LevelVisitor)
+            }
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Logging {
+            level: Level,
+            separator: char,
+        }
+
+        let vars = vec![
+            (String::from("LEVEL"), String::from("debug")),
+            (String::from("SEPARATOR"), String::from("|")),
+        ];
+
+        assert_eq!(
+            from_iter::<Logging, _>(vars).unwrap(),
+            Logging {
+                level: Level(String::from("debug")),
+                separator: '|',
+            }
+        );
+
+        let error = from_iter::<Logging, _>(vec![
+            (String::from("LEVEL"), String::from("trace")),
+            (String::from("SEPARATOR"), String::from("|")),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "invalid value: string \"trace\", expected a log level"
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Database {
+            host: String,
+            code: String,
+            #[serde(deserialize_with = "super::parse")]
+            port: u16,
+            #[serde(deserialize_with = "super::parse")]
+            timeout: f64,
+            #[serde(deserialize_with = "super::parse")]
+            tls: bool,
+            pool: Option<String>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct App {
+            name: String,
+            #[serde(flatten)]
+            database: Database,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Rest {
+            name: String,
+            #[serde(flatten)]
+            rest: HashMap<String, String>,
+        }
+
+        let vars = vec![
+            (String::from("NAME"), String::from("api")),
+            (String::from("HOST"), String::from("localhost")),
+            (String::from("CODE"), String::from("007")),
+            (String::from("PORT"), String::from("5432")),
+            (String::from("TIMEOUT"), String::from("2.5")),
+            (String::from("TLS"), String::from("true")),
+        ];
+
+        assert_eq!(
+            from_iter::<App, _>(vars.clone()).unwrap(),
+            App {
+                name: String::from("api"),
+                database: Database {
+                    host: String::from("localhost"),
+                    code: String::from("007"),
+                    port: 5432,
+                    timeout: 2.5,
+                    tls: true,
+                    pool: None,
+                },
+            }
+        );
+
+        // Values are passed as strings, whatever they look like
+        let rest = from_iter::<Rest, _>(vars).unwrap();
+
+        assert_eq!(rest.rest["code"], "007");
+        assert_eq!(rest.rest["port"], "5432");
+        assert_eq!(rest.rest["tls"], "true");
+    }
+
+    #[test]
+    fn test_tagged_enums() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "type", rename_all = "lowercase")]
+        enum Storage {
+            S3 {
+                bucket: String,
+                region: Option<String>,
+            },
+            Local {
+                path: PathBuf,
+                #[serde(deserialize_with = "super::parse")]
+                max_size: u64,
+            },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+        enum Listen {
+            Port(#[serde(deserialize_with = "super::parse")] u16),
+            Socket(PathBuf),
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct App {
+            storage: Storage,
+            listen: Listen,
+        }
+
+        let vars = vec![
+            (String::from("STORAGE_TYPE"), String::from("local")),
+            (String::from("STORAGE_PATH"), String::from("/var/cache")),
+            (String::from("STORAGE_MAX_SIZE"), String::from("1024")),
+            (String::from("LISTEN_VALUE"), String::from("8080")),
+            (String::from("LISTEN_KIND"), String::from("port")),
+        ];
+
+        // Enums may hold a single value, so they're only read from the prefix they're given
+        let builder = crate::builder()
+            .scope("storage", "STORAGE_")
+            .scope("listen", "LISTEN_");
+
+        assert!(from_iter::<App, _>(vars.clone()).is_err());
+        assert_eq!(
+            builder.from_iter::<App, _>(vars).unwrap(),
+            App {
+                storage: Storage::Local {
+                    path: PathBuf::from("/var/cache"),
+                    max_size: 1024,
+                },
+                listen: Listen::Port(8080),
+            }
+        );
+
+        let vars = vec![
+            (String::from("TYPE"), String::from("s3")),
+            (String::from("BUCKET"), String::from("assets")),
+        ];
+
+        assert_eq!(
+            from_iter::<Storage, _>(vars).unwrap(),
+            Storage::S3 {
+                bucket: String::from("assets"),
+                region: None,
+            }
+        );
+
+        let vars = vec![(String::from("TYPE"), String::from("gcs"))];
+
+        assert_eq!(
+            from_iter::<Storage, _>(vars),
+            Err(Error::Custom(String::from(
+                "unknown variant `gcs`, expected `s3` or `local`"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_untagged_enums() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Port {
+            Number(#[serde(deserialize_with = "super::parse")] u16),
+            Service(String),
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum Auth {
+            Token { token: String },
+            Basic { user: String, password: String },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct App {
+            port: Port,
+            admin_port: Option<Port>,
+            auth: Auth,
+        }
+
+        let vars = vec![
+            (String::from("PORT"), String::from("8080")),
+            (String::from("ADMIN_PORT"), String::from("http-alt")),
+            (String::from("AUTH_USER"), String::from("admin")),
+            (String::from("AUTH_PASSWORD"), String::from("hunter2")),
+        ];
+
+        // Without a prefix, untagged enums don't pick up the prefixed variables
+        let builder = crate::builder().scope("auth", "AUTH_");
+
+        assert_eq!(
+            from_iter::<App, _>(vars.clone()),
+            Err(Error::MissingValue(String::from("auth")))
+        );
+        assert_eq!(
+            builder.from_iter::<App, _>(vars).unwrap(),
+            App {
+                port: Port::Number(8080),
+                admin_port: Some(Port::Service(String::from("http-alt"))),
+                auth: Auth::Basic {
+                    user: String::from("admin"),
+                    password: String::from("hunter2"),
+                },
+            }
+        );
+
+        // Values that look like numbers still match string fields
+        let vars = vec![
+            (String::from("PORT"), String::from("https")),
+            (String::from("AUTH_TOKEN"), String::from("1234")),
+        ];
+
+        assert_eq!(
+            builder.from_iter::<App, _>(vars).unwrap(),
+            App {
+                port: Port::Service(String::from("https")),
+                admin_port: None,
+                auth: Auth::Token {
+                    token: String::from("1234"),
+                },
+            }
+        );
+        assert_eq!(
+            builder.from_iter::<App, _>(vec![
+                (String::from("PORT"), String::from("80")),
+                (String::from("AUTH_USER"), String::from("admin")),
+            ]),
+            Err(Error::Custom(String::from(
+                "data did not match any variant of untagged enum Auth"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_nested_scopes() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Replica {
+            host: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Database {
+            port: u16,
+            primary: Replica,
+            labels: HashMap<String, String>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct App {
+            db_name: String,
+            db: Database,
+            cache: Option<Replica>,
+        }
+
+        let vars = vec![
+            (String::from("DB_NAME"), String::from("users")),
+            (String::from("DB_PORT"), String::from("5432")),
+            (String::from("DB_PRIMARY_HOST"), String::from("10.0.0.1")),
+            (String::from("DB_LABELS_ENV"), String::from("prod")),
+        ];
+
+        assert_eq!(
+            from_iter::<App, _>(vars.clone()).unwrap(),
+            App {
+                db_name: String::from("users"),
+                db: Database {
+                    port: 5432,
+                    primary: Replica {
+                        host: String::from("10.0.0.1"),
+                    },
+                    labels: HashMap::from([(
+                        String::from("env"),
+                        String::from("prod")
+                    )]),
+                },
+                cache: None,
+            }
+        );
+
+        let error = from_iter::<App, _>(vec![
+            (String::from("DB_NAME"), String::from("users")),
+            (String::from("DB_PORT"), String::from("5432")),
+            (String::from("DB_PRIMARY_HOSTT"), String::from("10.0.0.1")),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            Error::SimilarKey {
+                field: String::from("db_primary_host"),
+                similar: String::from("DB_PRIMARY_HOSTT"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_prefixed_variables_of_unset_scalars() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Terminal {
+            term: Option<String>,
+            shell: Option<String>,
+        }
+
+        assert_eq!(
+            from_iter::<Terminal, _>(vec![(
+                String::from("TERM_PROGRAM"),
+                String::from("vscode")
+            )]),
+            Ok(Terminal {
+                term: None,
+                shell: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_track_paths() {
+        #[derive(Debug, Deserialize)]
+        struct Replica {
+            #[allow(dead_code)]
+            host: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Database {
+            #[allow(dead_code)]
+            port: u16,
+            #[allow(dead_code)]
+            ports: Vec<u16>,
+            #[allow(dead_code)]
+            primary: Replica,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct App {
+            #[allow(dead_code)]
+            db: Database,
+        }
+
+        let error_of = |vars: [(&str, &str); 3]| {
+            crate::builder()
+                .track_paths(true)
+                .from_iter::<App, _>(
+                    vars.map(|(key, value)| (key.to_owned(), value.to_owned())),
+                )
+                .unwrap_err()
+        };
+
+        let error = error_of([
+            ("DB_PORT", "5432"),
+            ("DB_PORTS", "5432, 5433"),
+            ("DB_PRIMARY_NAME", "main"),
+        ]);
+
+        assert_eq!(error.path(), Some("db.primary.host"));
+        assert_eq!(
+            error.to_string(),
+            "db.primary.host: missing value for db_primary_host"
+        );
+
+        let error = error_of([
+            ("DB_PORT", "5432"),
+            ("DB_PORTS", "5432, replica"),
+            ("DB_PRIMARY_HOST", "10.0.0.1"),
+        ]);
+
+        assert_eq!(error.path(), Some("db.ports[1]"));
+
+        let error = error_of([
+            ("DB_PORT", "primary"),
+            ("DB_PORTS", "5432"),
+            ("DB_PRIMARY_HOST", "10.0.0.1"),
+        ]);
+
+        assert_eq!(error.path(), Some("db.port"));
+        assert!(matches!(
+            error,
+            Error::AtPath { error, .. } if matches!(*error, Error::Custom(_))
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_keys_that_are_fields() {
+        use super::{borrowed_field_key, Options};
+        use std::borrow::Cow;
+
+        let options = Options::default();
+
+        // Lowercase keys are already fields, so no lowercase copy is made
+        assert!(matches!(
+            borrowed_field_key("db_host", &options),
+            Cow::Borrowed("db_host")
+        ));
+        assert!(matches!(
+            borrowed_field_key("DB_HOST", &options),
+            Cow::Owned(key) if key == "db_host"
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_vars_are_streamed() {
+        use super::BorrowedEnvVarDeserializer;
+        use crate::DuplicateKeyPolicy;
+        use std::borrow::Cow;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Server<'a> {
+            host: &'a str,
+            port: u16,
         }
+
+        let vars = || {
+            vec![
+                ("host", Cow::Borrowed("localhost")),
+                ("HOST", Cow::Borrowed("example.com")),
+                ("port", Cow::Borrowed("8080")),
+            ]
+            .into_iter()
+        };
+
+        // The duplicate is reported as soon as it's read, before reading any further
+        let de = BorrowedEnvVarDeserializer::new(
+            vars().chain(std::iter::from_fn(|| panic!("read past the duplicate"))),
+        )
+        .duplicate_keys(DuplicateKeyPolicy::Error);
+
+        assert_eq!(
+            Server::deserialize(de),
+            Err(Error::DuplicateKey {
+                first: String::from("host"),
+                second: String::from("HOST"),
+            })
+        );
+
+        let de = BorrowedEnvVarDeserializer::new(vars())
+            .duplicate_keys(DuplicateKeyPolicy::FirstWins);
+
+        assert_eq!(
+            Server::deserialize(de),
+            Ok(Server {
+                host: "localhost",
+                port: 8080,
+            })
+        );
+
+        // Variables that don't match a field are still kept for suggestions
+        let de = BorrowedEnvVarDeserializer::new(
+            vec![
+                ("host", Cow::Borrowed("localhost")),
+                ("PORTT", Cow::Borrowed("80")),
+            ]
+            .into_iter(),
+        )
+        .duplicate_keys(DuplicateKeyPolicy::FirstWins);
+
+        assert_eq!(
+            Server::deserialize(de),
+            Err(Error::SimilarKey {
+                field: String::from("port"),
+                similar: String::from("PORTT"),
+            })
+        );
     }
 }
@@ -0,0 +1,138 @@
+//! Decoding values that arrive encoded, such as binary data or multi-line
+//! keys given as base64 on platforms that only allow single-line values
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// How the value of a variable is decoded before it's deserialized.
+/// Set with [`crate::Builder::decode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decode {
+    /// Standard base64, with `+` and `/`
+    Base64,
+
+    /// URL-safe base64, with `-` and `_` instead of `+` and `/`
+    Base64Url,
+
+    /// Standard base64 of gzip compressed data, for large payloads such as
+    /// service account keys or CA bundles, decompressed with [flate2](https://docs.rs/flate2)
+    #[cfg(feature = "flate2")]
+    GzipBase64,
+}
+
+impl Decode {
+    /// The 6 bits `byte` stands for, if it belongs to the alphabet
+    fn sextet(self, byte: u8) -> Option<u8> {
+        match (self, byte) {
+            (_, b'A'..=b'Z') => Some(byte - b'A'),
+            (_, b'a'..=b'z') => Some(byte - b'a' + 26),
+            (_, b'0'..=b'9') => Some(byte - b'0' + 52),
+            (Decode::Base64Url, b'-') => Some(62),
+            (Decode::Base64Url, b'_') => Some(63),
+            (_, b'+') => Some(62),
+            (_, b'/') => Some(63),
+            _ => None,
+        }
+    }
+
+    /// Decode `value` into the bytes it encodes. Whitespace is skipped, so values
+    /// can be wrapped over several lines, and the trailing `=` padding is optional
+    pub(crate) fn decode(self, value: &str) -> Result<Vec<u8>, String> {
+        #[cfg(feature = "flate2")]
+        if self == Decode::GzipBase64 {
+            return gunzip(&Decode::Base64.decode(value)?);
+        }
+
+        let encoded: Vec<u8> = value
+            .bytes()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        let data = encoded
+            .iter()
+            .rposition(|&byte| byte != b'=')
+            .map_or(&encoded[..0], |last| &encoded[..=last]);
+
+        if encoded.len() - data.len() > 2 {
+            return Err(String::from("too much padding"));
+        }
+
+        let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+        let mut buffer = 0u32;
+        let mut bits = 0;
+
+        for (offset, &byte) in data.iter().enumerate() {
+            let sextet = self.sextet(byte).ok_or_else(|| {
+                format!(
+                    "invalid character '{}' at offset {}",
+                    char::from(byte),
+                    offset
+                )
+            })?;
+
+            buffer = buffer << 6 | u32::from(sextet);
+            bits += 6;
+
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+                buffer &= (1 << bits) - 1;
+            }
+        }
+
+        // A lone character left over can't encode a whole byte, and the bits
+        // that didn't make up one must be zero in canonical encodings
+        if data.len() % 4 == 1 || buffer != 0 {
+            return Err(String::from("invalid length or trailing bits"));
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Decompress gzip compressed `bytes`
+#[cfg(feature = "flate2")]
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|error| format!("invalid gzip data: {}", error))?;
+
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Decode;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Decode::Base64.decode("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(Decode::Base64.decode("aGVsbG8").unwrap(), b"hello");
+        assert_eq!(Decode::Base64.decode("aGVs\n bG8h").unwrap(), b"hello!");
+        assert_eq!(Decode::Base64.decode("").unwrap(), b"");
+        assert_eq!(Decode::Base64.decode("//79").unwrap(), [0xff, 0xfe, 0xfd]);
+        assert_eq!(
+            Decode::Base64Url.decode("__79").unwrap(),
+            [0xff, 0xfe, 0xfd]
+        );
+
+        assert!(Decode::Base64.decode("__79").is_err());
+        assert!(Decode::Base64.decode("aGVsbG9").is_err());
+        assert!(Decode::Base64.decode("aGVsbG8===").is_err());
+        assert!(Decode::Base64.decode("a").is_err());
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_decode_gzip() {
+        assert_eq!(
+            Decode::GzipBase64
+                .decode("H4sIAAAAAAAAA8tIzcnJBwCGphA2BQAAAA==")
+                .unwrap(),
+            b"hello"
+        );
+        assert!(Decode::GzipBase64.decode("aGVsbG8=").is_err());
+    }
+}
@@ -0,0 +1,620 @@
+//! Learning the fields of a struct and the shape of their values
+//! without any input, for checks that run before deserialization
+//!
+//! Serde gives no way of inspecting a type, but the derived `Deserialize` impls tell
+//! the deserializer which fields they expect, and what kind of value each of them wants
+//! when it's asked for. The struct is probed once for its fields and then once per field,
+//! every probe being cut short with an error as soon as it has learnt what it was after.
+//!
+//! Since that takes a deserialization per field, the [`Layout`] the deserializers
+//! need is learnt once per type and kept for the lifetime of the process.
+
+use crate::{Error, Result};
+use serde::de::{self, Deserialize};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// What a field of the probed struct expects, as far as the deserializer can tell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FieldInfo {
+    /// The name of the field, as serde knows it
+    pub(crate) name: &'static str,
+
+    /// The kind of value the field expects
+    pub(crate) shape: Shape,
+
+    /// Whether the field is an [`Option`]
+    pub(crate) optional: bool,
+}
+
+/// The kind of value a field expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Shape {
+    /// A single value, such as a number, a string or a unit enum variant,
+    /// named after the `deserialize_*` method that was called
+    Scalar(&'static str),
+
+    /// A sequence or a tuple, read from delimited values
+    Seq,
+
+    /// A map or a struct
+    Map,
+
+    /// Anything, the field didn't say
+    Any,
+}
+
+impl Shape {
+    /// A short name for the kind of value, such as `u16` or `seq`
+    pub(crate) fn hint(&self) -> &'static str {
+        match self {
+            Shape::Scalar(name) => name,
+            Shape::Seq => "seq",
+            Shape::Map => "map",
+            Shape::Any => "any",
+        }
+    }
+
+    /// Whether the field holds a single value, which can't be
+    /// spread across several variables
+    pub(crate) fn is_scalar(&self) -> bool {
+        matches!(self, Shape::Scalar(_) | Shape::Seq)
+    }
+}
+
+/// How deeply [`nested_paths`] looks into nested structs, which
+/// keeps recursive types, such as `Option<Box<Self>>`, from looping forever
+const MAX_DEPTH: usize = 16;
+
+/// Learn the fields of the struct `T`.
+///
+/// Returns an empty list for anything other than a struct with named fields,
+/// including structs with flattened fields, which serde reads as maps
+pub(crate) fn describe<'de, T>() -> Vec<FieldInfo>
+where
+    T: Deserialize<'de>,
+{
+    describe_at::<T>(&[])
+}
+
+/// What the deserializers need to know about the struct they deserialize,
+/// learnt with [`layout`]
+#[derive(Debug, Default)]
+pub(crate) struct Layout {
+    /// The names of the fields of the struct, empty if it isn't one
+    pub(crate) fields: Vec<&'static str>,
+
+    /// The paths of the fields that can be read from variables prefixed with
+    /// their name, see [`nested_paths`]
+    pub(crate) nested: Arc<[String]>,
+}
+
+/// Learn the [`Layout`] of `T`, probing it only the first time
+/// it's asked for, since types don't change while the process runs
+pub(crate) fn layout<'de, T>() -> Arc<Layout>
+where
+    T: Deserialize<'de>,
+{
+    static LAYOUTS: OnceLock<RwLock<HashMap<TypeId, Arc<Layout>>>> = OnceLock::new();
+
+    // Lifetimes don't change the fields of a type, and `T` may borrow from the input
+    let id = typeid::of::<T>();
+    let layouts = LAYOUTS.get_or_init(RwLock::default);
+
+    if let Some(layout) = layouts
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&id)
+    {
+        return Arc::clone(layout);
+    }
+
+    let fields = describe::<T>();
+    let layout = Arc::new(Layout {
+        nested: nested_paths::<T>(&fields).into(),
+        fields: fields
+            .into_iter()
+            .map(|field| field.name)
+            .collect(),
+    });
+
+    layouts
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(id, Arc::clone(&layout));
+
+    layout
+}
+
+/// The paths of the `fields` of `T` and of its nested structs, such as `db.primary`,
+/// which hold structs or maps. These are the fields that can be read from variables
+/// prefixed with their name. Only the fields holding structs are probed any further.
+///
+/// Fields that don't say what they hold, such as tagged or untagged enums,
+/// aren't included, since they may just as well hold a single value.
+fn nested_paths<'de, T>(fields: &[FieldInfo]) -> Vec<String>
+where
+    T: Deserialize<'de>,
+{
+    let mut paths = Vec::new();
+    let mut pending = vec![(Vec::new(), String::new(), fields.to_vec())];
+
+    while let Some((route, parent, fields)) = pending.pop() {
+        for (i, field) in fields.into_iter().enumerate() {
+            if field.shape != Shape::Map {
+                continue;
+            }
+
+            let path = match parent.as_str() {
+                "" => field.name.to_owned(),
+                parent => format!("{}.{}", parent, field.name),
+            };
+
+            if route.len() < MAX_DEPTH {
+                let mut route = route.clone();
+                route.push(i);
+
+                // Maps have no fields, which leaves nothing to probe
+                let fields = describe_at::<T>(&route);
+                if !fields.is_empty() {
+                    pending.push((route, path.clone(), fields));
+                }
+            }
+
+            paths.push(path);
+        }
+    }
+
+    paths
+}
+
+/// Learn the fields of the struct reached from `T` through the fields at the
+/// indices of `route`, one per level of nesting, `T` itself if it's empty
+fn describe_at<'de, T>(route: &[usize]) -> Vec<FieldInfo>
+where
+    T: Deserialize<'de>,
+{
+    let mut probe = Probe {
+        route: route.to_vec(),
+        depth: 0,
+        field: None,
+        fields: &[],
+        info: None,
+    };
+    let _ = T::deserialize(&mut probe);
+
+    let fields = probe.fields;
+
+    (0..fields.len())
+        .map(|i| {
+            let mut probe = Probe {
+                route: route.to_vec(),
+                depth: 0,
+                field: Some(i),
+                fields: &[],
+                info: None,
+            };
+            let _ = T::deserialize(&mut probe);
+
+            probe.info.unwrap_or(FieldInfo {
+                name: fields[i],
+                shape: Shape::Any,
+                optional: false,
+            })
+        })
+        .collect()
+}
+
+/// The error that cuts a probe short
+fn stop() -> Error {
+    Error::Custom(String::from("probe finished"))
+}
+
+/// Stands in for the whole input of the probed struct. Records its fields
+/// and, if told which one, hands that single field to the visitor.
+///
+/// Structs nested in the probed one are reached by handing the visitor
+/// of every struct on the way the field at the next index of `route`.
+struct Probe {
+    route: Vec<usize>,
+    depth: usize,
+    field: Option<usize>,
+    fields: &'static [&'static str],
+    info: Option<FieldInfo>,
+}
+
+impl<'de> de::Deserializer<'de> for &mut Probe {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(stop())
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let _ = name;
+
+        if let Some(&index) = self.route.get(self.depth) {
+            self.depth += 1;
+
+            return visitor.visit_map(FieldAccess {
+                name: Some(fields.get(index).ok_or_else(stop)?),
+                descend: true,
+                probe: self,
+            });
+        }
+
+        self.fields = fields;
+
+        match self.field {
+            Some(field) => visitor.visit_map(FieldAccess {
+                name: Some(fields[field]),
+                descend: false,
+                probe: self,
+            }),
+            None => Err(stop()),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+        bytes byte_buf map unit_struct tuple_struct
+        identifier tuple ignored_any enum
+    }
+}
+
+/// Yields the single probed field, or the field leading
+/// to the probed struct if `descend` is set
+struct FieldAccess<'a> {
+    name: Option<&'static str>,
+    descend: bool,
+    probe: &'a mut Probe,
+}
+
+impl<'de> de::MapAccess<'de> for FieldAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.name {
+            Some(name) => seed
+                .deserialize(de::value::BorrowedStrDeserializer::new(name))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let name = self.name.take().ok_or_else(stop)?;
+
+        seed.deserialize(ValueProbe {
+            name,
+            optional: false,
+            descend: self.descend,
+            probe: self.probe,
+        })
+    }
+}
+
+/// Stands in for the value of the probed field,
+/// recording what the field asks for, or for the value of a field leading
+/// to the probed struct if `descend` is set, which records nothing
+struct ValueProbe<'a> {
+    name: &'static str,
+    optional: bool,
+    descend: bool,
+    probe: &'a mut Probe,
+}
+
+impl ValueProbe<'_> {
+    fn record<T>(self, shape: Shape) -> Result<T> {
+        if self.descend {
+            return Err(stop());
+        }
+
+        self.probe.info = Some(FieldInfo {
+            name: self.name,
+            shape,
+            optional: self.optional,
+        });
+
+        Err(stop())
+    }
+}
+
+macro_rules! scalars {
+    ($($method:ident => $name:literal)*) => {
+        $(
+            fn $method<V>(self, _: V) -> Result<V::Value>
+            where
+                V: de::Visitor<'de>,
+            {
+                self.record(Shape::Scalar($name))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueProbe<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Any)
+    }
+
+    scalars! {
+        deserialize_bool => "bool"
+        deserialize_u8 => "u8"
+        deserialize_u16 => "u16"
+        deserialize_u32 => "u32"
+        deserialize_u64 => "u64"
+        deserialize_i8 => "i8"
+        deserialize_i16 => "i16"
+        deserialize_i32 => "i32"
+        deserialize_i64 => "i64"
+        deserialize_u128 => "u128"
+        deserialize_i128 => "i128"
+        deserialize_f32 => "f32"
+        deserialize_f64 => "f64"
+        deserialize_char => "char"
+        deserialize_str => "string"
+        deserialize_string => "string"
+        deserialize_bytes => "bytes"
+        deserialize_byte_buf => "bytes"
+        deserialize_unit => "unit"
+        deserialize_identifier => "string"
+    }
+
+    fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.optional = true;
+
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V>(self, _: &'static str, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Scalar("unit"))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Scalar("enum"))
+    }
+
+    fn deserialize_seq<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Seq)
+    }
+
+    fn deserialize_tuple<V>(self, _: usize, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Seq)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _: &'static str,
+        _: usize,
+        _: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Seq)
+    }
+
+    fn deserialize_map<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Map)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.descend {
+            return self
+                .probe
+                .deserialize_struct(name, fields, visitor);
+        }
+
+        self.record(Shape::Map)
+    }
+
+    fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.record(Shape::Any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe, layout, FieldInfo, Shape};
+    use serde::Deserialize;
+    use std::{collections::HashMap, sync::Arc};
+
+    #[test]
+    fn test_describe() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Port(u16);
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct App {
+            name: String,
+            port: Option<Port>,
+            hosts: Vec<String>,
+            labels: HashMap<String, String>,
+        }
+
+        assert_eq!(
+            describe::<App>(),
+            vec![
+                FieldInfo {
+                    name: "name",
+                    shape: Shape::Scalar("string"),
+                    optional: false,
+                },
+                FieldInfo {
+                    name: "port",
+                    shape: Shape::Scalar("u16"),
+                    optional: true,
+                },
+                FieldInfo {
+                    name: "hosts",
+                    shape: Shape::Seq,
+                    optional: false,
+                },
+                FieldInfo {
+                    name: "labels",
+                    shape: Shape::Map,
+                    optional: false,
+                },
+            ]
+        );
+        assert!(describe::<HashMap<String, String>>().is_empty());
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Replica {
+            host: String,
+            labels: HashMap<String, String>,
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Database {
+            port: u16,
+            primary: Replica,
+            replicas: Vec<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        #[serde(tag = "type")]
+        enum Storage {
+            Local { path: String },
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Node {
+            name: String,
+            next: Option<Box<Node>>,
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct App {
+            term: Option<String>,
+            db: Database,
+            cache: Option<Replica>,
+            storage: Storage,
+            node: Node,
+        }
+
+        let mut paths = layout::<Option<App>>().nested.to_vec();
+        paths.sort();
+
+        assert_eq!(paths.len(), 8 + super::MAX_DEPTH - 2);
+        assert_eq!(
+            &paths[..8],
+            [
+                "cache",
+                "cache.labels",
+                "db",
+                "db.primary",
+                "db.primary.labels",
+                "node",
+                "node.next",
+                "node.next.next",
+            ]
+        );
+        assert!(!paths.contains(&String::from("storage")));
+        assert!(Arc::ptr_eq(
+            &layout::<Option<App>>().nested,
+            &layout::<Option<App>>().nested
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+//! Comparing two values of a type field by field, such as the
+//! configuration before and after a reload, to log what changed
+
+use crate::config::REDACTED;
+use crate::value::{to_value, Value};
+use crate::Result;
+use serde::Serialize;
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A field whose value differs between two values, see [`diff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The path of the field, with the names of nested fields separated by `.`
+    /// and the positions of elements in lists of structs written as `[0]`,
+    /// such as `database.port` or `servers[1].host`
+    pub path: String,
+
+    /// The old value, `None` if the field isn't set in it, such as an [`Option::None`]
+    pub old: Option<String>,
+
+    /// The new value, `None` if the field isn't set in it
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let value = |value: &Option<String>| match value {
+            Some(value) => format!("{:?}", value),
+            None => String::from("(unset)"),
+        };
+
+        write!(
+            fmt,
+            "{}: {} -> {}",
+            self.path,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+/// Compares two values of some type `T` field by field, reporting every field
+/// whose value differs, in the order the fields are serialized.
+/// Users are meant to obtain this struct by calling [`differ`].
+///
+/// Fields marked as secret with [`Differ::secret`] are still compared,
+/// but both of their values are redacted.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{differ, FieldDiff};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     host: String,
+///     password: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct App {
+///     port: u16,
+///     database: Database,
+/// }
+///
+/// let old = App {
+///     port: 8080,
+///     database: Database {
+///         host: String::from("localhost"),
+///         password: String::from("hunter2"),
+///     },
+/// };
+///
+/// let new = App {
+///     port: 8080,
+///     database: Database {
+///         host: String::from("db.internal"),
+///         password: String::from("hunter3"),
+///     },
+/// };
+///
+/// let changes = differ().secret("password").diff(&old, &new).unwrap();
+///
+/// assert_eq!(
+///     changes,
+///     vec![
+///         FieldDiff {
+///             path: String::from("database.host"),
+///             old: Some(String::from("localhost")),
+///             new: Some(String::from("db.internal")),
+///         },
+///         FieldDiff {
+///             path: String::from("database.password"),
+///             old: Some(String::from("<redacted>")),
+///             new: Some(String::from("<redacted>")),
+///         },
+///     ]
+/// );
+/// assert_eq!(changes[0].to_string(), r#"database.host: "localhost" -> "db.internal""#);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Differ<'a> {
+    secrets: Vec<&'a str>,
+}
+
+impl<'a> Differ<'a> {
+    /// Mark a field as secret, which redacts its values. `field` is either the path
+    /// of a single field, such as `database.password`, or the name of a field, such
+    /// as `password`, which marks every field with that name, however deeply nested.
+    /// Everything inside a secret field is secret as well.
+    pub fn secret(mut self, field: &'a str) -> Self {
+        self.secrets.push(field);
+        self
+    }
+
+    /// Compare `old` with `new`, reporting every field whose value differs
+    ///
+    /// # Errors
+    ///
+    /// If either of the values can't be serialized
+    pub fn diff<T>(&self, old: &T, new: &T) -> Result<Vec<FieldDiff>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let old = fields(old)?;
+        let new = fields(new)?;
+
+        let mut paths: Vec<&String> = old.iter().map(|(path, _)| path).collect();
+        for (path, _) in &new {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+
+        let find = |fields: &[(String, String)], path: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == path)
+                .map(|(_, value)| value.clone())
+        };
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| {
+                let old = find(&old, path);
+                let new = find(&new, path);
+
+                if old == new {
+                    return None;
+                }
+
+                let redact = |value: Option<String>| match self.is_secret(path) {
+                    true => value.map(|_| REDACTED.to_owned()),
+                    false => value,
+                };
+
+                Some(FieldDiff {
+                    path: path.clone(),
+                    old: redact(old),
+                    new: redact(new),
+                })
+            })
+            .collect())
+    }
+
+    fn is_secret(&self, path: &str) -> bool {
+        self.secrets.iter().any(|secret| {
+            path == *secret
+                || path
+                    .strip_prefix(secret)
+                    .is_some_and(|rest| rest.starts_with(['.', '[']))
+                || path
+                    .split(['.', '['])
+                    .any(|segment| segment == *secret)
+        })
+    }
+}
+
+/// Serialize `value` and flatten it into the paths of its fields and their values
+fn fields<T>(value: &T) -> Result<Vec<(String, String)>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut fields = Vec::new();
+    flatten(to_value(value)?, String::new(), &mut fields);
+
+    Ok(fields)
+}
+
+fn flatten(value: Value, path: String, fields: &mut Vec<(String, String)>) {
+    let join = |name: &str| match path.is_empty() {
+        true => name.to_owned(),
+        false => format!("{}.{}", path, name),
+    };
+
+    match value {
+        Value::None => {}
+        Value::Some(value) | Value::Newtype(value) => flatten(*value, path, fields),
+        Value::Variant(variant, value) if *value == Value::Unit => {
+            fields.push((path, variant.to_owned()))
+        }
+        Value::Variant(variant, value) => flatten(*value, join(variant), fields),
+        Value::Map(entries) => {
+            for (key, value) in entries {
+                let key = match key {
+                    Value::String(key) => key,
+                    key => render(key),
+                };
+
+                flatten(value, join(&key), fields);
+            }
+        }
+        Value::Seq(values) if values.iter().any(is_nested) => {
+            for (index, value) in values.into_iter().enumerate() {
+                flatten(value, format!("{}[{}]", path, index), fields);
+            }
+        }
+        value => fields.push((path, render(value))),
+    }
+}
+
+/// Whether `value` holds fields of its own, rather than being a single value
+fn is_nested(value: &Value) -> bool {
+    match value {
+        Value::Some(value) | Value::Newtype(value) => is_nested(value),
+        Value::Variant(_, value) => **value != Value::Unit,
+        Value::Map(_) => true,
+        Value::Seq(values) => values.iter().any(is_nested),
+        _ => false,
+    }
+}
+
+/// Write a single value the way it would be written in a variable,
+/// with the elements of lists separated by commas
+fn render(value: Value) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::I128(value) => value.to_string(),
+        Value::U128(value) => value.to_string(),
+        Value::F64(value) => value.to_string(),
+        Value::Char(value) => value.to_string(),
+        Value::String(value) => value,
+        Value::Bytes(value) => String::from_utf8_lossy(&value).into_owned(),
+        Value::None | Value::Unit => String::new(),
+        Value::Some(value) | Value::Newtype(value) => render(*value),
+        Value::Variant(variant, _) => variant.to_owned(),
+        Value::Seq(values) => values
+            .into_iter()
+            .map(render)
+            .collect::<Vec<_>>()
+            .join(","),
+        Value::Map(entries) => entries
+            .into_iter()
+            .map(|(key, value)| format!("{}:{}", render(key), render(value)))
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+/// Compare `old` with `new` field by field, reporting the path and both values of
+/// every field that differs, such as after reloading the configuration.
+/// Use [`differ`] to redact the values of secret fields.
+///
+/// # Errors
+///
+/// If either of the values can't be serialized
+///
+/// # Example
+///
+/// ```
+/// use renvar::diff;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct App {
+///     port: u16,
+///     hosts: Vec<String>,
+///     token: Option<String>,
+/// }
+///
+/// let old = App {
+///     port: 8080,
+///     hosts: vec![String::from("a")],
+///     token: None,
+/// };
+///
+/// let new = App {
+///     port: 8080,
+///     hosts: vec![String::from("a"), String::from("b")],
+///     token: Some(String::from("abc")),
+/// };
+///
+/// let changes: Vec<String> = diff(&old, &new)
+///     .unwrap()
+///     .iter()
+///     .map(ToString::to_string)
+///     .collect();
+///
+/// assert_eq!(
+///     changes,
+///     vec![r#"hosts: "a" -> "a,b""#, r#"token: (unset) -> "abc""#]
+/// );
+/// ```
+pub fn diff<T>(old: &T, new: &T) -> Result<Vec<FieldDiff>>
+where
+    T: ?Sized + Serialize,
+{
+    differ().diff(old, new)
+}
+
+/// Create a [`Differ`] with the default settings
+///
+/// # Example
+///
+/// ```
+/// use renvar::differ;
+///
+/// let differ = differ().secret("password");
+/// ```
+pub fn differ<'a>() -> Differ<'a> {
+    Differ::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, differ, FieldDiff};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Mode {
+        Plain,
+        Tls { cert: String },
+    }
+
+    #[derive(Serialize)]
+    struct Server {
+        host: String,
+        mode: Mode,
+    }
+
+    #[derive(Serialize)]
+    struct App {
+        servers: Vec<Server>,
+        labels: BTreeMap<String, String>,
+        credentials: BTreeMap<String, String>,
+    }
+
+    fn change(path: &str, old: Option<&str>, new: Option<&str>) -> FieldDiff {
+        FieldDiff {
+            path: path.to_owned(),
+            old: old.map(str::to_owned),
+            new: new.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = App {
+            servers: vec![Server {
+                host: String::from("a"),
+                mode: Mode::Plain,
+            }],
+            labels: BTreeMap::from([(String::from("team"), String::from("core"))]),
+            credentials: BTreeMap::from([(String::from("user"), String::from("root"))]),
+        };
+
+        let new = App {
+            servers: vec![
+                Server {
+                    host: String::from("a"),
+                    mode: Mode::Tls {
+                        cert: String::from("/etc/cert.pem"),
+                    },
+                },
+                Server {
+                    host: String::from("b"),
+                    mode: Mode::Plain,
+                },
+            ],
+            labels: BTreeMap::new(),
+            credentials: BTreeMap::from([(
+                String::from("user"),
+                String::from("admin"),
+            )]),
+        };
+
+        assert_eq!(diff(&old, &old), Ok(vec![]));
+        assert_eq!(
+            differ().secret("credentials").diff(&old, &new),
+            Ok(vec![
+                change("servers[0].mode", Some("Plain"), None),
+                change("labels.team", Some("core"), None),
+                change("credentials.user", Some("<redacted>"), Some("<redacted>")),
+                change("servers[0].mode.Tls.cert", None, Some("/etc/cert.pem")),
+                change("servers[1].host", None, Some("b")),
+                change("servers[1].mode", None, Some("Plain")),
+            ])
+        );
+        assert_eq!(
+            differ()
+                .secret("servers[0].mode")
+                .diff(&old, &new)
+                .unwrap()[0],
+            change("servers[0].mode", Some("<redacted>"), None)
+        );
+        assert_eq!(diff(&1, &2), Ok(vec![change("", Some("1"), Some("2"))]));
+    }
+}
@@ -0,0 +1,116 @@
+//! Variables stored as a directory of files, one per variable
+
+use crate::{file, Error, Result, Source};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A directory where the name of each file is a key and its contents are the value,
+/// the layout of the volumes Kubernetes mounts for Secrets, ConfigMaps and the downward
+/// API, as a [`Source`]. Dashes and dots in the file names become underscores,
+/// so `db-password` is read as `db_password`.
+///
+/// A single trailing newline is removed from the contents of every file.
+/// Subdirectories and hidden entries, such as the `..data` link
+/// Kubernetes uses for atomic updates, are skipped.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_source, DirSource};
+/// use serde::Deserialize;
+/// use std::{env, fs};
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     db_password: String,
+/// }
+///
+/// let dir = env::temp_dir().join("renvar_doc_dir_source");
+/// fs::create_dir_all(&dir).unwrap();
+/// fs::write(dir.join("db-password"), "hunter2\n").unwrap();
+///
+/// let custom_struct = from_source::<CustomStruct, _>(&DirSource::new(&dir));
+/// fs::remove_dir_all(&dir).unwrap();
+///
+/// assert_eq!(
+///     custom_struct.unwrap(),
+///     CustomStruct {
+///         db_password: "hunter2".to_owned()
+///     }
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirSource {
+    path: PathBuf,
+}
+
+impl DirSource {
+    /// Read the files in the directory at `path`, such as `/etc/secrets`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Source for DirSource {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        let io_error = |path: &Path, e: std::io::Error| Error::Io {
+            path: path.to_owned(),
+            reason: e.to_string(),
+        };
+
+        let mut vars = Vec::new();
+
+        for entry in fs::read_dir(&self.path).map_err(|e| io_error(&self.path, e))? {
+            let entry = entry.map_err(|e| io_error(&self.path, e))?;
+            let path = entry.path();
+
+            let name = match entry.file_name().into_string() {
+                Ok(name) if !name.starts_with('.') => name,
+                _ => continue,
+            };
+
+            // Follows the links Kubernetes mounts the files as
+            if !path.is_file() {
+                continue;
+            }
+
+            vars.push((name.replace(['-', '.'], "_"), file::read_value(&path)?));
+        }
+
+        vars.sort();
+
+        Ok(vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DirSource;
+    use crate::{Error, Source};
+    use std::{env, fs};
+
+    #[test]
+    fn test_dir_source() {
+        let dir = env::temp_dir().join("renvar_test_dir_source");
+        fs::create_dir_all(dir.join("..2024_01_01")).unwrap();
+        fs::write(dir.join("api.key"), "abc\r\n").unwrap();
+        fs::write(dir.join("port"), "8080").unwrap();
+        fs::write(dir.join("..data"), "ignored").unwrap();
+
+        let vars = DirSource::new(&dir).load();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            vars.unwrap(),
+            vec![
+                (String::from("api_key"), String::from("abc")),
+                (String::from("port"), String::from("8080")),
+            ]
+        );
+        assert!(matches!(DirSource::new(&dir).load(), Err(Error::Io { .. })));
+    }
+}
@@ -0,0 +1,255 @@
+//! Helpers for [`Duration`] fields written as human readable durations
+//!
+//! Serde deserializes a [`Duration`] from a struct with `secs` and `nanos` fields,
+//! which can't be written in a single variable. Fields marked with
+//! `#[serde(with = "renvar::duration")]` accept either a plain number of seconds (`90`)
+//! or a human readable duration, such as `30s`, `5m` or `1h 30m`, parsed by
+//! [`humantime::parse_duration`], and are serialized the way [`humantime::format_duration`]
+//! writes them. Use [`option`] for `Option<Duration>` fields.
+//!
+//! # Example
+//!
+//! ```
+//! use renvar::from_iter;
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Eq)]
+//! struct Timeouts {
+//!     #[serde(with = "renvar::duration")]
+//!     connect: Duration,
+//!     #[serde(default, with = "renvar::duration::option")]
+//!     retry: Option<Duration>,
+//! }
+//!
+//! let vars = vec![("CONNECT".to_owned(), "1m 30s".to_owned())];
+//!
+//! assert_eq!(
+//!     from_iter::<Timeouts, _>(vars).unwrap(),
+//!     Timeouts {
+//!         connect: Duration::from_secs(90),
+//!         retry: None,
+//!     }
+//! );
+//! ```
+
+use crate::{Error, Result};
+use serde::{de, Deserializer, Serializer};
+use std::{fmt, time::Duration};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parse a [`Duration`] from either a plain number of seconds (`30`)
+/// or a human readable duration, such as `30s`, `5m` or `1h 30m`
+///
+/// Human readable durations are parsed by [`humantime::parse_duration`]
+pub(crate) fn parse_duration(value: &str) -> Result<Duration> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    humantime::parse_duration(value)
+        .map_err(|e| Error::Custom(format!("{} while parsing value '{}'", e, value)))
+}
+
+/// Accepts the string or the number of seconds a [`Duration`] is written as
+struct DurationVisitor;
+
+impl<'de> de::Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number of seconds or a human readable duration")
+    }
+
+    fn visit_u64<E>(self, secs: u64) -> std::result::Result<Duration, E>
+    where
+        E: de::Error,
+    {
+        Ok(Duration::from_secs(secs))
+    }
+
+    fn visit_str<E>(self, value: &str) -> std::result::Result<Duration, E>
+    where
+        E: de::Error,
+    {
+        parse_duration(value).map_err(E::custom)
+    }
+}
+
+/// Deserialize a [`Duration`], see the [module documentation](self)
+///
+/// # Errors
+///
+/// If the value is neither a number of seconds nor a human readable duration
+pub fn deserialize<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(DurationVisitor)
+}
+
+/// Serialize a [`Duration`] as a human readable duration, such as `1m 30s`
+///
+/// # Errors
+///
+/// Any errors the serializer raises for strings
+pub fn serialize<S>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(&humantime::format_duration(*duration))
+}
+
+/// Same as the [parent module](super), for `Option<Duration>` fields,
+/// which are `None` when the variable is unset or empty
+pub mod option {
+    use super::DurationVisitor;
+    use serde::{de, Deserializer, Serializer};
+    use std::{fmt, time::Duration};
+
+    /// Accepts an optional [`Duration`]
+    struct OptionVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionVisitor {
+        type Value = Option<Duration>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an optional duration")
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer
+                .deserialize_str(DurationVisitor)
+                .map(Some)
+        }
+    }
+
+    /// Deserialize an `Option<Duration>`, see the [parent module](super)
+    ///
+    /// # Errors
+    ///
+    /// If the value is set but is neither a number of seconds nor a human readable duration
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(OptionVisitor)
+    }
+
+    /// Serialize an `Option<Duration>` as a human readable duration, if any
+    ///
+    /// # Errors
+    ///
+    /// Any errors the serializer raises for strings or options
+    pub fn serialize<S>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => {
+                serializer.collect_str(&humantime::format_duration(*duration))
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_duration;
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    struct Timeouts {
+        #[serde(with = "crate::duration")]
+        connect: Duration,
+        #[serde(with = "crate::duration")]
+        read: Duration,
+        #[serde(with = "crate::duration")]
+        idle: Duration,
+        #[serde(default, with = "crate::duration::option")]
+        retry: Option<Duration>,
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[test]
+    fn test_duration_fields() {
+        let vars = vec![
+            (String::from("CONNECT"), String::from("30s")),
+            (String::from("READ"), String::from("1h 30m")),
+            (String::from("IDLE"), String::from("90")),
+            (String::from("RETRY"), String::from("250ms")),
+        ];
+
+        let actual = crate::from_iter::<Timeouts, _>(vars).unwrap();
+
+        assert_eq!(
+            actual,
+            Timeouts {
+                connect: Duration::from_secs(30),
+                read: Duration::from_secs(5400),
+                idle: Duration::from_secs(90),
+                retry: Some(Duration::from_millis(250)),
+            }
+        );
+        assert_eq!(
+            crate::from_iter::<Timeouts, _>(crate::to_vec(&actual).unwrap()),
+            Ok(actual)
+        );
+    }
+
+    #[test]
+    fn test_duration_struct_names_are_left_alone() {
+        // Only fields marked with the helper are parsed as durations
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Duration {
+            secs: String,
+            nanos: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Window {
+            span: Duration,
+        }
+
+        let vars = vec![
+            (String::from("SPAN_SECS"), String::from("thirty")),
+            (String::from("SPAN_NANOS"), String::from("none")),
+        ];
+
+        assert_eq!(
+            crate::from_iter::<Window, _>(vars).unwrap(),
+            Window {
+                span: Duration {
+                    secs: String::from("thirty"),
+                    nanos: String::from("none"),
+                }
+            }
+        );
+    }
+}
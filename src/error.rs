@@ -1,5 +1,8 @@
-use serde::de::Error as SerdeError;
-use std::{error::Error as StdError, ffi::OsString, fmt};
+use crate::ValidationError;
+use serde::{de::Error as SerdeError, ser::Error as SerdeSerError};
+use std::{
+    error::Error as StdError, ffi::OsString, fmt, path::PathBuf, time::Duration,
+};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -17,8 +20,181 @@ pub enum Error {
     /// Same purpose as [`serde::de::Error::missing_field`],
     MissingValue(String),
 
+    /// Raised instead of [`Error::MissingValue`] when a variable
+    /// with a similar name, likely a misspelling, was found
+    SimilarKey {
+        /// The field that's missing
+        field: String,
+
+        /// The name of the similar variable
+        similar: String,
+    },
+
     /// Same purpose as [`serde::de::Error::custom`]
     Custom(String),
+
+    /// Raised when [`crate::Builder::reject_control_chars`] is enabled
+    /// and the value of a variable contains NUL or other control characters
+    ControlCharacters {
+        /// The key of the offending variable
+        key: String,
+
+        /// Byte offsets of the control characters in the value
+        offsets: Vec<usize>,
+    },
+
+    /// Raised when [`crate::Builder::deny_unknown`] is enabled and some
+    /// of the variables don't match any field of the target struct
+    UnknownKeys(Vec<String>),
+
+    /// Raised when [`crate::EmptyStringPolicy::Error`] is in effect and
+    /// a variable is set to an empty string. Holds the name of the variable
+    EmptyValue(String),
+
+    /// Raised when [`crate::DuplicateKeyPolicy::Error`] is in effect
+    /// and several variables map to the same key
+    DuplicateKey {
+        /// The name of the first variable
+        first: String,
+
+        /// The name of the variable that collided with it
+        second: String,
+    },
+
+    /// Raised when [`crate::Builder::deny_duplicate_elements`] is enabled and
+    /// an element is repeated in a sequence or set. Holds the element as it was written
+    DuplicateElement(String),
+
+    /// Raised when [`crate::Builder::interpolate`] is enabled
+    /// and a reference in the value of a variable can't be expanded
+    Interpolation {
+        /// The key of the offending variable
+        key: String,
+
+        /// Why the reference couldn't be expanded
+        reason: String,
+    },
+
+    /// Raised when a file can't be read
+    Io {
+        /// The file that was being read
+        path: PathBuf,
+
+        /// Description of the underlying [`std::io::Error`]
+        reason: String,
+    },
+
+    /// Raised when an env file is older than the age allowed
+    /// by [`crate::Builder::max_age`]
+    Stale {
+        /// The file that was being read
+        path: PathBuf,
+
+        /// How old the contents of the file are
+        age: Duration,
+
+        /// The maximum age that was allowed
+        max_age: Duration,
+    },
+
+    /// Raised when a line of an env file can't be parsed, such as
+    /// a line without a `=` or a value with an unterminated quote
+    Parse {
+        /// The number of the line, starting from 1
+        line: usize,
+
+        /// The column the problem was found at, in characters, starting from 1
+        column: usize,
+
+        /// What's wrong with the line
+        reason: String,
+    },
+
+    /// Raised by [`crate::keys::validate`], and when [`crate::Exporter::strict_keys`]
+    /// is enabled, for a key that isn't a valid POSIX name of an environment variable
+    InvalidKey {
+        /// The offending key
+        key: String,
+
+        /// What's wrong with the key
+        reason: String,
+    },
+
+    /// Raised by [`crate::validate`] when a deserialized value breaks any of
+    /// its constraints, or when the variables break any of the rules set with
+    /// [`crate::Builder::requires`] and [`crate::Builder::conflicts`]
+    Validation(Vec<ValidationError>),
+
+    /// Wraps any other error with the path of the field it belongs to,
+    /// when [`crate::Builder::track_paths`] is enabled
+    AtPath {
+        /// Path of the field from the top-level struct, such as
+        /// `server.tls.cert_path`, with indices of sequences as in `hosts[1]`
+        path: String,
+
+        /// The underlying error
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// The path of the field the error belongs to,
+    /// if it was tracked, see [`crate::Builder::track_paths`]
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::AtPath { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Put the error under `segment`, the name of a field or the index
+    /// of an element written as `[1]`, in front of the path it already has
+    pub(crate) fn at(self, segment: &str) -> Self {
+        match self {
+            Error::AtPath { path, error } => {
+                let separator = match path.starts_with('[') {
+                    true => "",
+                    false => ".",
+                };
+
+                Error::AtPath {
+                    path: format!("{}{}{}", segment, separator, path),
+                    error,
+                }
+            }
+            error => Error::AtPath {
+                path: segment.to_owned(),
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Rewrite the names of the variables mentioned by the error
+    pub(crate) fn map_keys(self, f: impl Fn(String) -> String) -> Self {
+        match self {
+            Error::UnknownKeys(keys) => {
+                Error::UnknownKeys(keys.into_iter().map(f).collect())
+            }
+            Error::EmptyValue(key) => Error::EmptyValue(f(key)),
+            Error::DuplicateKey { first, second } => Error::DuplicateKey {
+                first: f(first),
+                second: f(second),
+            },
+            Error::SimilarKey { field, similar } => Error::SimilarKey {
+                field,
+                similar: f(similar),
+            },
+            Error::ControlCharacters { key, offsets } => Error::ControlCharacters {
+                key: f(key),
+                offsets,
+            },
+            Error::AtPath { path, error } => Error::AtPath {
+                path,
+                error: Box::new(error.map_keys(f)),
+            },
+            error => error,
+        }
+    }
 }
 
 impl StdError for Error {}
@@ -34,7 +210,74 @@ impl fmt::Display for Error {
                 )
             }
             Error::MissingValue(field) => write!(fmt, "missing value for {}", &field),
+            Error::SimilarKey { field, similar } => write!(
+                fmt,
+                "missing `{}`; found similar variable `{}`",
+                field, similar
+            ),
             Error::Custom(msg) => write!(fmt, "{}", msg),
+            Error::ControlCharacters { key, offsets } => {
+                let offsets = offsets
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(
+                    fmt,
+                    "control characters found in the value of {} at byte offsets {}",
+                    key, offsets
+                )
+            }
+            Error::UnknownKeys(keys) => {
+                write!(fmt, "unknown environment variables: {}", keys.join(", "))
+            }
+            Error::EmptyValue(key) => {
+                write!(fmt, "environment variable {} is set but empty", key)
+            }
+            Error::DuplicateKey { first, second } => write!(
+                fmt,
+                "environment variables {} and {} map to the same key",
+                first, second
+            ),
+            Error::DuplicateElement(element) => {
+                write!(fmt, "duplicate element `{}`", element)
+            }
+            Error::Interpolation { key, reason } => {
+                write!(fmt, "failed to expand the value of {}: {}", key, reason)
+            }
+            Error::Io { path, reason } => {
+                write!(fmt, "failed to read {}: {}", path.display(), reason)
+            }
+            Error::Stale { path, age, max_age } => write!(
+                fmt,
+                "{} is {}s old, exceeding the maximum age of {}s",
+                path.display(),
+                age.as_secs(),
+                max_age.as_secs()
+            ),
+            Error::Parse {
+                line,
+                column,
+                reason,
+            } => write!(fmt, "line {}, column {}: {}", line, column, reason),
+            Error::InvalidKey { key, reason } => {
+                write!(
+                    fmt,
+                    "invalid environment variable name `{}`: {}",
+                    key, reason
+                )
+            }
+            Error::Validation(errors) => {
+                let errors = errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+
+                write!(fmt, "validation failed: {}", errors)
+            }
+            Error::AtPath { path, error } => write!(fmt, "{}: {}", path, error),
         }
     }
 }
@@ -48,3 +291,9 @@ impl SerdeError for Error {
         Error::MissingValue(field.into())
     }
 }
+
+impl SerdeSerError for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(format!("{}", msg))
+    }
+}
@@ -0,0 +1,184 @@
+//! Reporting which variable every field of a struct would be read from,
+//! without deserializing anything, such as for a `--print-config` flag
+
+use crate::config::REDACTED;
+use crate::{builder, RawEnv};
+use serde::de;
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// How a single field of a struct would be read, see [`Explanation`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldExplanation {
+    /// The name of the field, as serde knows it
+    pub field: String,
+
+    /// The variable the field is read from, or the key it
+    /// would be read from if none of the variables match it
+    pub key: String,
+
+    /// The value of the variable as it's set, before defaults, interpolation
+    /// or resolvers are applied, `None` if the variable isn't set
+    pub value: Option<String>,
+
+    /// Whether the field is an [`Option`], which can be left unset
+    pub optional: bool,
+}
+
+/// The resolution plan of a struct: every one of its fields, the variable
+/// it would be read from and the value that variable currently holds.
+/// Created with [`explain`] or [`crate::Builder::explain`].
+///
+/// Printing it lists the fields one per line.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, RawEnv};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+///     password: String,
+///     log_level: Option<String>,
+/// }
+///
+/// let raw = RawEnv::parse("APP_PORT=8080\nAPP_PASSWORD=hunter2\n");
+///
+/// let explanation = builder()
+///     .prefix("APP_")
+///     .explain::<App>(&raw)
+///     .redact("password");
+///
+/// assert_eq!(
+///     explanation.to_string(),
+///     "port: APP_PORT=8080\n\
+///      password: APP_PASSWORD=<redacted>\n\
+///      log_level: APP_LOG_LEVEL is not set (optional)\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Explanation {
+    fields: Vec<FieldExplanation>,
+}
+
+impl Explanation {
+    pub(crate) fn new(fields: Vec<FieldExplanation>) -> Self {
+        Self { fields }
+    }
+
+    /// Replace the value of `field` with a placeholder,
+    /// for secrets that shouldn't end up in logs or terminals
+    pub fn redact(mut self, field: &str) -> Self {
+        for explanation in self.fields.iter_mut() {
+            if explanation.field == field && explanation.value.is_some() {
+                explanation.value = Some(REDACTED.to_owned());
+            }
+        }
+        self
+    }
+
+    /// The fields of the struct, in the order they're declared
+    pub fn fields(&self) -> &[FieldExplanation] {
+        &self.fields
+    }
+
+    /// The required fields no variable is set for, which would
+    /// fail deserialization unless they have a default
+    pub fn missing(&self) -> impl Iterator<Item = &FieldExplanation> {
+        self.fields
+            .iter()
+            .filter(|field| field.value.is_none() && !field.optional)
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for field in &self.fields {
+            match (&field.value, field.optional) {
+                (Some(value), _) => {
+                    writeln!(fmt, "{}: {}={}", field.field, field.key, value)?
+                }
+                (None, true) => writeln!(
+                    fmt,
+                    "{}: {} is not set (optional)",
+                    field.field, field.key
+                )?,
+                (None, false) => {
+                    writeln!(fmt, "{}: {} is not set", field.field, field.key)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Report which variable of the environment of the running process
+/// every field of the struct `T` would be read from, with the default
+/// settings. See [`crate::Builder::explain`] for other settings.
+///
+/// Returns an empty report for anything other than a struct with named fields,
+/// including structs with flattened fields, whose keys can't be known in advance.
+///
+/// # Example
+///
+/// ```
+/// use renvar::explain;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     renvar_explain_example_port: u16,
+/// }
+///
+/// let explanation = explain::<App>();
+///
+/// assert_eq!(
+///     explanation.fields()[0].key,
+///     "RENVAR_EXPLAIN_EXAMPLE_PORT"
+/// );
+/// assert_eq!(explanation.missing().count(), 1);
+/// ```
+pub fn explain<'de, T>() -> Explanation
+where
+    T: de::Deserialize<'de>,
+{
+    builder().explain::<T>(&RawEnv::from_env())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{builder, KeyCase, RawEnv};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Server {
+        max_connections: u32,
+        host: Option<String>,
+        extra: std::collections::HashMap<String, String>,
+    }
+
+    #[test]
+    fn test_explain_matching() {
+        let raw = RawEnv::parse(
+            "MAX_CONNECTIONS=8\nMAX_CONNECTIONS_SVC=16\nOTHER_SVC=1\nHOST=no\n",
+        );
+
+        let explanation = builder()
+            .postfix("_SVC")
+            .key_case(KeyCase::ScreamingSnake)
+            .capture_rest("extra")
+            .explain::<Server>(&raw);
+
+        assert_eq!(explanation.fields().len(), 2);
+        assert_eq!(explanation.fields()[0].key, "MAX_CONNECTIONS_SVC");
+        assert_eq!(explanation.fields()[0].value.as_deref(), Some("16"));
+        assert_eq!(explanation.fields()[1].key, "HOST_SVC");
+        assert_eq!(explanation.fields()[1].value, None);
+        assert_eq!(explanation.missing().count(), 0);
+    }
+}
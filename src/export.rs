@@ -0,0 +1,793 @@
+use crate::{keys, ser::EnvVarSerializer, Error, KeyCase, Result};
+use serde::Serialize;
+use std::{collections::BTreeMap, env, process::Command};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// When [`Exporter::to_env_file`] quotes values. Values are double quoted in env
+/// files, with `\`, `"`, newlines and carriage returns escaped as `\\`, `\"`, `\n`
+/// and `\r`, so every variable stays on its own line and reads back with
+/// [`crate::from_str`]. They're single quoted with [`Exporter::shell_export`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// Quote the values that wouldn't read back as they are otherwise, such as
+    /// values spanning several lines or starting or ending with quotes or whitespace,
+    /// or, with [`Exporter::shell_export`], any character the shell treats specially
+    #[default]
+    IfNeeded,
+
+    /// Quote every value
+    Always,
+
+    /// Write values as they are, for tools that don't understand quotes, such as
+    /// `docker run --env-file`. Values spanning several lines are an error.
+    /// Ignored by [`Exporter::shell_export`], since unquoted values aren't safe to source.
+    Never,
+}
+
+/// Serializes some type `T` into environment variables, ready to be handed
+/// to a child process, such as [`std::process::Command::envs`], or to
+/// the environment of an integration test container.
+/// Users are meant to obtain this struct by calling [`exporter`].
+///
+/// Fields marked as secret with [`Exporter::secret`] are left out,
+/// unless [`Exporter::include_secrets`] is enabled.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{exporter, Exporter};
+/// use serde::Serialize;
+/// use std::process::Command;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     port: u16,
+///     password: String,
+/// }
+///
+/// let config = Config {
+///     port: 5432,
+///     password: String::from("hunter2"),
+/// };
+///
+/// let exporter: Exporter = exporter().prefix("APP_").secret("password");
+///
+/// let vars = exporter.to_vec(&config).unwrap();
+///
+/// assert_eq!(vars, vec![(String::from("APP_PORT"), String::from("5432"))]);
+///
+/// let mut command = Command::new("env");
+/// command.envs(vars);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Exporter<'a> {
+    prefix: Option<&'a str>,
+    secrets: Vec<&'a str>,
+    include_secrets: bool,
+    key_case: KeyCase,
+    strict_keys: bool,
+    quoting: Quoting,
+    shell_export: bool,
+}
+
+impl<'a> Exporter<'a> {
+    /// Prepend `prefix` to every key
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Mark a field as secret, which leaves it out of the exported
+    /// variables unless [`Exporter::include_secrets`] is enabled.
+    /// Field names are matched ignoring case.
+    pub fn secret(mut self, field: &'a str) -> Self {
+        self.secrets.push(field);
+        self
+    }
+
+    /// Write the keys in `key_case`, see [`KeyCase`]
+    ///
+    /// Defaults to [`KeyCase::ScreamingSnake`].
+    pub fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// Pass the fields marked with [`Exporter::secret`] through as well
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::exporter;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     password: String,
+    /// }
+    ///
+    /// let config = Config {
+    ///     password: String::from("hunter2"),
+    /// };
+    ///
+    /// let vars = exporter()
+    ///     .secret("password")
+    ///     .include_secrets(true)
+    ///     .to_vec(&config)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(vars, vec![(String::from("PASSWORD"), String::from("hunter2"))]);
+    /// ```
+    pub fn include_secrets(mut self, include: bool) -> Self {
+        self.include_secrets = include;
+        self
+    }
+
+    /// Fail with [`crate::Error::InvalidKey`] if any key, prefix included,
+    /// isn't a valid POSIX name of an environment variable, see [`keys::validate`],
+    /// such as keys written in [`KeyCase::Kebab`] or map keys with dots, which
+    /// shells and some tools can't pass on when the variables are written to an env file
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::exporter;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let labels = BTreeMap::from([("app.tier", "web")]);
+    ///
+    /// assert!(exporter().to_vec(&labels).is_ok());
+    ///
+    /// let error = exporter().strict_keys(true).to_vec(&labels).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "invalid environment variable name `APP.TIER`: invalid character `.` at offset 3"
+    /// );
+    /// ```
+    pub fn strict_keys(mut self, strict: bool) -> Self {
+        self.strict_keys = strict;
+        self
+    }
+
+    /// Set when [`Exporter::to_env_file`] quotes values, see [`Quoting`]
+    ///
+    /// Defaults to [`Quoting::IfNeeded`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{exporter, Quoting};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     name: String,
+    ///     motd: String,
+    /// }
+    ///
+    /// let config = Config {
+    ///     name: String::from("api"),
+    ///     motd: String::from("Welcome\nto \"api\""),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     exporter().to_env_file(&config).unwrap(),
+    ///     "NAME=api\nMOTD=\"Welcome\\nto \\\"api\\\"\"\n"
+    /// );
+    /// assert_eq!(
+    ///     exporter().quoting(Quoting::Always).to_env_file(&config).unwrap(),
+    ///     "NAME=\"api\"\nMOTD=\"Welcome\\nto \\\"api\\\"\"\n"
+    /// );
+    /// ```
+    pub fn quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Make [`Exporter::to_env_file`] write `export KEY='value'` lines, safe
+    /// to `source` in bash and other POSIX shells whatever the values hold:
+    /// single quotes keep spaces, `$`, backticks and newlines as they are,
+    /// and single quotes within values are written as `'\''`. Keys can't be
+    /// quoted, so they're always checked with [`keys::validate`], as if
+    /// [`Exporter::strict_keys`] was enabled.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::exporter;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     port: u16,
+    ///     greeting: String,
+    /// }
+    ///
+    /// let config = Config {
+    ///     port: 8080,
+    ///     greeting: String::from("it's $HOME\nbye"),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     exporter().shell_export(true).to_env_file(&config).unwrap(),
+    ///     "export PORT=8080\nexport GREETING='it'\\''s $HOME\nbye'\n"
+    /// );
+    /// ```
+    pub fn shell_export(mut self, shell_export: bool) -> Self {
+        self.shell_export = shell_export;
+        self
+    }
+
+    /// Serialize `value` into the contents of an env file, one `KEY=value` line
+    /// per variable, with values quoted according to [`Exporter::quoting`],
+    /// or into `export` lines for a shell with [`Exporter::shell_export`]
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during serialization, see [`Exporter::to_vec`],
+    /// if a value spans several lines with [`Quoting::Never`], or [`crate::Error::InvalidKey`]
+    /// if a key isn't a valid name with [`Exporter::shell_export`]
+    pub fn to_env_file<T>(&self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut file = String::new();
+
+        for (key, value) in self.to_vec(value)? {
+            let value = match self.shell_export {
+                true => {
+                    // A key such as `X;rm -rf ~` would run a command when sourced
+                    keys::validate(&key)?;
+                    file.push_str("export ");
+                    self.shell_quote(&value)
+                }
+                false => self.quote(&key, &value)?,
+            };
+
+            file.push_str(&key);
+            file.push('=');
+            file.push_str(&value);
+            file.push('\n');
+        }
+
+        Ok(file)
+    }
+
+    /// Serialize `value` into `(key, value)` pairs
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during serialization, see [`crate::to_vec`],
+    /// or [`crate::Error::InvalidKey`] if [`Exporter::strict_keys`] is enabled
+    /// and a key isn't a valid name
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<(String, String)>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = EnvVarSerializer::with_key_case(self.key_case);
+        value.serialize(&mut serializer)?;
+
+        let vars: Vec<_> = serializer
+            .into_inner()
+            .into_iter()
+            .filter(|(key, _)| self.include_secrets || !self.is_secret(key))
+            .map(|(key, value)| match self.prefix {
+                Some(prefix) => (format!("{}{}", prefix, key), value),
+                None => (key, value),
+            })
+            .collect();
+
+        if self.strict_keys {
+            for (key, _) in &vars {
+                keys::validate(key)?;
+            }
+        }
+
+        Ok(vars)
+    }
+
+    /// Serialize `value` into a map of keys to values, the shape
+    /// expected by most container and process builder APIs
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during serialization, see [`crate::to_vec`]
+    pub fn to_map<T>(&self, value: &T) -> Result<BTreeMap<String, String>>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.to_vec(value)
+            .map(|vars| vars.into_iter().collect())
+    }
+
+    /// Write `value`, the value of `key`, for an env file
+    fn quote(&self, key: &str, value: &str) -> Result<String> {
+        let multiline = value.contains(['\n', '\r']);
+        let quote = match self.quoting {
+            Quoting::Always => true,
+            Quoting::IfNeeded => {
+                let unsafe_end = |c: char| c.is_whitespace() || c == '"' || c == '\'';
+
+                multiline || value.starts_with(unsafe_end) || value.ends_with(unsafe_end)
+            }
+            Quoting::Never if multiline => {
+                return Err(Error::Custom(format!(
+                    "the value of {} spans several lines, which can't be written without quotes",
+                    key
+                )))
+            }
+            Quoting::Never => false,
+        };
+
+        if !quote {
+            return Ok(value.to_owned());
+        }
+
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push('"');
+
+        for c in value.chars() {
+            match c {
+                '\\' => quoted.push_str("\\\\"),
+                '"' => quoted.push_str("\\\""),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                c => quoted.push(c),
+            }
+        }
+
+        quoted.push('"');
+
+        Ok(quoted)
+    }
+
+    /// Write `value` for a POSIX shell, single quoted unless it's only made of
+    /// characters the shell doesn't treat specially and quoting isn't forced
+    fn shell_quote(&self, value: &str) -> String {
+        let plain = !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+
+        match self.quoting != Quoting::Always && plain {
+            true => value.to_owned(),
+            false => format!("'{}'", value.replace('\'', "'\\''")),
+        }
+    }
+
+    /// Serialize `value` and set the variables in the environment of the
+    /// child processes `command` spawns, on top of the ones they inherit
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during serialization, see [`Exporter::to_vec`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::exporter;
+    /// use serde::Serialize;
+    /// use std::process::Command;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Worker {
+    ///     queue: String,
+    ///     token: String,
+    /// }
+    ///
+    /// let worker = Worker {
+    ///     queue: String::from("emails"),
+    ///     token: String::from("secret"),
+    /// };
+    ///
+    /// let mut command = Command::new("worker");
+    ///
+    /// exporter()
+    ///     .prefix("WORKER_")
+    ///     .secret("token")
+    ///     .apply_to_command(&worker, &mut command)
+    ///     .unwrap();
+    ///
+    /// let envs: Vec<_> = command.get_envs().collect();
+    ///
+    /// assert_eq!(envs.len(), 1);
+    /// ```
+    pub fn apply_to_command<T>(&self, value: &T, command: &mut Command) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        command.envs(self.to_vec(value)?);
+        Ok(())
+    }
+
+    /// Serialize `value` and set the variables in the environment of the current
+    /// process, see [`crate::export`]. Nothing is set if any of them can't be.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`crate::export`]
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during serialization, see [`Exporter::to_vec`],
+    /// [`crate::Error::InvalidKey`] if a key is empty or contains `=` or NUL,
+    /// or if a value contains NUL, which can't be stored in the environment
+    pub unsafe fn export<T>(&self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let vars = self.to_vec(value)?;
+
+        for (key, value) in &vars {
+            let reason = match key.find(['=', '\0']) {
+                _ if key.is_empty() => Some(String::from("empty name")),
+                Some(offset) => Some(format!(
+                    "invalid character `{}` at offset {}",
+                    key[offset..]
+                        .chars()
+                        .next()
+                        .unwrap_or_default()
+                        .escape_debug(),
+                    offset
+                )),
+                None => None,
+            };
+
+            if let Some(reason) = reason {
+                return Err(Error::InvalidKey {
+                    key: key.clone(),
+                    reason,
+                });
+            }
+
+            if value.contains('\0') {
+                return Err(Error::Custom(format!(
+                    "the value of {} contains NUL, which can't be stored in the environment",
+                    key
+                )));
+            }
+        }
+
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+
+        Ok(())
+    }
+
+    fn is_secret(&self, key: &str) -> bool {
+        self.secrets.iter().any(|secret| {
+            self.key_case
+                .to_key(secret)
+                .eq_ignore_ascii_case(key)
+        })
+    }
+}
+
+/// Serialize `value` and set the variables, with `prefix` prepended to their keys,
+/// in the environment of the child processes `command` spawns, on top of the ones
+/// they inherit. Use [`exporter`] to leave secrets out or change the case of the keys.
+///
+/// # Errors
+///
+/// Any errors that might occur during serialization, see [`crate::to_vec`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::apply_to_command;
+/// use serde::Serialize;
+/// use std::ffi::OsStr;
+/// use std::process::Command;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let database = Database {
+///     host: String::from("localhost"),
+///     port: 5432,
+/// };
+///
+/// let mut command = Command::new("psql");
+///
+/// apply_to_command(&database, &mut command, "PG").unwrap();
+///
+/// assert_eq!(
+///     command.get_envs().collect::<Vec<_>>(),
+///     vec![
+///         (OsStr::new("PGHOST"), Some(OsStr::new("localhost"))),
+///         (OsStr::new("PGPORT"), Some(OsStr::new("5432"))),
+///     ]
+/// );
+/// ```
+pub fn apply_to_command<T>(value: &T, command: &mut Command, prefix: &str) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    exporter()
+        .prefix(prefix)
+        .apply_to_command(value, command)
+}
+
+/// Serialize `value` and set the variables, with `prefix` prepended to their keys,
+/// in the environment of the current process, for code that reads its configuration
+/// from the environment directly, such as legacy libraries, or child processes
+/// spawned without [`apply_to_command`]. Nothing is set if any of them can't be.
+///
+/// # Safety
+///
+/// Modifying the environment isn't thread-safe on most platforms: reading or
+/// writing it from another thread at the same time, even through libc, such as
+/// when resolving host names, is undefined behavior. [`std::env::set_var`] requires
+/// `unsafe` for this reason since the 2024 edition. Call it before spawning threads,
+/// such as at the start of `main`, or while holding [`crate::testing::lock`] in tests.
+///
+/// # Errors
+///
+/// Any errors that might occur during serialization, see [`crate::to_vec`],
+/// [`crate::Error::InvalidKey`] if a key is empty or contains `=` or NUL,
+/// or if a value contains NUL, which can't be stored in the environment
+///
+/// # Example
+///
+/// ```
+/// use renvar::export;
+/// use serde::Serialize;
+/// use std::env;
+///
+/// #[derive(Serialize)]
+/// struct Legacy {
+///     home: String,
+///     verbose: bool,
+/// }
+///
+/// let legacy = Legacy {
+///     home: String::from("/opt/legacy"),
+///     verbose: true,
+/// };
+///
+/// // No other thread is running yet
+/// unsafe { export(&legacy, "EXPORT_DOC_LEGACY_").unwrap() };
+///
+/// assert_eq!(env::var("EXPORT_DOC_LEGACY_HOME").unwrap(), "/opt/legacy");
+/// assert_eq!(env::var("EXPORT_DOC_LEGACY_VERBOSE").unwrap(), "true");
+/// ```
+pub unsafe fn export<T>(value: &T, prefix: &str) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    exporter().prefix(prefix).export(value)
+}
+
+/// Serialize `value` into a map of keys to values, ready to be handed
+/// to [`Command::envs`] or to the environment of a container
+///
+/// # Errors
+///
+/// Any errors that might occur during serialization, see [`crate::to_vec`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::to_env_map;
+/// use serde::Serialize;
+/// use std::process::Command;
+///
+/// #[derive(Serialize)]
+/// struct Server {
+///     port: u16,
+///     hosts: Vec<String>,
+/// }
+///
+/// let server = Server {
+///     port: 8080,
+///     hosts: vec![String::from("a"), String::from("b")],
+/// };
+///
+/// let vars = to_env_map(&server).unwrap();
+///
+/// assert_eq!(vars["HOSTS"], "a,b");
+///
+/// Command::new("server").envs(&vars);
+/// ```
+pub fn to_env_map<T>(value: &T) -> Result<BTreeMap<String, String>>
+where
+    T: ?Sized + Serialize,
+{
+    exporter().to_map(value)
+}
+
+/// Create an [`Exporter`] with the default settings
+///
+/// # Example
+///
+/// ```
+/// use renvar::exporter;
+///
+/// let exporter = exporter().prefix("APP_");
+/// ```
+pub fn exporter<'a>() -> Exporter<'a> {
+    Exporter::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{exporter, Quoting};
+    use crate::Error;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Database {
+        host: String,
+        port: u16,
+        user: String,
+        password: String,
+        replicas: Vec<String>,
+    }
+
+    #[test]
+    fn test_to_map() {
+        let database = Database {
+            host: String::from("localhost"),
+            port: 5432,
+            user: String::from("postgres"),
+            password: String::from("hunter2"),
+            replicas: vec![String::from("a"), String::from("b")],
+        };
+
+        let exporter = exporter().prefix("POSTGRES_").secret("PASSWORD");
+
+        assert_eq!(
+            exporter.to_map(&database).unwrap(),
+            BTreeMap::from([
+                (String::from("POSTGRES_HOST"), String::from("localhost")),
+                (String::from("POSTGRES_PORT"), String::from("5432")),
+                (String::from("POSTGRES_REPLICAS"), String::from("a,b")),
+                (String::from("POSTGRES_USER"), String::from("postgres")),
+            ])
+        );
+
+        let vars = exporter
+            .include_secrets(true)
+            .to_map(&database)
+            .unwrap();
+
+        assert_eq!(vars["POSTGRES_PASSWORD"], "hunter2");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_to_command() {
+        #[derive(Serialize)]
+        struct Child {
+            greeting: String,
+            count: u8,
+        }
+
+        let child = Child {
+            greeting: String::from("hello world"),
+            count: 3,
+        };
+
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "printf '%s/%s' \"$CHILD_GREETING\" \"$CHILD_COUNT\""]);
+        super::apply_to_command(&child, &mut command, "CHILD_").unwrap();
+
+        let output = command.output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world/3");
+    }
+
+    #[test]
+    fn test_export() {
+        #[derive(Serialize)]
+        struct Exported {
+            name: String,
+            level: u8,
+        }
+
+        let _lock = crate::testing::lock();
+        let exported = Exported {
+            name: String::from("renvar"),
+            level: 2,
+        };
+
+        let result = unsafe { super::export(&exported, "RENVAR_TEST_EXPORT_") };
+        let name = std::env::var("RENVAR_TEST_EXPORT_NAME");
+        let level = std::env::var("RENVAR_TEST_EXPORT_LEVEL");
+
+        let nul = Exported {
+            name: String::from("a\0b"),
+            level: 3,
+        };
+        let error = unsafe { super::export(&nul, "RENVAR_TEST_EXPORT_NUL_") };
+        let untouched = std::env::var_os("RENVAR_TEST_EXPORT_NUL_LEVEL");
+
+        let invalid = unsafe { exporter().prefix("A=").export(&exported) };
+
+        std::env::remove_var("RENVAR_TEST_EXPORT_NAME");
+        std::env::remove_var("RENVAR_TEST_EXPORT_LEVEL");
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(name.as_deref(), Ok("renvar"));
+        assert_eq!(level.as_deref(), Ok("2"));
+        assert!(matches!(error, Err(Error::Custom(_))));
+        assert_eq!(untouched, None);
+        assert_eq!(
+            invalid,
+            Err(Error::InvalidKey {
+                key: String::from("A=NAME"),
+                reason: String::from("invalid character `=` at offset 1"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_env_file() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+        struct Banner {
+            plain: String,
+            quoted: String,
+            lines: String,
+            path: String,
+            empty: String,
+        }
+
+        let banner = Banner {
+            plain: String::from("a b"),
+            quoted: String::from("'single' and \"double\""),
+            lines: String::from("one\r\ntwo $(rm -rf /) `x`"),
+            path: String::from("C:\\temp\\"),
+            empty: String::new(),
+        };
+
+        let file = exporter().to_env_file(&banner).unwrap();
+
+        assert_eq!(
+            file,
+            "PLAIN=a b\nQUOTED=\"'single' and \\\"double\\\"\"\nLINES=\"one\\r\\ntwo $(rm -rf /) `x`\"\nPATH=C:\\temp\\\nEMPTY=\n"
+        );
+        assert_eq!(crate::from_str::<Banner>(&file).unwrap(), banner);
+
+        let file = exporter()
+            .shell_export(true)
+            .quoting(Quoting::Never)
+            .to_env_file(&banner)
+            .unwrap();
+
+        assert_eq!(
+            file,
+            "export PLAIN='a b'\nexport QUOTED=''\\''single'\\'' and \"double\"'\nexport LINES='one\r\ntwo $(rm -rf /) `x`'\nexport PATH='C:\\temp\\'\nexport EMPTY=''\n"
+        );
+        assert!(matches!(
+            exporter()
+                .quoting(Quoting::Never)
+                .to_env_file(&banner),
+            Err(Error::Custom(_))
+        ));
+
+        // Keys can't be quoted, so they're checked before they're sourced
+        let labels = BTreeMap::from([("x;rm -rf ~", "web")]);
+
+        assert_eq!(
+            exporter().shell_export(true).to_env_file(&labels),
+            Err(Error::InvalidKey {
+                key: String::from("X;RM _RF ~"),
+                reason: String::from("invalid character `;` at offset 1"),
+            })
+        );
+        assert!(exporter().to_env_file(&labels).is_ok());
+    }
+}
@@ -0,0 +1,409 @@
+//! Loading environment variables from `.env` style files
+
+use crate::{Error, Result};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Comment header that records when an env file was generated, such as
+///
+/// ```text
+/// # generated-at: 2024-05-01T12:00:00Z
+/// ```
+const GENERATED_AT: &str = "generated-at:";
+
+/// Byte order mark some editors on Windows start UTF-8 files with
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Read the contents of the file at `path` into a [`String`],
+/// without the byte order mark it may start with
+pub(crate) fn read(path: &Path) -> Result<String> {
+    let mut content = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_owned(),
+        reason: e.to_string(),
+    })?;
+
+    if content.starts_with(BOM) {
+        content.drain(..BOM.len_utf8());
+    }
+
+    Ok(content)
+}
+
+/// Read the contents of the UTF-16 encoded file at `path` into a [`String`].
+/// The byte order is taken from the byte order mark, little endian
+/// if there's none, as written by Windows tools such as PowerShell
+pub(crate) fn read_utf16(path: &Path) -> Result<String> {
+    let error = |reason: String| Error::Io {
+        path: path.to_owned(),
+        reason,
+    };
+
+    let bytes = fs::read(path).map_err(|e| error(e.to_string()))?;
+
+    if !bytes.len().is_multiple_of(2) {
+        return Err(error(String::from("odd number of bytes in UTF-16 file")));
+    }
+
+    let (bytes, big_endian) = match bytes.as_slice() {
+        [0xfe, 0xff, rest @ ..] => (rest, true),
+        [0xff, 0xfe, rest @ ..] => (rest, false),
+        bytes => (bytes, false),
+    };
+
+    char::decode_utf16(
+        bytes
+            .chunks_exact(2)
+            .map(|pair| match big_endian {
+                true => u16::from_be_bytes([pair[0], pair[1]]),
+                false => u16::from_le_bytes([pair[0], pair[1]]),
+            }),
+    )
+    .collect::<std::result::Result<String, _>>()
+    .map_err(|e| error(format!("invalid UTF-16: {}", e)))
+}
+
+/// Read the contents of a file holding a single value, such as a mounted secret,
+/// removing the trailing newline most tools that write such files add
+pub(crate) fn read_value(path: &Path) -> Result<String> {
+    let mut content = read(path)?;
+
+    if content.ends_with('\n') {
+        content.pop();
+
+        if content.ends_with('\r') {
+            content.pop();
+        }
+    }
+
+    Ok(content)
+}
+
+/// Determine how old the contents of an env file are.
+///
+/// An embedded `# generated-at:` header takes precedence over the
+/// modification time of the file, since copying files into images
+/// tends to reset the latter.
+pub(crate) fn age(path: &Path, content: &str) -> Result<Duration> {
+    let generated_at = match generated_at(content) {
+        Some(generated_at) => generated_at?,
+        None => fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| Error::Io {
+                path: path.to_owned(),
+                reason: e.to_string(),
+            })?,
+    };
+
+    Ok(SystemTime::now()
+        .duration_since(generated_at)
+        .unwrap_or_default())
+}
+
+/// Fail with [`Error::Stale`] if `age` exceeds `max_age`
+pub(crate) fn check_age(path: &Path, age: Duration, max_age: Duration) -> Result<()> {
+    if age > max_age {
+        Err(Error::Stale {
+            path: path.to_owned(),
+            age,
+            max_age,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Find and parse the `# generated-at:` header among the comment lines
+fn generated_at(content: &str) -> Option<Result<SystemTime>> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('#'))
+        .find_map(|comment| comment.trim().strip_prefix(GENERATED_AT))
+        .map(|timestamp| {
+            parse_timestamp(timestamp.trim()).ok_or_else(|| {
+                Error::Custom(format!(
+                    "invalid timestamp '{}' in the generated-at header",
+                    timestamp.trim()
+                ))
+            })
+        })
+}
+
+/// Parse either a number of seconds since the unix epoch or
+/// an RFC 3339 timestamp, such as `2024-05-01T12:00:00Z`
+/// or `2024-05-01T14:00:00.5+02:00`
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    if let Ok(secs) = timestamp.parse::<u64>() {
+        return Some(UNIX_EPOCH + Duration::from_secs(secs));
+    }
+
+    let (date, time) = timestamp.split_once(['T', 't', ' '])?;
+    let (time, offset) = time.split_at(time.find(['Z', 'z', '+', '-'])?);
+
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let year = date.next()?.ok()?;
+    let month = date.next()?.ok()?;
+    let day = date.next()?.ok()?;
+
+    let (time, fraction) = time.split_once('.').unwrap_or((time, "0"));
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let hour = time.next()?.ok()?;
+    let minute = time.next()?.ok()?;
+    let second = time.next()?.ok()?;
+    let nanos = format!("{:0<9}", fraction)
+        .get(..9)?
+        .parse::<u32>()
+        .ok()?;
+
+    let offset = match offset {
+        "Z" | "z" => 0,
+        offset => {
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            let is_two_digits = |part: &str| {
+                part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit())
+            };
+
+            if !is_two_digits(hours) || !is_two_digits(minutes) {
+                return None;
+            }
+
+            let (hours, minutes) =
+                (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+
+            match offset.starts_with('-') {
+                true => -(hours * 3600 + minutes * 60),
+                false => hours * 3600 + minutes * 60,
+            }
+        }
+    };
+
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || !(0..24).contains(&hour)
+        || !(0..60).contains(&minute)
+        || !(0..=60).contains(&second)
+    {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second
+            - offset;
+
+    // Timestamps before the epoch are counted back from it, down to the whole second
+    match u64::try_from(secs) {
+        Ok(secs) => UNIX_EPOCH.checked_add(Duration::new(secs, nanos)),
+        Err(_) => UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+            .checked_add(Duration::from_nanos(nanos.into())),
+    }
+}
+
+/// Number of days in `month` of `year` of the proleptic Gregorian calendar
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days between the unix epoch and the given date of the proleptic
+/// Gregorian calendar, see <http://howardhinnant.github.io/date_algorithms.html>
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builder, DuplicateKeyPolicy};
+    use serde::Deserialize;
+    use std::{env, fs};
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Credentials {
+        token: String,
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = UNIX_EPOCH + Duration::from_secs(1714564800);
+
+        assert_eq!(parse_timestamp("1714564800"), Some(expected));
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00Z"), Some(expected));
+        assert_eq!(parse_timestamp("2024-05-01T14:00:00+02:00"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2024-05-01T11:00:00.25-01:00"),
+            Some(expected + Duration::from_millis(250))
+        );
+        assert_eq!(parse_timestamp("2024-13-01T12:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+
+        // Days are checked against the length of the month
+        assert!(parse_timestamp("2024-02-29T00:00:00Z").is_some());
+        assert_eq!(parse_timestamp("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-02-31T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-04-31T00:00:00Z"), None);
+
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00+99:99"), None);
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00+02:60"), None);
+        assert_eq!(parse_timestamp("2024-05-01T12:00:00+2:00"), None);
+
+        assert_eq!(
+            parse_timestamp("1969-12-31T23:59:59Z"),
+            Some(UNIX_EPOCH - Duration::from_secs(1))
+        );
+        assert_eq!(
+            parse_timestamp("1969-12-31T23:59:59.75Z"),
+            Some(UNIX_EPOCH - Duration::from_millis(250))
+        );
+        assert_eq!(
+            parse_timestamp("1900-01-01T00:00:00Z"),
+            Some(UNIX_EPOCH - Duration::from_secs(2208988800))
+        );
+    }
+
+    #[test]
+    fn test_max_age() {
+        let path = env::temp_dir().join("renvar_test_max_age.env");
+        fs::write(
+            &path,
+            "# generated-at: 2020-01-01T00:00:00Z\nTOKEN=secret\n",
+        )
+        .unwrap();
+
+        let error = builder()
+            .max_age(Duration::from_secs(7 * 86400))
+            .from_file::<Credentials>(&path)
+            .unwrap_err();
+
+        assert!(matches!(error, Error::Stale { .. }));
+
+        let credentials = builder().from_file::<Credentials>(&path).unwrap();
+
+        assert_eq!(
+            credentials,
+            Credentials {
+                token: String::from("secret")
+            }
+        );
+
+        fs::write(&path, "TOKEN=secret\n").unwrap();
+
+        let credentials = builder()
+            .max_age(Duration::from_secs(7 * 86400))
+            .from_file::<Credentials>(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(credentials.is_ok());
+    }
+
+    #[test]
+    fn test_defaults() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+
+        let path = env::temp_dir().join("renvar_test_defaults.env");
+        fs::write(&path, "APP_HOST=localhost\nAPP_PORT=8080\nAPP_WORKERS=4\n").unwrap();
+
+        let vars = vec![(String::from("APP_PORT"), String::from("9090"))];
+
+        let server = builder()
+            .prefix("APP_")
+            .defaults(&path)
+            .from_iter::<Server, _>(vars.clone());
+
+        let error = builder()
+            .prefix("APP_")
+            .defaults(&path)
+            .require_known_defaults(true)
+            .from_iter::<Server, _>(vars);
+
+        let lowercase = builder()
+            .prefix("APP_")
+            .defaults(&path)
+            .duplicate_keys(DuplicateKeyPolicy::FirstWins)
+            .from_iter::<Server, _>(vec![(
+                String::from("APP_port"),
+                String::from("9090"),
+            )]);
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            server.unwrap(),
+            Server {
+                host: String::from("localhost"),
+                port: 9090
+            }
+        );
+        assert_eq!(
+            error.unwrap_err(),
+            Error::UnknownKeys(vec![String::from("APP_WORKERS")])
+        );
+        assert_eq!(lowercase.unwrap().port, 9090);
+    }
+
+    #[test]
+    fn test_bom_crlf_and_utf16() {
+        let path = env::temp_dir().join("renvar_test_encodings.env");
+
+        fs::write(&path, "\u{feff}TOKEN=secret\r\n").unwrap();
+        let utf8 = builder().from_file::<Credentials>(&path);
+
+        let utf16le: Vec<u8> = "\u{feff}TOKEN=s\u{e9}cret\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        fs::write(&path, utf16le).unwrap();
+        let le = builder().from_utf16_file::<Credentials>(&path);
+
+        let utf16be: Vec<u8> = "\u{feff}TOKEN=secret"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        fs::write(&path, utf16be).unwrap();
+        let be = builder().from_utf16_file::<Credentials>(&path);
+
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+        let odd = builder().from_utf16_file::<Credentials>(&path);
+
+        fs::remove_file(&path).unwrap();
+
+        let credentials = |token: &str| {
+            Ok(Credentials {
+                token: token.to_owned(),
+            })
+        };
+
+        assert_eq!(utf8, credentials("secret"));
+        assert_eq!(le, credentials("s\u{e9}cret"));
+        assert_eq!(be, credentials("secret"));
+        assert!(matches!(odd, Err(Error::Io { .. })));
+        assert_eq!(
+            crate::from_str::<Credentials>("\u{feff}TOKEN=secret\r"),
+            credentials("secret")
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::convert::maybe_invalid_unicode_vars_os;
+use crate::{from_iter, Result};
+use regex::Regex;
+use serde::de;
+use std::env;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Name of the capture group that, when present in the pattern,
+/// becomes the key matched against the fields of the target struct
+const NAME_GROUP: &str = "name";
+
+/// Aids in deserializing some type `T` from environment variables whose
+/// keys match a regular expression. Users are meant to obtain this struct
+/// by calling [`filtered`].
+///
+/// If the pattern has a capture group called `name`, the captured part of
+/// the key is used to match the fields of the target struct, which makes it
+/// a generalization of [`crate::prefixed`] and [`crate::postfixed`].
+/// Otherwise the whole key is used.
+///
+/// # Example
+///
+/// ```
+/// use regex::Regex;
+/// use renvar::{filtered, Filtered};
+///
+/// let filtered: Filtered = filtered(Regex::new("^MYAPP_(?P<name>.+)$").unwrap());
+///
+/// assert_eq!(filtered.regex().as_str(), "^MYAPP_(?P<name>.+)$")
+/// ```
+#[derive(Debug, Clone)]
+pub struct Filtered(Regex);
+
+impl Filtered {
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Panics
+    /// if any of the environment variables contain invalid unicode
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use renvar::filtered;
+    /// use serde::Deserialize;
+    /// use std::env;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     key: String,
+    /// }
+    ///
+    /// env::set_var("FILTERED_KEY_V2", "value");
+    ///
+    /// let custom_struct: CustomStruct = filtered(Regex::new("^FILTERED_(?P<name>.+)_V2$").unwrap())
+    ///     .from_env()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         key: String::from("value")
+    ///     }
+    /// )
+    /// ```
+    pub fn from_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(env::vars())
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time, but doesn't panic
+    /// if any of the environment variables contain invalid unicode, instead returns
+    /// an error.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_os_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(maybe_invalid_unicode_vars_os()?)
+    }
+
+    /// Deserialize some type `T` from an iterator `Iter` that is an iterator over key-value pairs,
+    /// filtering only the pairs where the key matches the regular expression.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use renvar::filtered;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("SVC_DB_HOST".to_owned(), "localhost".to_owned()),
+    ///     ("SVC_DB_PORT".to_owned(), "5432".to_owned()),
+    ///     ("SVC_CACHE_PORT".to_owned(), "6379".to_owned()),
+    /// ];
+    ///
+    /// let regex = Regex::new("^SVC_DB_(?P<name>.+)$").unwrap();
+    /// let custom_struct: CustomStruct = filtered(regex).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host: String::from("localhost"),
+    ///         port: 5432
+    ///     }
+    /// )
+    /// ```
+    pub fn from_iter<T, Iter>(&self, iter: Iter) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        Iter: IntoIterator<Item = (String, String)>,
+    {
+        from_iter(iter.into_iter().filter_map(|(k, v)| {
+            let captures = self.0.captures(&k)?;
+
+            match captures.name(NAME_GROUP) {
+                Some(name) => Some((name.as_str().to_owned(), v)),
+                None => Some((k, v)),
+            }
+        }))
+    }
+
+    /// Retrieve the regular expression specified at the
+    /// time of constructing an instance of [`Filtered`]
+    pub fn regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+/// Aids in deserializing some type `T` from environment variables,
+/// where the keys match a regular expression. Users are meant to obtain
+/// a [`Filtered`] struct by calling [`filtered`].
+///
+/// # Example
+///
+/// ```
+/// use regex::Regex;
+/// use renvar::filtered;
+///
+/// let filtered = filtered(Regex::new("^(?P<name>.+)_PROD$").unwrap());
+///
+/// assert_eq!(filtered.regex().as_str(), "^(?P<name>.+)_PROD$")
+/// ```
+pub fn filtered(regex: Regex) -> Filtered {
+    Filtered(regex)
+}
+
+#[cfg(test)]
+mod test_filtered {
+    use regex::Regex;
+    use serde::Deserialize;
+
+    use super::filtered;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Test {
+        svc_key: String,
+        other: Option<String>,
+    }
+
+    #[test]
+    fn test_filtered_without_name_group() {
+        let vars = vec![
+            (String::from("SVC_KEY"), String::from("value")),
+            (String::from("OTHER"), String::from("ignored")),
+        ];
+
+        let actual = filtered(Regex::new("^SVC_").unwrap())
+            .from_iter::<Test, _>(vars)
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            Test {
+                svc_key: String::from("value"),
+                other: None
+            }
+        )
+    }
+}
@@ -0,0 +1,113 @@
+//! Feature flags toggled through environment variables that share a prefix
+
+use crate::convert::sanitize;
+use crate::{parse::parse_lenient_bool, KeyCase};
+use std::{collections::HashMap, env};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Every variable whose key starts with some prefix, such as `APP_FEATURE_`,
+/// read as an on/off switch. The prefix is stripped and the rest of the key
+/// is lowercased, so `APP_FEATURE_NEW_UI=on` enables the `new_ui` flag.
+///
+/// Values are parsed the same way as with [`crate::Builder::lenient_bools`],
+/// accepting `1/0`, `yes/no`, `on/off` and `true/false`, ignoring case.
+/// Variables with any other value don't set a flag and are reported
+/// by [`FeatureFlags::invalid`] instead.
+///
+/// # Example
+///
+/// ```
+/// use renvar::FeatureFlags;
+///
+/// let vars = vec![
+///     ("APP_FEATURE_NEW_UI".to_owned(), "on".to_owned()),
+///     ("APP_FEATURE_BETA".to_owned(), "0".to_owned()),
+///     ("APP_FEATURE_DARK_MODE".to_owned(), "sometimes".to_owned()),
+///     ("APP_PORT".to_owned(), "8080".to_owned()),
+/// ];
+///
+/// let flags = FeatureFlags::from_iter("APP_FEATURE_", vars);
+///
+/// assert!(flags.is_enabled("new_ui"));
+/// assert!(!flags.is_enabled("beta"));
+/// assert!(!flags.is_enabled("unheard_of"));
+/// assert_eq!(flags.get("beta"), Some(false));
+/// assert_eq!(
+///     flags.invalid(),
+///     &[("APP_FEATURE_DARK_MODE".to_owned(), "sometimes".to_owned())]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureFlags {
+    flags: HashMap<String, bool>,
+    invalid: Vec<(String, String)>,
+}
+
+impl FeatureFlags {
+    /// Collect the flags under `prefix` from the environment of the running process.
+    /// Variables that aren't valid unicode are skipped
+    pub fn from_env(prefix: &str) -> Self {
+        Self::from_iter(
+            prefix,
+            env::vars_os().filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            }),
+        )
+    }
+
+    /// Collect the flags under `prefix` from an iterator over key-value pairs.
+    ///
+    /// Like with [`crate::from_iter`], single quotes, double quotes
+    /// and whitespace will be trimmed
+    pub fn from_iter<Iter>(prefix: &str, iter: Iter) -> Self
+    where
+        Iter: IntoIterator<Item = (String, String)>,
+    {
+        let mut flags = Self::default();
+
+        for (key, value) in sanitize(iter) {
+            let name = match key.strip_prefix(prefix) {
+                Some(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+
+            match parse_lenient_bool(&value) {
+                Some(enabled) => {
+                    flags
+                        .flags
+                        .insert(KeyCase::ScreamingSnake.to_field(name), enabled);
+                }
+                None => flags.invalid.push((key, value)),
+            }
+        }
+
+        flags
+    }
+
+    /// Whether the flag called `name` is set and enabled
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.get(name).unwrap_or(false)
+    }
+
+    /// The state of the flag called `name`, or `None` if it isn't set
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.flags.get(name).copied()
+    }
+
+    /// The flags that were set, by name
+    pub fn flags(&self) -> &HashMap<String, bool> {
+        &self.flags
+    }
+
+    /// The `(key, value)` pairs of the variables under the
+    /// prefix whose values aren't recognized as on or off
+    pub fn invalid(&self) -> &[(String, String)] {
+        &self.invalid
+    }
+
+    /// Take the flags, discarding the invalid variables
+    pub fn into_map(self) -> HashMap<String, bool> {
+        self.flags
+    }
+}
@@ -0,0 +1,482 @@
+//! Key-value formats other than `.env` files, such as the ones of
+//! `docker run --env-file`, systemd's `EnvironmentFile=`, PowerShell
+//! scripts setting variables and the NUL separated output of `env -0`
+
+use crate::{Error, Result};
+use std::{env, ffi::OsString};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The format of the variables read with [`crate::from_reader`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// `KEY=value` lines, as described in [`crate::from_str`]
+    #[default]
+    Dotenv,
+
+    /// `KEY=value` lines like `docker run --env-file` reads them: values are taken
+    /// as they're written, quotes and trailing whitespace included, and a line
+    /// with a key alone takes the value of that variable in the environment,
+    /// being skipped if it isn't set
+    Docker,
+
+    /// PowerShell scripts made of `$env:KEY = "value"` lines, with double quoted
+    /// values expanding backtick escapes such as `` `n ``, and single quoted
+    /// values taken as they're written, except for `''` standing for `'`
+    PowerShell,
+
+    /// Files read by systemd's `EnvironmentFile=`, following its rules so they're
+    /// read the same way by both: lines starting with `#` or `;` are comments,
+    /// unquoted values are trimmed, with `\` escaping the next character and
+    /// joining lines when it ends one, single quoted values are taken as they're
+    /// written, and double quoted values expand `\"`, `\\`, `` \` `` and `\$`,
+    /// keeping other backslashes. Quoted values can span several lines.
+    /// Lines without `=` and keys that aren't valid variable names are skipped.
+    Systemd,
+}
+
+/// Parse a line of a docker env file, `None` for a key alone that isn't set
+pub(crate) fn docker_line(
+    number: usize,
+    line: &str,
+) -> Result<Option<(String, String)>> {
+    let line = line.trim_start();
+    let (key, value) = match line.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (line, None),
+    };
+
+    if key.is_empty() {
+        return Err(parse_error(number, 1, "expected a key before `=`"));
+    }
+
+    if let Some(offset) = key.find(char::is_whitespace) {
+        return Err(parse_error(
+            number,
+            key[..offset].chars().count() + 1,
+            "whitespace in key",
+        ));
+    }
+
+    Ok(match value {
+        Some(value) => Some((key.to_owned(), value.to_owned())),
+        None => env::var(key)
+            .ok()
+            .map(|value| (key.to_owned(), value)),
+    })
+}
+
+/// Parse a `$env:KEY = value` line of a PowerShell script
+pub(crate) fn powershell_line(number: usize, line: &str) -> Result<(String, String)> {
+    let trimmed = line.trim_start();
+    let column = |rest: &str| line.chars().count() - rest.chars().count() + 1;
+
+    let rest = trimmed
+        .get(..5)
+        .filter(|start| start.eq_ignore_ascii_case("$env:"))
+        .map(|_| &trimmed[5..])
+        .ok_or_else(|| {
+            parse_error(number, column(trimmed), "expected `$env:KEY = value`")
+        })?;
+
+    let end = rest
+        .find(|c: char| c == '=' || c.is_whitespace())
+        .unwrap_or(rest.len());
+    let (key, rest) = rest.split_at(end);
+
+    if key.is_empty() {
+        return Err(parse_error(
+            number,
+            column(rest),
+            "expected a key after `$env:`",
+        ));
+    }
+
+    let rest = rest
+        .trim_start()
+        .strip_prefix('=')
+        .ok_or_else(|| parse_error(number, column(rest), "expected `=` after the key"))?
+        .trim_start();
+
+    let (value, rest) = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => unquote_powershell(&rest[1..], quote)
+            .ok_or_else(|| parse_error(number, column(rest), "unterminated quote"))?,
+        _ => (rest.trim_end().to_owned(), ""),
+    };
+
+    let rest = rest.trim_start();
+
+    if !rest.is_empty() && !rest.starts_with('#') && !rest.starts_with(';') {
+        return Err(parse_error(
+            number,
+            column(rest),
+            "unexpected text after the value",
+        ));
+    }
+
+    Ok((key.to_owned(), value))
+}
+
+/// Read the value quoted by `quote` at the start of `input`, which comes right
+/// after the opening quote, returning it along with what follows the closing one
+fn unquote_powershell(input: &str, quote: char) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // Quotes are escaped by doubling them in either kind of string
+            c if c == quote => match chars.peek() {
+                Some((_, next)) if *next == quote => {
+                    value.push(quote);
+                    chars.next();
+                }
+                _ => return Some((value, &input[i + 1..])),
+            },
+            '`' if quote == '"' => {
+                let (_, escaped) = chars.next()?;
+
+                value.push(match escaped {
+                    '0' => '\0',
+                    'a' => '\u{7}',
+                    'b' => '\u{8}',
+                    'e' => '\u{1b}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'v' => '\u{b}',
+                    escaped => escaped,
+                });
+            }
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+/// Where a [`Systemd`] parser is within an assignment
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SystemdState {
+    #[default]
+    PreKey,
+    Key,
+    PreValue,
+    Value,
+    ValueEscape,
+    SingleQuoted,
+    DoubleQuoted,
+    DoubleQuotedEscape,
+    Comment,
+}
+
+/// Parser of the files systemd reads with `EnvironmentFile=`, following the
+/// state machine of systemd's own parser. It's fed line by line, keeping
+/// the assignments that span several lines until they're complete.
+#[derive(Debug, Default)]
+pub(crate) struct Systemd {
+    state: SystemdState,
+    key: String,
+    value: String,
+    /// Where the trailing whitespace of an unquoted value starts
+    trailing: Option<usize>,
+}
+
+impl Systemd {
+    /// Feed the line `line`, adding the assignments it completes to `vars`
+    pub(crate) fn feed(&mut self, line: &str, vars: &mut Vec<(String, String)>) {
+        for c in line.chars().chain(Some('\n')) {
+            self.push(c, vars);
+        }
+    }
+
+    /// Add the assignment the input ended in the middle of to `vars`, if any
+    pub(crate) fn finish(mut self, vars: &mut Vec<(String, String)>) {
+        if !matches!(
+            self.state,
+            SystemdState::PreKey | SystemdState::Key | SystemdState::Comment
+        ) {
+            self.assign(vars);
+        }
+    }
+
+    fn push(&mut self, c: char, vars: &mut Vec<(String, String)>) {
+        use SystemdState::*;
+
+        let newline = c == '\n' || c == '\r';
+        let whitespace = newline || c == ' ' || c == '\t';
+
+        match self.state {
+            PreKey if c == '#' || c == ';' => self.state = Comment,
+            PreKey if !whitespace => {
+                self.state = Key;
+                self.key.push(c);
+            }
+            PreKey => {}
+            Key if newline => {
+                self.state = PreKey;
+                self.key.clear();
+            }
+            Key if c == '=' => {
+                self.state = PreValue;
+                self.trailing = None;
+            }
+            Key => self.key.push(c),
+            PreValue | Value if newline => {
+                self.state = PreKey;
+                self.assign(vars);
+            }
+            PreValue if c == '\'' => self.state = SingleQuoted,
+            PreValue if c == '"' => self.state = DoubleQuoted,
+            PreValue | Value if c == '\\' => {
+                self.state = ValueEscape;
+                self.trailing = None;
+            }
+            PreValue if whitespace => {}
+            PreValue | Value => {
+                self.state = Value;
+
+                if !whitespace {
+                    self.trailing = None;
+                } else if self.trailing.is_none() {
+                    self.trailing = Some(self.value.len());
+                }
+
+                self.value.push(c);
+            }
+            // An escaped newline joins the lines
+            ValueEscape => {
+                self.state = Value;
+
+                if !newline {
+                    self.value.push(c);
+                }
+            }
+            SingleQuoted if c == '\'' => self.state = PreValue,
+            SingleQuoted => self.value.push(c),
+            DoubleQuoted if c == '"' => self.state = PreValue,
+            DoubleQuoted if c == '\\' => self.state = DoubleQuotedEscape,
+            DoubleQuoted => self.value.push(c),
+            DoubleQuotedEscape => {
+                self.state = DoubleQuoted;
+
+                match c {
+                    '"' | '\\' | '`' | '$' => self.value.push(c),
+                    '\n' => {}
+                    c => {
+                        self.value.push('\\');
+                        self.value.push(c);
+                    }
+                }
+            }
+            Comment if newline => self.state = PreKey,
+            Comment => {}
+        }
+    }
+
+    /// Add the assignment that was just read to `vars`,
+    /// unless its key isn't a valid variable name
+    fn assign(&mut self, vars: &mut Vec<(String, String)>) {
+        if let Some(trailing) = self.trailing.take() {
+            self.value.truncate(trailing);
+        }
+
+        let key = std::mem::take(&mut self.key);
+        let value = std::mem::take(&mut self.value);
+        let key = key.trim_end_matches([' ', '\t', '\n', '\r']);
+
+        let valid = key
+            .chars()
+            .next()
+            .is_some_and(|first| !first.is_ascii_digit())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if valid {
+            vars.push((key.to_owned(), value));
+        }
+    }
+}
+
+/// Split NUL separated `KEY=value` entries, such as the output of `env -0`
+/// or the contents of `/proc/<pid>/environ`, into `(key, value)` pairs,
+/// skipping the entries without a `=`
+pub(crate) fn nul_delimited(bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    let utf8 = |bytes: &[u8]| {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| Error::InvalidUnicode(os_string(e.into_bytes())))
+    };
+
+    bytes
+        .split(|byte| *byte == 0)
+        .filter_map(|entry| {
+            let i = entry.iter().position(|byte| *byte == b'=')?;
+
+            Some(utf8(&entry[..i]).and_then(|key| Ok((key, utf8(&entry[i + 1..])?))))
+        })
+        .collect()
+}
+
+/// The [`OsString`] made of `bytes`, which can only be kept as they are on unix
+#[cfg(unix)]
+pub(crate) fn os_string(bytes: Vec<u8>) -> OsString {
+    std::os::unix::ffi::OsStringExt::from_vec(bytes)
+}
+
+/// The [`OsString`] made of `bytes`, which can only be kept as they are on unix
+#[cfg(not(unix))]
+pub(crate) fn os_string(bytes: Vec<u8>) -> OsString {
+    OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn parse_error(line: usize, column: usize, reason: &str) -> Error {
+    Error::Parse {
+        line,
+        column,
+        reason: reason.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{docker_line, nul_delimited, powershell_line, Format, Systemd};
+    use crate::{convert::is_blank_or_comment, testing::with_vars, Error, Result};
+
+    /// Parse every line of `input`, written in `format`
+    fn parse(format: Format, input: &str) -> Result<Vec<(String, String)>> {
+        let mut vars = Vec::new();
+
+        for (i, line) in input.lines().enumerate() {
+            if is_blank_or_comment(line) {
+                continue;
+            }
+
+            match format {
+                Format::PowerShell => vars.push(powershell_line(i + 1, line)?),
+                _ => vars.extend(docker_line(i + 1, line)?),
+            }
+        }
+
+        Ok(vars)
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_formats() {
+        let input = "# comment\n  QUOTED=\"a b\" \nEMPTY=\nRENVAR_FORMAT_HOST_VAR\nRENVAR_FORMAT_UNSET\n";
+
+        assert_eq!(
+            with_vars([("RENVAR_FORMAT_HOST_VAR", "from host")], || {
+                parse(Format::Docker, input)
+            })
+            .unwrap(),
+            vars(&[
+                ("QUOTED", "\"a b\" "),
+                ("EMPTY", ""),
+                ("RENVAR_FORMAT_HOST_VAR", "from host"),
+            ])
+        );
+        assert_eq!(
+            parse(Format::Docker, "A B=c"),
+            Err(Error::Parse {
+                line: 1,
+                column: 2,
+                reason: String::from("whitespace in key"),
+            })
+        );
+
+        let script = r#"
+# Generated by setup.ps1
+$env:DATABASE_URL = "postgres://localhost/app"
+$Env:GREETING="Hello`n`"World`" ""again"""
+  $env:PATTERN = 'C:\temp\it''s'  # single quotes
+$env:PORT=8080
+"#;
+
+        assert_eq!(
+            parse(Format::PowerShell, script).unwrap(),
+            vars(&[
+                ("DATABASE_URL", "postgres://localhost/app"),
+                ("GREETING", "Hello\n\"World\" \"again\""),
+                ("PATTERN", "C:\\temp\\it's"),
+                ("PORT", "8080"),
+            ])
+        );
+        assert_eq!(
+            parse(Format::PowerShell, "$env:KEY = \"open"),
+            Err(Error::Parse {
+                line: 1,
+                column: 12,
+                reason: String::from("unterminated quote"),
+            })
+        );
+        assert!(parse(Format::PowerShell, "set KEY=value").is_err());
+    }
+
+    #[test]
+    fn test_systemd() {
+        let file = r#"
+# Comments start with either
+; character
+  PATH_LIKE = /usr/bin:/bin   
+ESCAPED=a\ b\#c\
+continued
+SINGLE='keeps \n and "quotes"
+across lines'
+DOUBLE="say \"hi\" \$HOME \d \
+joined"
+MIXED='a' "b"c
+EMPTY=
+NOVALUE
+1INVALID=x
+BAD KEY=x
+UNTERMINATED="end"#;
+
+        let mut systemd = Systemd::default();
+        let mut parsed = Vec::new();
+
+        for line in file.lines() {
+            systemd.feed(line, &mut parsed);
+        }
+
+        systemd.finish(&mut parsed);
+
+        assert_eq!(
+            parsed,
+            vars(&[
+                ("PATH_LIKE", "/usr/bin:/bin"),
+                ("ESCAPED", "a b#ccontinued"),
+                ("SINGLE", "keeps \\n and \"quotes\"\nacross lines"),
+                ("DOUBLE", "say \"hi\" $HOME \\d joined"),
+                ("MIXED", "abc"),
+                ("EMPTY", ""),
+                // The newline ending the line belongs to the unterminated value
+                ("UNTERMINATED", "end\n"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_nul_delimited() {
+        assert_eq!(
+            nul_delimited(b"A=1\0B=x=y\0\0NOVALUE\0C=\0D=two\nlines\n\0").unwrap(),
+            vars(&[("A", "1"), ("B", "x=y"), ("C", ""), ("D", "two\nlines\n")])
+        );
+
+        #[cfg(unix)]
+        assert_eq!(
+            nul_delimited(b"A=\xff\0"),
+            Err(Error::InvalidUnicode(
+                std::os::unix::ffi::OsStringExt::from_vec(vec![0xff])
+            ))
+        );
+    }
+}
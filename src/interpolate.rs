@@ -0,0 +1,202 @@
+//! Expanding references to other variables inside values, the way
+//! docker-compose does for `.env` files, see [`crate::Builder::interpolate`]
+
+use crate::{Error, Result};
+use std::{collections::HashMap, env};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// How deep references may go before giving up, which also catches cycles
+const MAX_DEPTH: usize = 16;
+
+/// Expand the references in the values of the variables of `vars` for which
+/// `is_read` holds, looking them up among all of `vars` first and in the
+/// environment of the running process second. The other variables are only
+/// looked up, so a malformed value of a variable that isn't read is no error.
+pub(crate) fn interpolate<F>(vars: &mut [(String, String)], is_read: F) -> Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    // The last occurrence of a key wins, like it does when deserializing
+    let raw: HashMap<String, String> = vars.iter().cloned().collect();
+
+    for (key, value) in vars.iter_mut().filter(|(key, _)| is_read(key)) {
+        *value = expand(key, value, &raw, 0)?;
+    }
+
+    Ok(())
+}
+
+/// Expand the references in `value`, which belongs to `key`:
+///
+/// - `$NAME` and `${NAME}` are replaced with the value of `NAME`,
+///   or nothing if it's unset
+/// - `${NAME:-default}` is replaced with `default` if `NAME` is unset or empty
+/// - `$$` and `\$` are replaced with a literal `$`
+fn expand(
+    key: &str,
+    value: &str,
+    raw: &HashMap<String, String>,
+    depth: usize,
+) -> Result<String> {
+    if depth > MAX_DEPTH {
+        return Err(Error::Interpolation {
+            key: key.to_owned(),
+            reason: format!(
+                "references nest deeper than {} levels, likely a cycle",
+                MAX_DEPTH
+            ),
+        });
+    }
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(i) = rest.find(['$', '\\']) {
+        expanded.push_str(&rest[..i]);
+        let tail = &rest[i..];
+
+        if let Some(tail) = tail
+            .strip_prefix("$$")
+            .or(tail.strip_prefix("\\$"))
+        {
+            expanded.push('$');
+            rest = tail;
+            continue;
+        }
+
+        if let Some(tail) = tail.strip_prefix('\\') {
+            expanded.push('\\');
+            rest = tail;
+            continue;
+        }
+
+        let tail = &tail[1..];
+
+        let (name, default, tail) = match tail.strip_prefix('{') {
+            Some(braced) => {
+                let end =
+                    closing_brace(braced).ok_or_else(|| Error::Interpolation {
+                        key: key.to_owned(),
+                        reason: String::from("unterminated `${`"),
+                    })?;
+                let (name, default) = match braced[..end].split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (&braced[..end], None),
+                };
+
+                (name, default, &braced[end + 1..])
+            }
+            None => {
+                let end = tail
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(tail.len());
+
+                // A lone `$`, such as in `5$`
+                if end == 0 {
+                    expanded.push('$');
+                    rest = tail;
+                    continue;
+                }
+
+                (&tail[..end], None, &tail[end..])
+            }
+        };
+
+        let resolved = match raw.get(name) {
+            Some(value) => Some(expand(name, value, raw, depth + 1)?),
+            None => env::var(name).ok(),
+        };
+
+        match (resolved, default) {
+            (Some(value), Some(default)) if value.is_empty() => {
+                expanded.push_str(&expand(key, default, raw, depth + 1)?)
+            }
+            (Some(value), _) => expanded.push_str(&value),
+            (None, Some(default)) => {
+                expanded.push_str(&expand(key, default, raw, depth + 1)?)
+            }
+            (None, None) => {}
+        }
+
+        rest = tail;
+    }
+
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+/// Find the `}` closing a `${`, whose contents start at the beginning
+/// of `braced`, skipping over the ones nested in defaults
+fn closing_brace(braced: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in braced.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpolate;
+    use crate::Error;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let mut input = vars(&[
+            ("HOST", "localhost"),
+            ("PORT", "5432"),
+            ("URL", "postgres://${HOST}:$PORT/${DB:-app}"),
+            ("PRICE", "$$5 or \\$5 or 5$"),
+            ("NESTED", "${URL}?ssl=${SSL:-${PORT}}"),
+        ]);
+
+        interpolate(&mut input, |_| true).unwrap();
+
+        assert_eq!(
+            input,
+            vars(&[
+                ("HOST", "localhost"),
+                ("PORT", "5432"),
+                ("URL", "postgres://localhost:5432/app"),
+                ("PRICE", "$5 or $5 or 5$"),
+                ("NESTED", "postgres://localhost:5432/app?ssl=5432"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_interpolate_cycle() {
+        let mut input = vars(&[("A", "${B}"), ("B", "${A}")]);
+
+        assert!(matches!(
+            interpolate(&mut input, |_| true),
+            Err(Error::Interpolation { .. })
+        ));
+
+        let mut input = vars(&[("A", "${B")]);
+
+        assert_eq!(
+            interpolate(&mut input, |_| true),
+            Err(Error::Interpolation {
+                key: String::from("A"),
+                reason: String::from("unterminated `${`"),
+            })
+        );
+    }
+}
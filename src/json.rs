@@ -0,0 +1,124 @@
+//! Struct, map and sequence values given as inline JSON, such as
+//! `FEATURES='{"a":true,"b":false}'`, the way orchestrators like
+//! Kubernetes tend to inject whole documents in a single variable
+
+use crate::{Error, Result};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parse `value` as JSON if it starts with `open`, ignoring leading whitespace.
+/// Returns `None` for any other value, to be deserialized as usual
+pub(crate) fn parse(value: &str, open: char) -> Result<Option<serde_json::Value>> {
+    if !value.trim_start().starts_with(open) {
+        return Ok(None);
+    }
+
+    serde_json::from_str(value)
+        .map(Some)
+        .map_err(|e| {
+            Error::Custom(format!("{} while parsing JSON value '{}'", e, value))
+        })
+}
+
+/// Read the fields of the JSON object `value` as `(key, value)` pairs.
+/// String fields are taken as they are, anything else is written as JSON.
+/// Returns `None` if `value` isn't a JSON object
+#[cfg(any(feature = "aws", feature = "vault"))]
+pub(crate) fn object_vars(value: serde_json::Value) -> Option<Vec<(String, String)>> {
+    match value {
+        serde_json::Value::Object(object) => Some(
+            object
+                .into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(value) => (key, value),
+                    value => (key, value.to_string()),
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Convert an error raised while deserializing a parsed JSON value
+pub(crate) fn error(error: serde_json::Error) -> Error {
+    Error::Custom(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_iter, Error};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Features {
+        a: bool,
+        b: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Upstream {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct App {
+        features: Features,
+        upstreams: Vec<Upstream>,
+        labels: HashMap<String, String>,
+        hosts: Vec<String>,
+    }
+
+    #[test]
+    fn test_json_values() {
+        let vars = vec![
+            (
+                String::from("FEATURES"),
+                String::from(r#"{"a":true,"b":false}"#),
+            ),
+            (
+                String::from("UPSTREAMS"),
+                String::from(r#"[{"host":"a","port":80},{"host":"b","port":81}]"#),
+            ),
+            (String::from("LABELS"), String::from(r#"{"env":"prod"}"#)),
+            (String::from("HOSTS"), String::from("a,b")),
+        ];
+
+        assert_eq!(
+            from_iter::<App, _>(vars).unwrap(),
+            App {
+                features: Features { a: true, b: false },
+                upstreams: vec![
+                    Upstream {
+                        host: String::from("a"),
+                        port: 80
+                    },
+                    Upstream {
+                        host: String::from("b"),
+                        port: 81
+                    },
+                ],
+                labels: HashMap::from([(String::from("env"), String::from("prod"))]),
+                hosts: vec![String::from("a"), String::from("b")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let vars = vec![
+            (String::from("FEATURES"), String::from(r#"{"a":true"#)),
+            (String::from("UPSTREAMS"), String::from("[]")),
+            (String::from("LABELS"), String::from("{}")),
+            (String::from("HOSTS"), String::from("")),
+        ];
+
+        assert_eq!(
+            from_iter::<App, _>(vars),
+            Err(Error::Custom(String::from(
+                r#"EOF while parsing an object at line 1 column 9 while parsing JSON value '{"a":true'"#
+            )))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Checking and normalizing the names of environment variables
+//!
+//! POSIX only guarantees that names made of ASCII letters, digits and underscores,
+//! not starting with a digit, can be passed around: shells can't set or read
+//! other names, and some tools drop them. [`validate`] checks a name against these
+//! rules, and [`normalize`] turns any string, such as a config path or a header,
+//! into a name in a [`KeyCase`].
+//!
+//! # Example
+//!
+//! ```
+//! use renvar::{keys, KeyCase};
+//!
+//! assert!(keys::validate("DATABASE_URL").is_ok());
+//! assert!(keys::validate("database.url").is_err());
+//!
+//! assert_eq!(keys::normalize("database.url", KeyCase::ScreamingSnake), "DATABASE_URL");
+//! assert_eq!(keys::normalize("2fa-secret", KeyCase::ScreamingSnake), "_2FA_SECRET");
+//! ```
+
+use crate::{Error, KeyCase, Result};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Check that `key` is a valid POSIX name of an environment variable:
+/// ASCII letters, digits and underscores, not starting with a digit
+///
+/// # Errors
+///
+/// [`Error::InvalidKey`] if `key` is empty, starts with a digit
+/// or contains any other character, along with its offset
+pub fn validate(key: &str) -> Result<()> {
+    let error = |reason: String| Error::InvalidKey {
+        key: key.to_owned(),
+        reason,
+    };
+
+    if key.is_empty() {
+        return Err(error(String::from("empty name")));
+    }
+
+    if key.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(error(String::from("starts with a digit")));
+    }
+
+    match key
+        .char_indices()
+        .find(|(_, c)| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        Some((offset, c)) => Err(error(format!(
+            "invalid character `{}` at offset {}",
+            c.escape_debug(),
+            offset
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Turn `key` into a name written in `case`, replacing every run of characters
+/// that aren't ASCII letters or digits with a single separator, and prepending
+/// an underscore if it would start with a digit.
+///
+/// Names written in [`KeyCase::ScreamingSnake`] or [`KeyCase::Snake`]
+/// always pass [`validate`], unless `key` has no letters or digits at all.
+/// The other cases don't all follow POSIX, since they use dashes or drop separators.
+pub fn normalize(key: &str, case: KeyCase) -> String {
+    let mut words = String::with_capacity(key.len());
+
+    for word in key
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !words.is_empty() {
+            words.push('_');
+        }
+
+        words.push_str(word);
+    }
+
+    let normalized = case.to_key(&words);
+
+    match normalized.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", normalized),
+        false => normalized,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, validate};
+    use crate::{Error, KeyCase};
+
+    #[test]
+    fn test_validate() {
+        for key in ["PATH", "_private", "a1_B2"] {
+            assert_eq!(validate(key), Ok(()));
+        }
+
+        for (key, reason) in [
+            ("", "empty name"),
+            ("1PASSWORD", "starts with a digit"),
+            ("APP-PORT", "invalid character `-` at offset 3"),
+            ("CAFÉ", "invalid character `É` at offset 3"),
+            ("A\nB", "invalid character `\\n` at offset 1"),
+        ] {
+            assert_eq!(
+                validate(key),
+                Err(Error::InvalidKey {
+                    key: key.to_owned(),
+                    reason: reason.to_owned(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("server.tls-cert path", KeyCase::ScreamingSnake),
+            "SERVER_TLS_CERT_PATH"
+        );
+        assert_eq!(
+            normalize("--Max  Connections--", KeyCase::Snake),
+            "max_connections"
+        );
+        assert_eq!(
+            normalize("max.connections", KeyCase::Camel),
+            "maxConnections"
+        );
+        assert_eq!(normalize("db.poolSize", KeyCase::Kebab), "db-pool-size");
+        assert_eq!(normalize("3d.scale", KeyCase::Snake), "_3d_scale");
+        assert_eq!(normalize("naïve", KeyCase::ScreamingSnake), "NA_VE");
+    }
+}
@@ -17,21 +17,160 @@ mod case_insensitive_prefixed;
 mod postfixed;
 #[cfg(feature = "case_insensitive_postfixed")]
 mod case_insensitive_postfixed;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "regex")]
+mod filtered;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "aws")]
+mod aws;
+#[cfg(feature = "vault")]
+mod vault;
+#[cfg(all(windows, feature = "winreg"))]
+mod registry;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "shellwords")]
+mod shellwords;
+mod bytesize;
+mod case;
+mod clock;
 mod error;
+mod file;
+mod format;
+mod flags;
+mod interpolate;
 mod sanitize;
 mod convert;
+mod decode;
+mod describe;
+mod diff;
+mod dir;
+mod explain;
+mod lint;
+mod log_filter;
+mod mock;
+mod options;
+mod parse;
+#[cfg(target_os = "linux")]
+mod pid;
+mod presence;
+mod profiles;
+mod raw;
+mod resolve;
+mod rules;
+mod schema;
+mod snapshot;
+mod source;
+mod template;
+mod validate;
+mod value;
+mod repr;
+mod suggest;
+mod builder;
+mod export;
+mod config;
+mod warning;
 
 pub mod de;
+#[cfg(feature = "humantime")]
+pub mod duration;
+pub mod keys;
+pub mod ser;
+pub mod testing;
 
 pub(crate) mod proc_macros;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-pub use convert::{from_env, from_iter, from_os_env, from_str};
+pub use convert::{
+    from_env, from_iter, from_iter_raw, from_nul_delimited, from_os_env,
+    from_os_env_lossless, from_reader, from_str, from_str_raw,
+    from_str_with_env_override, var, var_opt, EnvPrecedence,
+};
+
+pub use builder::{builder, Builder};
+
+pub use format::Format;
+
+pub use profiles::{profiles, ProfilePosition, Profiles};
+
+pub use options::{DuplicateKeyPolicy, EmptyStringPolicy};
+
+pub use sanitize::TrimPolicy;
+
+pub use decode::Decode;
+
+pub use case::KeyCase;
+
+pub use warning::{Deprecation, Warning, Warnings};
+
+pub use ser::to_vec;
+
+pub use diff::{diff, differ, Differ, FieldDiff};
+
+pub use export::{apply_to_command, export, exporter, to_env_map, Exporter, Quoting};
+
+pub use config::{Change, Config};
+
+pub use lint::{lint, Finding};
+
+pub use raw::RawEnv;
+
+pub use snapshot::EnvSnapshot;
+
+pub use explain::{explain, Explanation, FieldExplanation};
+
+pub use schema::{schema, schema_with_defaults, FieldSpec};
+
+pub use validate::{validate, Validate, ValidationError};
+
+pub use template::{generate_env_example, generate_markdown, template, Template};
+
+pub use flags::FeatureFlags;
+
+pub use bytesize::ByteSize;
+
+pub use clock::{CronSchedule, TimeOfDay, Timestamp};
+
+pub use log_filter::{LogDirective, LogFilter, LogLevel};
+
+pub use resolve::{FileResolver, ValueResolver};
+
+pub use source::{from_source, ProcessEnv, Source};
+
+pub use mock::MockEnv;
+
+pub use dir::DirSource;
+
+#[cfg(target_os = "linux")]
+pub use pid::from_pid;
+
+#[cfg(feature = "aws")]
+pub use aws::{SecretsManagerClient, SecretsManagerSource, SsmClient, SsmSource};
+
+#[cfg(feature = "vault")]
+pub use vault::VaultSource;
+
+#[cfg(all(windows, feature = "winreg"))]
+pub use registry::RegistrySource;
+
+#[cfg(feature = "async_source")]
+pub use source::{from_async_source, AsyncSource};
+
+#[cfg(feature = "watch")]
+pub use watch::{watch_file, Watched};
+
+#[cfg(feature = "otel")]
+pub use otel::to_otel_attributes;
 
 #[cfg(feature = "prefixed")]
 pub use prefixed::{prefixed, Prefixed};
 
+#[cfg(feature = "regex")]
+pub use filtered::{filtered, Filtered};
+
 #[cfg(feature = "case_insensitive_prefixed")]
 pub use case_insensitive_prefixed::{
     case_insensitive_prefixed, CaseInsensitivePrefixed,
@@ -0,0 +1,217 @@
+//! Checking variables against the fields of a struct without deserializing them,
+//! meant for catching mistakes in committed files such as `.env.example` in CI
+
+use crate::describe::{describe, Shape};
+use crate::{suggest, KeyCase, RawEnv};
+use serde::de;
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A mismatch between a variable and the fields of a struct, found by [`lint`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Finding {
+    /// The variable doesn't match any field
+    UnknownKey {
+        /// The name of the variable
+        key: String,
+
+        /// The expected key closest to it, likely what was meant
+        similar: Option<String>,
+    },
+
+    /// The variable matches a field, but not in the expected case.
+    /// Deserializing ignores the case, so it's read anyway
+    WrongCase {
+        /// The name of the variable
+        key: String,
+
+        /// The key the field expects
+        expected: String,
+    },
+
+    /// The variable looks like it sets a part of a field, such as `DATABASE_HOST`
+    /// for a `database` field, but the field takes a single value
+    NestedKey {
+        /// The name of the variable
+        key: String,
+
+        /// The name of the field
+        field: String,
+    },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Finding::UnknownKey {
+                key,
+                similar: Some(similar),
+            } => write!(fmt, "unknown key {}, did you mean {}?", key, similar),
+            Finding::UnknownKey { key, similar: None } => {
+                write!(fmt, "unknown key {}", key)
+            }
+            Finding::WrongCase { key, expected } => {
+                write!(fmt, "key {} should be written as {}", key, expected)
+            }
+            Finding::NestedKey { key, field } => write!(
+                fmt,
+                "key {} looks nested, but field `{}` takes a single value",
+                key, field
+            ),
+        }
+    }
+}
+
+/// Compare the keys of `raw` with the ones the fields of the struct `T` expect,
+/// written in SCREAMING_SNAKE_CASE, and report every mismatch. Nothing is deserialized,
+/// so the values are never looked at and missing variables aren't reported.
+///
+/// Returns no findings for anything other than a struct with named fields,
+/// including structs with flattened fields, whose keys can't be known in advance.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{lint, Finding, RawEnv};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     database: String,
+///     port: u16,
+/// }
+///
+/// let raw = RawEnv::parse("DATABASE_HOST=localhost\nPort=8080\nPORTT=8080\n");
+///
+/// assert_eq!(
+///     lint::<App>(&raw),
+///     vec![
+///         Finding::NestedKey {
+///             key: "DATABASE_HOST".to_owned(),
+///             field: "database".to_owned()
+///         },
+///         Finding::WrongCase {
+///             key: "Port".to_owned(),
+///             expected: "PORT".to_owned()
+///         },
+///         Finding::UnknownKey {
+///             key: "PORTT".to_owned(),
+///             similar: Some("PORT".to_owned())
+///         },
+///     ]
+/// );
+/// ```
+pub fn lint<'de, T>(raw: &RawEnv) -> Vec<Finding>
+where
+    T: de::Deserialize<'de>,
+{
+    let fields = describe::<T>();
+
+    if fields.is_empty() {
+        return Vec::new();
+    }
+
+    let expected: Vec<(String, _)> = fields
+        .iter()
+        .map(|field| (KeyCase::ScreamingSnake.to_key(field.name), field))
+        .collect();
+
+    raw.keys()
+        .filter_map(|key| {
+            if expected
+                .iter()
+                .any(|(expected, _)| expected == key)
+            {
+                return None;
+            }
+
+            if let Some((expected, _)) = expected
+                .iter()
+                .find(|(expected, _)| expected.eq_ignore_ascii_case(key))
+            {
+                return Some(Finding::WrongCase {
+                    key: key.to_owned(),
+                    expected: expected.clone(),
+                });
+            }
+
+            // Variables prefixed with the name of a struct or map field make up that field
+            let scoped = expected.iter().any(|(expected, field)| {
+                field.shape == Shape::Map
+                    && key.len() > expected.len() + 1
+                    && key.starts_with(expected.as_str())
+                    && key[expected.len()..].starts_with('_')
+            });
+
+            if scoped {
+                return None;
+            }
+
+            let nested = expected.iter().find(|(expected, field)| {
+                field.shape.is_scalar()
+                    && key.len() > expected.len() + 1
+                    && key.get(..expected.len() + 1).is_some_and(|head| {
+                        head.eq_ignore_ascii_case(&format!("{}_", expected))
+                    })
+            });
+
+            if let Some((_, field)) = nested {
+                return Some(Finding::NestedKey {
+                    key: key.to_owned(),
+                    field: field.name.to_owned(),
+                });
+            }
+
+            Some(Finding::UnknownKey {
+                key: key.to_owned(),
+                similar: suggest::closest(
+                    &key.to_uppercase(),
+                    expected
+                        .iter()
+                        .map(|(expected, _)| expected.as_str()),
+                )
+                .map(str::to_owned),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Finding};
+    use crate::RawEnv;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct App {
+        database_url: String,
+        hosts: Vec<String>,
+        labels: HashMap<String, String>,
+    }
+
+    #[test]
+    fn test_lint() {
+        let raw = RawEnv::parse(
+            "DATABASE_URL=postgres://localhost\nHOSTS_0=a\nLABELS_ENV=prod\nUNRELATED=1\n",
+        );
+
+        assert_eq!(
+            lint::<App>(&raw),
+            vec![
+                Finding::NestedKey {
+                    key: String::from("HOSTS_0"),
+                    field: String::from("hosts"),
+                },
+                Finding::UnknownKey {
+                    key: String::from("UNRELATED"),
+                    similar: None,
+                },
+            ]
+        );
+        assert!(lint::<HashMap<String, String>>(&raw).is_empty());
+    }
+}
@@ -0,0 +1,423 @@
+//! Log levels and `RUST_LOG`-style filters, for keeping the logging
+//! setup in the same config struct as everything else
+
+use crate::Error;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// How verbose logging is, from [`LogLevel::Off`] to [`LogLevel::Trace`],
+/// parsed ignoring case, so `info`, `INFO` and `Info` are all [`LogLevel::Info`].
+/// `warning` is accepted for [`LogLevel::Warn`].
+///
+/// With the `log` and `tracing` features, it converts
+/// into `log::LevelFilter` and `tracing::level_filters::LevelFilter`.
+/// `log::LevelFilter` deserializes itself too with the `log` feature,
+/// from uppercase names, or any case with [`crate::Builder::lenient_variants`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Nothing is logged
+    Off,
+
+    /// Only errors
+    Error,
+
+    /// Warnings and errors
+    Warn,
+
+    /// Informational messages and above
+    Info,
+
+    /// Debugging messages and above
+    Debug,
+
+    /// Everything
+    Trace,
+}
+
+impl LogLevel {
+    /// The name of the level, in lowercase
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    /// The level called `name`, ignoring case
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            LogLevel::Off,
+            LogLevel::Error,
+            LogLevel::Warn,
+            LogLevel::Info,
+            LogLevel::Debug,
+            LogLevel::Trace,
+        ]
+        .into_iter()
+        .find(|level| level.as_str().eq_ignore_ascii_case(name))
+        .or_else(|| {
+            name.eq_ignore_ascii_case("warning")
+                .then_some(LogLevel::Warn)
+        })
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        LogLevel::from_name(value.trim()).ok_or_else(|| {
+            Error::Custom(format!(
+                "expected one of off, error, warn, info, debug or trace while parsing value '{}'",
+                value
+            ))
+        })
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl From<LogLevel> for tracing::level_filters::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        use tracing::level_filters::LevelFilter;
+
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// A directive of a [`LogFilter`], setting the level of a target,
+/// or the default level if there's no target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogDirective {
+    /// The target, usually a module path such as `hyper::client`, along with
+    /// any span filter of `tracing_subscriber`, such as `app[request]`
+    pub target: Option<String>,
+
+    /// The level of the target, [`LogLevel::Trace`] if the directive names
+    /// a target without a level
+    pub level: LogLevel,
+}
+
+/// A filter written like `RUST_LOG`, which is understood by `env_logger`
+/// and `tracing_subscriber::EnvFilter`: directives separated by commas, each
+/// being a default level, such as `info`, a target, such as `my_app`, which
+/// enables every level for it, or a target and its level, such as `hyper=warn`.
+///
+/// Deserializing checks the levels of the directives, and the filter is
+/// kept as it was written, so it can be handed to the logger with [`LogFilter::as_str`],
+/// such as `EnvFilter::new(config.log.as_str())`.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, LogFilter, LogLevel};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     log: LogFilter,
+///     audit_level: LogLevel,
+/// }
+///
+/// let vars = vec![
+///     ("LOG".to_owned(), "info,my_app=debug,hyper::client=warn".to_owned()),
+///     ("AUDIT_LEVEL".to_owned(), "WARN".to_owned()),
+/// ];
+///
+/// let custom_struct: CustomStruct = from_iter(vars).unwrap();
+///
+/// assert_eq!(custom_struct.log.default_level(), Some(LogLevel::Info));
+/// assert_eq!(custom_struct.log.level_for("my_app::db"), Some(LogLevel::Debug));
+/// assert_eq!(custom_struct.log.level_for("hyper"), Some(LogLevel::Info));
+/// assert_eq!(custom_struct.log.max_level(), LogLevel::Debug);
+/// assert_eq!(custom_struct.audit_level, LogLevel::Warn);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    filter: String,
+    directives: Vec<LogDirective>,
+}
+
+impl LogFilter {
+    /// The filter as it was written
+    pub fn as_str(&self) -> &str {
+        &self.filter
+    }
+
+    /// The directives of the filter, in the order they were written
+    pub fn directives(&self) -> &[LogDirective] {
+        &self.directives
+    }
+
+    /// The level of the targets no directive names, the last one given
+    pub fn default_level(&self) -> Option<LogLevel> {
+        self.directives
+            .iter()
+            .rev()
+            .find(|directive| directive.target.is_none())
+            .map(|directive| directive.level)
+    }
+
+    /// The level of `target`, from the directive naming the longest
+    /// module path it's in, or the default level if none does
+    pub fn level_for(&self, target: &str) -> Option<LogLevel> {
+        self.directives
+            .iter()
+            .filter_map(|directive| {
+                let name = directive.target.as_deref()?;
+
+                target
+                    .strip_prefix(name)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+                    .then_some((name.len(), directive.level))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, level)| level)
+            .or_else(|| self.default_level())
+    }
+
+    /// The most verbose level of any directive, which
+    /// loggers use to skip the messages no target would accept
+    pub fn max_level(&self) -> LogLevel {
+        self.directives
+            .iter()
+            .map(|directive| directive.level)
+            .max()
+            .unwrap_or(LogLevel::Off)
+    }
+
+    /// Parse the directive `directive`, returning why it can't be
+    fn parse_directive(directive: &str) -> Result<LogDirective, String> {
+        match directive.rsplit_once('=') {
+            // `=` may also appear within the field filters of a span
+            Some((target, level)) if !level.contains(']') => Ok(LogDirective {
+                target: Some(target.trim().to_owned()),
+                level: LogLevel::from_name(level.trim())
+                    .ok_or_else(|| format!("unknown level `{}`", level.trim()))?,
+            }),
+            _ => Ok(match LogLevel::from_name(directive) {
+                Some(level) => LogDirective {
+                    target: None,
+                    level,
+                },
+                None => LogDirective {
+                    target: Some(directive.to_owned()),
+                    level: LogLevel::Trace,
+                },
+            }),
+        }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let directives = split_directives(value)
+            .into_iter()
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(LogFilter::parse_directive)
+            .collect::<Result<_, _>>()
+            .map_err(|reason| {
+                Error::Custom(format!("{} while parsing value '{}'", reason, value))
+            })?;
+
+        Ok(LogFilter {
+            filter: value.trim().to_owned(),
+            directives,
+        })
+    }
+}
+
+/// Split `filter` on the commas separating its directives, leaving alone the
+/// ones within the span and field filters, such as `app[span{a=1,b=2}]=debug`
+fn split_directives(filter: &str) -> Vec<&str> {
+    let mut directives = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, c) in filter.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                directives.push(&filter[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    directives.push(&filter[start..]);
+    directives
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.filter)
+    }
+}
+
+/// Deserialize a type parsed from a string with [`FromStr`]
+macro_rules! deserialize_from_str {
+    ($($typ:ident => $expecting:literal,)*) => {
+        $(
+            impl Serialize for $typ {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $typ {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    struct FromStrVisitor;
+
+                    impl de::Visitor<'_> for FromStrVisitor {
+                        type Value = $typ;
+
+                        fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                            fmt.write_str($expecting)
+                        }
+
+                        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+                        where
+                            E: de::Error,
+                        {
+                            value.parse().map_err(E::custom)
+                        }
+                    }
+
+                    deserializer.deserialize_str(FromStrVisitor)
+                }
+            }
+        )*
+    };
+}
+
+pub(crate) use deserialize_from_str;
+
+deserialize_from_str! {
+    LogLevel => "a log level",
+    LogFilter => "a log filter, such as `info,my_app=debug`",
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogDirective, LogFilter, LogLevel};
+    use crate::Error;
+
+    #[test]
+    fn test_log_filter() {
+        let filter: LogFilter =
+            "warn, my_app=DEBUG,tokio,app[request{id=1}]=trace,db[query{a=1,b=2}]=debug"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            filter.directives(),
+            &[
+                LogDirective {
+                    target: None,
+                    level: LogLevel::Warn,
+                },
+                LogDirective {
+                    target: Some(String::from("my_app")),
+                    level: LogLevel::Debug,
+                },
+                LogDirective {
+                    target: Some(String::from("tokio")),
+                    level: LogLevel::Trace,
+                },
+                LogDirective {
+                    target: Some(String::from("app[request{id=1}]")),
+                    level: LogLevel::Trace,
+                },
+                LogDirective {
+                    target: Some(String::from("db[query{a=1,b=2}]")),
+                    level: LogLevel::Debug,
+                },
+            ]
+        );
+        assert_eq!(filter.level_for("my_app"), Some(LogLevel::Debug));
+        assert_eq!(filter.level_for("my_app_extra"), Some(LogLevel::Warn));
+        assert_eq!(filter.max_level(), LogLevel::Trace);
+        assert_eq!(
+            "".parse::<LogFilter>()
+                .map(|filter| filter.default_level()),
+            Ok(None)
+        );
+        assert_eq!(
+            "my_app=loud".parse::<LogFilter>(),
+            Err(Error::Custom(String::from(
+                "unknown level `loud` while parsing value 'my_app=loud'"
+            )))
+        );
+        assert_eq!("Warning".parse::<LogLevel>(), Ok(LogLevel::Warn));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_level_filter() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Logging {
+            level: log::LevelFilter,
+            audit: LogLevel,
+        }
+
+        let vars = vec![
+            (String::from("LEVEL"), String::from("debug")),
+            (String::from("AUDIT"), String::from("error")),
+        ];
+        let logging: Logging = crate::builder()
+            .lenient_variants(true)
+            .from_iter(vars)
+            .unwrap();
+
+        assert_eq!(logging.level, log::LevelFilter::Debug);
+        assert_eq!(
+            log::LevelFilter::from(logging.audit),
+            log::LevelFilter::Error
+        );
+    }
+}
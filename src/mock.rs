@@ -0,0 +1,176 @@
+//! An in-memory stand-in for the environment of the running process
+
+use crate::{from_iter, Builder, Result, Source};
+use serde::de;
+use std::collections::BTreeMap;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Variables held in memory, deserialized like the environment of the running
+/// process would be, so tests never have to modify the actual environment.
+///
+/// [`MockEnv::from_env`] mirrors [`crate::from_env`], and since it implements [`Source`],
+/// any [`Builder`] can read from it with [`Builder::from_source`], so for example
+/// `builder().prefix("APP_").from_source(&mock)` stands in for `prefixed("APP_").from_env()`.
+/// Application code taking any `E: Source` can be handed a [`crate::ProcessEnv`] in
+/// production and a [`MockEnv`] in tests.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, MockEnv, ProcessEnv, Result, Source};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// fn load<E: Source>(env: &E) -> Result<Server> {
+///     builder().prefix("APP_").from_source(env)
+/// }
+///
+/// let env = MockEnv::new()
+///     .var("APP_HOST", "localhost")
+///     .var("APP_PORT", "8080");
+///
+/// assert_eq!(
+///     load(&env).unwrap(),
+///     Server {
+///         host: "localhost".to_owned(),
+///         port: 8080,
+///     }
+/// );
+///
+/// // in production
+/// let _ = load(&ProcessEnv);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockEnv {
+    vars: BTreeMap<String, String>,
+}
+
+impl MockEnv {
+    /// Construct a [`MockEnv`] without any variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the variable `key` to `value`, chaining
+    pub fn var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Set the variable `key` to `value`, returning its previous value
+    pub fn set(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<String> {
+        self.vars.insert(key.into(), value.into())
+    }
+
+    /// Remove the variable `key`, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.vars.remove(key)
+    }
+
+    /// The value of the variable `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(String::as_str)
+    }
+
+    /// Deserialize some type `T` from the variables,
+    /// like [`crate::from_env`] does from the environment
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        from_iter(self.vars.clone())
+    }
+
+    /// Deserialize some type `T` from the variables with the settings of `builder`,
+    /// like [`Builder::from_env`] does from the environment
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_env_with<T>(&self, builder: &Builder) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        builder.from_source(self)
+    }
+}
+
+impl Source for MockEnv {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.vars.clone().into_iter().collect())
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for MockEnv
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            vars: iter
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockEnv;
+    use crate::{builder, Error};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Database {
+        url: String,
+        pool_size: Option<u8>,
+    }
+
+    #[test]
+    fn test_mock_env() {
+        let mut env: MockEnv =
+            [("DB_URL", "postgres://localhost"), ("DB_POOL_SIZE", "4")]
+                .into_iter()
+                .collect();
+        let builder = builder().prefix("DB_");
+
+        assert_eq!(
+            env.from_env_with::<Database>(&builder).unwrap(),
+            Database {
+                url: String::from("postgres://localhost"),
+                pool_size: Some(4),
+            }
+        );
+
+        assert_eq!(env.remove("DB_POOL_SIZE"), Some(String::from("4")));
+        assert_eq!(
+            env.from_env_with::<Database>(&builder)
+                .unwrap()
+                .pool_size,
+            None
+        );
+
+        env.remove("DB_URL");
+        assert_eq!(
+            env.from_env_with::<Database>(&builder)
+                .unwrap_err(),
+            Error::MissingValue(String::from("url"))
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Settings shared between the [`crate::Builder`] and the deserializers
+
+use crate::value::Value;
+use crate::{describe, repr, Error, KeyCase, Result, TrimPolicy, Warning};
+use serde::Deserialize;
+use std::{collections::HashSet, fmt, sync::Arc};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserialization settings, collected by [`crate::Builder`]
+/// and handed down to [`crate::de::EnvVarDeserializer`]
+///
+/// The defaults reproduce the behaviour of the free functions,
+/// such as [`crate::from_iter`] or [`crate::from_env`]
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    /// Name of the struct field that receives every variable
+    /// not consumed by any other field
+    pub(crate) capture_rest: Option<String>,
+
+    /// Fail on values that contain NUL or other control characters
+    pub(crate) reject_control_chars: bool,
+
+    /// Accept `1/0`, `yes/no`, `on/off` and `true/false` for booleans, ignoring case
+    pub(crate) lenient_bools: bool,
+
+    /// Match enum variants ignoring case, dashes and underscores
+    pub(crate) lenient_variants: bool,
+
+    /// Accept underscores, radix prefixes and unit suffixes in numbers
+    pub(crate) lenient_numbers: bool,
+
+    /// Fail on variables that don't match any field of the target struct
+    pub(crate) deny_unknown: bool,
+
+    /// Fail on duplicate elements in sequences and sets rather than keeping them
+    pub(crate) deny_duplicate_elements: bool,
+
+    /// Wrap errors in [`crate::Error::AtPath`] with the path of the field they belong to
+    pub(crate) track_paths: bool,
+
+    /// What to do when several variables map to the same key
+    pub(crate) duplicate_keys: DuplicateKeyPolicy,
+
+    /// Naming convention of the keys, used to match them with struct fields
+    pub(crate) key_case: KeyCase,
+
+    /// Naming convention of the struct fields, `None` for snake_case
+    pub(crate) field_case: Option<KeyCase>,
+
+    /// Leave keys and values untouched: no case folding of the keys
+    /// and no trimming of the elements of sequences
+    pub(crate) raw: bool,
+
+    /// Match keys with struct fields exactly as they're written,
+    /// without converting them from `key_case` to `field_case`
+    pub(crate) exact_keys: bool,
+
+    /// Leave quotes around the elements of sequences and tuples,
+    /// trimming only whitespace
+    pub(crate) preserve_quotes: bool,
+
+    /// How the elements of sequences and tuples are trimmed,
+    /// overriding `raw` and `preserve_quotes`
+    pub(crate) trim_elements: Option<TrimPolicy>,
+
+    /// What to do with variables that are set to an empty string
+    pub(crate) empty_strings: EmptyStringPolicy,
+
+    /// Deserialize empty values as `None` rather than as empty sequences
+    /// for options of sequences, such as `Option<Vec<T>>`
+    pub(crate) empty_seqs_as_none: bool,
+
+    /// Separates the elements of tuples and tuple structs
+    pub(crate) tuple_delimiter: char,
+
+    /// Prefixes of the variables nested struct fields are read from, by the path
+    /// of the field, such as `db.primary`, overriding the name of the field
+    pub(crate) scopes: Vec<(String, String)>,
+
+    /// Paths of the fields of the target type that hold structs or maps,
+    /// which are read from the variables prefixed with their name when no
+    /// prefix is registered for them. `None` if the target type isn't known,
+    /// which reads nested fields only from the registered prefixes
+    pub(crate) nested: Option<Arc<[String]>>,
+
+    /// Names of the fields of the target struct, which tell whether an
+    /// `Option<T>` is set. `None` if they aren't known, in which case
+    /// any variable sets it
+    pub(crate) fields: Option<Vec<&'static str>>,
+
+    /// Names of the variables that must match a field of the target struct
+    pub(crate) must_match: HashSet<String>,
+
+    /// Receives the non-fatal issues found while deserializing
+    pub(crate) on_warning: Option<WarningHook>,
+
+    /// Receives the variables that didn't match any field of the target struct
+    pub(crate) on_unused_key: Option<UnusedKeyHook>,
+
+    /// Parsers that replace the usual parsing of the values of some
+    /// variables, by the field name or variable name they're registered for
+    pub(crate) parsers: Vec<(String, ParserHook)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            capture_rest: None,
+            reject_control_chars: false,
+            lenient_bools: false,
+            lenient_variants: false,
+            lenient_numbers: false,
+            deny_unknown: false,
+            deny_duplicate_elements: false,
+            track_paths: false,
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            key_case: KeyCase::default(),
+            field_case: None,
+            raw: false,
+            exact_keys: false,
+            preserve_quotes: false,
+            trim_elements: None,
+            empty_strings: EmptyStringPolicy::default(),
+            empty_seqs_as_none: false,
+            tuple_delimiter: repr::SEQ_DELIMITER,
+            scopes: Vec::new(),
+            nested: None,
+            fields: None,
+            must_match: HashSet::new(),
+            on_warning: None,
+            on_unused_key: None,
+            parsers: Vec::new(),
+        }
+    }
+}
+
+impl Options {
+    /// The key of the variable the field `field` is read from
+    pub(crate) fn to_key(&self, field: &str) -> String {
+        match self.exact_keys {
+            true => field.to_owned(),
+            false => self.key_case.to_key(field),
+        }
+    }
+
+    /// Hand `warning` to the callback, if there's one
+    pub(crate) fn warn(&self, warning: Warning) {
+        if let Some(on_warning) = &self.on_warning {
+            (on_warning.0)(&warning);
+        }
+    }
+
+    /// The prefix set up for the field at `path`, if any
+    pub(crate) fn scope(&self, path: &str) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|(registered, _)| registered == path)
+            .map(|(_, prefix)| prefix.as_str())
+    }
+
+    /// Learn the fields of `T` and which of them can be read from prefixed variables
+    pub(crate) fn nest<'de, T>(&mut self)
+    where
+        T: Deserialize<'de>,
+    {
+        let layout = describe::layout::<T>();
+
+        self.fields = (!layout.fields.is_empty()).then(|| layout.fields.clone());
+        self.nested = Some(Arc::clone(&layout.nested));
+    }
+
+    /// The parser registered for the field `key` or the variable `name`, if any
+    pub(crate) fn parser(&self, key: &str, name: &str) -> Option<&ParserHook> {
+        self.parsers
+            .iter()
+            .rev()
+            .find(|(registered, _)| registered == key || registered == name)
+            .map(|(_, parser)| parser)
+    }
+}
+
+/// What to do when several variables map to the same key, such as
+/// `APP_key` and `APP_KEY`, or a key repeated in a `.env` file.
+/// Set with [`crate::Builder::duplicate_keys`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Keep the value of the first variable
+    FirstWins,
+
+    /// Keep the value of the last variable, like a shell would
+    #[default]
+    LastWins,
+
+    /// Fail with [`crate::Error::DuplicateKey`]
+    Error,
+}
+
+/// What to do with variables that are set to an empty string, such as `KEY=`,
+/// as opposed to variables that aren't set at all.
+/// Set with [`crate::Builder::empty_strings`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyStringPolicy {
+    /// `Option` fields are `None`, as if the variable wasn't set,
+    /// while other fields, such as `String`s, are deserialized from the empty string
+    #[default]
+    TreatAsNone,
+
+    /// Every field is deserialized from the empty string,
+    /// so `Option<String>` fields are `Some(String::new())`
+    TreatAsEmpty,
+
+    /// Fail with [`crate::Error::EmptyValue`]
+    Error,
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A user supplied callback, cheap to clone along with the settings
+pub(crate) struct Hook<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("Hook")
+    }
+}
+
+/// Callback that receives non-fatal errors
+pub(crate) type ErrorHook = Hook<dyn Fn(&Error) + Send + Sync>;
+
+/// Callback that receives warnings
+pub(crate) type WarningHook = Hook<dyn Fn(&Warning) + Send + Sync>;
+
+/// Callback that receives the key and the value of a variable
+pub(crate) type UnusedKeyHook = Hook<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Callback that rewrites the value of a variable, given its key
+pub(crate) type ValueHook = Hook<dyn Fn(&str, &str) -> String + Send + Sync>;
+
+/// Callback that parses the value of a variable into whatever its field holds
+pub(crate) type ParserHook = Hook<dyn Fn(&str) -> Result<Value> + Send + Sync>;
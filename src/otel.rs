@@ -0,0 +1,63 @@
+use crate::{to_vec, Result};
+use opentelemetry::KeyValue;
+use serde::Serialize;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Convert the selected fields of some config `T` into OpenTelemetry
+/// [`KeyValue`] attributes, to be attached to a `Resource` or to baggage.
+///
+/// The attribute keys are the same keys produced by [`crate::to_vec`],
+/// so traces carry the configuration under the names it's set with.
+/// Only the fields listed in `fields` are exported, which keeps secrets
+/// out of the telemetry pipeline unless explicitly selected.
+/// Field names are matched ignoring case.
+///
+/// # Errors
+///
+/// Any errors that might occur during serialization
+///
+/// # Example
+///
+/// ```
+/// use opentelemetry::KeyValue;
+/// use renvar::to_otel_attributes;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     region: String,
+///     workers: u16,
+///     api_key: String,
+/// }
+///
+/// let config = Config {
+///     region: String::from("eu-west-1"),
+///     workers: 4,
+///     api_key: String::from("hunter2"),
+/// };
+///
+/// let attributes = to_otel_attributes(&config, &["region", "workers"]).unwrap();
+///
+/// assert_eq!(
+///     attributes,
+///     vec![
+///         KeyValue::new("REGION", "eu-west-1"),
+///         KeyValue::new("WORKERS", "4"),
+///     ]
+/// )
+/// ```
+pub fn to_otel_attributes<T>(value: &T, fields: &[&str]) -> Result<Vec<KeyValue>>
+where
+    T: ?Sized + Serialize,
+{
+    Ok(to_vec(value)?
+        .into_iter()
+        .filter(|(key, _)| {
+            fields
+                .iter()
+                .any(|field| field.eq_ignore_ascii_case(key))
+        })
+        .map(|(key, value)| KeyValue::new(key, value))
+        .collect())
+}
@@ -0,0 +1,231 @@
+//! Parsers for the string representation of values,
+//! for the cases where [`std::str::FromStr`] is too strict
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parse the common truthy and falsy strings, ignoring case:
+///
+/// * `1`, `yes`, `on`, `true`
+/// * `0`, `no`, `off`, `false`
+pub(crate) fn parse_lenient_bool(value: &str) -> Option<bool> {
+    const TRUTHY: &[&str] = &["1", "yes", "on", "true"];
+    const FALSY: &[&str] = &["0", "no", "off", "false"];
+
+    if TRUTHY
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(value))
+    {
+        Some(true)
+    } else if FALSY
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(value))
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Decimal and binary multipliers accepted after integers, such as `8k` or `16Mi`
+const UNITS: &[(&str, u128)] = &[
+    ("k", 1_000),
+    ("K", 1_000),
+    ("M", 1_000_000),
+    ("G", 1_000_000_000),
+    ("T", 1_000_000_000_000),
+    ("P", 1_000_000_000_000_000),
+    ("E", 1_000_000_000_000_000_000),
+    ("Ki", 1 << 10),
+    ("Mi", 1 << 20),
+    ("Gi", 1 << 30),
+    ("Ti", 1 << 40),
+    ("Pi", 1 << 50),
+    ("Ei", 1 << 60),
+];
+
+/// Parse an integer written with underscores between its digits, such as `1_000_000`,
+/// in hexadecimal, octal or binary with a `0x`, `0o` or `0b` prefix, or followed
+/// by a decimal or binary unit, such as `8k` for 8000 or `16Mi` for 16 × 2²⁰.
+/// Returns whether the integer is negative and its magnitude
+fn parse_lenient_integer(value: &str) -> Result<(bool, u128), String> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    let radix = match value
+        .get(..2)
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("0x") => Some(16),
+        Some("0o") => Some(8),
+        Some("0b") => Some(2),
+        _ => None,
+    };
+
+    let (digits, radix, multiplier) = match radix {
+        Some(radix) => (&value[2..], radix, 1),
+        None => {
+            let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let unit = &value[digits.len()..];
+            let multiplier = match unit {
+                "" => 1,
+                unit => UNITS
+                    .iter()
+                    .find(|(name, _)| *name == unit)
+                    .map(|(_, multiplier)| *multiplier)
+                    .ok_or_else(|| format!("unknown unit `{}`", unit))?,
+            };
+
+            (digits.trim_end(), 10, multiplier)
+        }
+    };
+
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return Err(String::from("misplaced underscore"));
+    }
+
+    let magnitude = u128::from_str_radix(&digits.replace('_', ""), radix)
+        .map_err(|error| error.to_string())?
+        .checked_mul(multiplier)
+        .ok_or_else(|| String::from("number too large to fit in target type"))?;
+
+    Ok((negative, magnitude))
+}
+
+/// Numbers parsed leniently, as with [`crate::Builder::lenient_numbers`]
+pub(crate) trait LenientNumber: Sized {
+    fn parse_lenient(value: &str) -> Result<Self, String>;
+}
+
+macro_rules! lenient_integers {
+    ($($typ:ty)*) => {
+        $(
+            impl LenientNumber for $typ {
+                fn parse_lenient(value: &str) -> Result<Self, String> {
+                    let (negative, magnitude) = parse_lenient_integer(value)?;
+
+                    let parsed = match negative {
+                        false => Self::try_from(magnitude).ok(),
+                        true => 0i128
+                            .checked_sub_unsigned(magnitude)
+                            .and_then(|signed| Self::try_from(signed).ok()),
+                    };
+
+                    parsed.ok_or_else(|| match negative {
+                        false => String::from("number too large to fit in target type"),
+                        true => String::from("number too small to fit in target type"),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+lenient_integers! { u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 }
+
+/// Floats only have their underscores removed
+macro_rules! lenient_floats {
+    ($($typ:ty)*) => {
+        $(
+            impl LenientNumber for $typ {
+                fn parse_lenient(value: &str) -> Result<Self, String> {
+                    value.replace('_', "").parse().map_err(|error: std::num::ParseFloatError| error.to_string())
+                }
+            }
+        )*
+    };
+}
+
+lenient_floats! { f32 f64 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_lenient_bool, LenientNumber};
+    use crate::builder;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Flags {
+        debug: bool,
+        verbose: bool,
+        color: bool,
+        dry_run: bool,
+    }
+
+    #[test]
+    fn test_parse_lenient_bool() {
+        for truthy in ["1", "yes", "YES", "On", "true", "TRUE"] {
+            assert_eq!(parse_lenient_bool(truthy), Some(true));
+        }
+
+        for falsy in ["0", "no", "No", "OFF", "false", "False"] {
+            assert_eq!(parse_lenient_bool(falsy), Some(false));
+        }
+
+        assert_eq!(parse_lenient_bool("maybe"), None);
+        assert_eq!(parse_lenient_bool(""), None);
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        let vars = vec![
+            (String::from("DEBUG"), String::from("1")),
+            (String::from("VERBOSE"), String::from("off")),
+            (String::from("COLOR"), String::from("Yes")),
+            (String::from("DRY_RUN"), String::from("FALSE")),
+        ];
+
+        let actual = builder()
+            .lenient_bools(true)
+            .from_iter::<Flags, _>(vars.clone())
+            .unwrap();
+
+        assert_eq!(
+            actual,
+            Flags {
+                debug: true,
+                verbose: false,
+                color: true,
+                dry_run: false,
+            }
+        );
+
+        assert!(builder().from_iter::<Flags, _>(vars).is_err());
+    }
+
+    #[test]
+    fn test_parse_lenient_numbers() {
+        assert_eq!(u32::parse_lenient("1_000_000"), Ok(1_000_000));
+        assert_eq!(u8::parse_lenient("0x1F"), Ok(31));
+        assert_eq!(u8::parse_lenient("0b1010_1010"), Ok(0b1010_1010));
+        assert_eq!(u16::parse_lenient("0o777"), Ok(0o777));
+        assert_eq!(u64::parse_lenient("8k"), Ok(8_000));
+        assert_eq!(u64::parse_lenient("16Mi"), Ok(16 * 1024 * 1024));
+        assert_eq!(u64::parse_lenient("2 Gi"), Ok(2 << 30));
+        assert_eq!(i32::parse_lenient("-4K"), Ok(-4_000));
+        assert_eq!(i8::parse_lenient("-128"), Ok(i8::MIN));
+        assert_eq!(i128::parse_lenient(&i128::MIN.to_string()), Ok(i128::MIN));
+        assert_eq!(f64::parse_lenient("1_000.5"), Ok(1000.5));
+
+        assert_eq!(
+            u16::parse_lenient("64Ki"),
+            Err(String::from("number too large to fit in target type"))
+        );
+        assert_eq!(
+            u8::parse_lenient("-1"),
+            Err(String::from("number too small to fit in target type"))
+        );
+        assert_eq!(
+            u32::parse_lenient("8kb"),
+            Err(String::from("unknown unit `kb`"))
+        );
+        assert_eq!(
+            u32::parse_lenient("1__000"),
+            Err(String::from("misplaced underscore"))
+        );
+        assert!(u32::parse_lenient("0x1k").is_err());
+        assert!(u32::parse_lenient("k").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Reading the environment of other processes, through `/proc` on Linux
+
+use crate::{format, from_iter, Error, Result};
+use serde::de;
+use std::{fs, path::PathBuf};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Deserialize some type `T` from the environment of the process with the id `pid`,
+/// as it was when the process started, read from `/proc/<pid>/environ`.
+/// Changes the process made to its own environment afterwards aren't visible.
+///
+/// Like with [`crate::from_iter`], single quotes, double quotes
+/// and whitespace will be trimmed
+///
+/// # Errors
+///
+/// If the environment can't be read, usually because the process doesn't exist
+/// or belongs to another user, if it contains invalid unicode,
+/// or any errors that might occur during deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::from_pid;
+/// use std::collections::HashMap;
+/// use std::process;
+///
+/// let vars: HashMap<String, String> = from_pid(process::id()).unwrap();
+/// ```
+pub fn from_pid<T>(pid: u32) -> Result<T>
+where
+    T: de::DeserializeOwned,
+{
+    from_iter(environ(pid)?)
+}
+
+/// Read the `(key, value)` pairs of the environment of the process with the id `pid`
+pub(crate) fn environ(pid: u32) -> Result<Vec<(String, String)>> {
+    let path = PathBuf::from(format!("/proc/{}/environ", pid));
+    let bytes = fs::read(&path).map_err(|e| Error::Io {
+        path,
+        reason: e.to_string(),
+    })?;
+
+    format::nul_delimited(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_pid, Error};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_from_pid() {
+        assert!(!from_pid::<HashMap<String, String>>(std::process::id())
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            from_pid::<HashMap<String, String>>(u32::MAX),
+            Err(Error::Io { .. })
+        ));
+    }
+}
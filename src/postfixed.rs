@@ -1,7 +1,7 @@
 use crate::convert::maybe_invalid_unicode_vars_os;
-use crate::{from_iter, Result};
+use crate::{builder, from_iter, Builder, Result};
 use serde::de;
-use std::{env, string::String};
+use std::{borrow::Cow, env, string::String};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -18,8 +18,8 @@ use std::{env, string::String};
 ///
 /// assert_eq!(with_postfix.postfix(), "_APP")
 /// ```
-#[derive(Debug)]
-pub struct Postfixed<'a>(&'a str);
+#[derive(Debug, Clone)]
+pub struct Postfixed<'a>(Cow<'a, str>);
 
 impl<'a> Postfixed<'a> {
     /// Deserialize some type `T` from a snapshot of the currently
@@ -155,25 +155,89 @@ impl<'a> Postfixed<'a> {
         T: de::DeserializeOwned,
         Iter: IntoIterator<Item = (String, String)>,
     {
-        from_iter(iter.into_iter().filter_map(|(k, v)| {
-            if k.ends_with(self.0) {
-                Some((k.trim_end_matches(self.0).to_owned(), v))
-            } else {
-                None
-            }
-        }))
+        from_iter(
+            iter.into_iter()
+                .filter_map(|(k, v)| Some((k.strip_suffix(&*self.0)?.to_owned(), v))),
+        )
+    }
+
+    /// Additionally filter on a prefix, stripping both ends of the keys.
+    /// See [`crate::Builder::prefix`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::postfixed;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     key: String,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_KEY_PROD".to_owned(), "production".to_owned()),
+    ///     ("OTHER_KEY_PROD".to_owned(), "unrelated".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = postfixed("_PROD")
+    ///     .prefixed("APP_")
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         key: String::from("production")
+    ///     }
+    /// )
+    /// ```
+    pub fn prefixed<'p>(&self, prefix: impl Into<Cow<'p, str>>) -> Builder {
+        builder()
+            .prefix(prefix.into())
+            .postfix(self.postfix())
+    }
+
+    /// Whether to strip the postfix from the keys, see [`crate::Builder::strip`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::postfixed;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     key_prod: String,
+    /// }
+    ///
+    /// let vars = vec![("KEY_PROD".to_owned(), "production".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     postfixed("_PROD").strip(false).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         key_prod: String::from("production")
+    ///     }
+    /// )
+    /// ```
+    pub fn strip(&self, strip: bool) -> Builder {
+        builder().postfix(self.postfix()).strip(strip)
     }
 
     /// Retrieve the postfix specified at the time
     /// of constructing an instance of [`Postfixed`]
     pub fn postfix(&self) -> &str {
-        self.0
+        &self.0
     }
 }
 
 /// Aids in deserializing some type `T` from environment variables,
 /// where the keys are postfixed. Users are meant to obtain a [`Postfixed`]
-/// struct by calling [`postfixed`].
+/// struct by calling [`postfixed`]. The postfix can be borrowed or owned,
+/// such as one computed at runtime.
 ///
 /// # Example
 ///
@@ -182,17 +246,22 @@ impl<'a> Postfixed<'a> {
 ///
 /// let with_postfix = postfixed("_APP");
 ///
-/// assert_eq!(with_postfix.postfix(), "_APP")
+/// assert_eq!(with_postfix.postfix(), "_APP");
+///
+/// let environment = "prod";
+/// let with_postfix = postfixed(format!("_{}", environment.to_uppercase()));
+///
+/// assert_eq!(with_postfix.postfix(), "_PROD")
 /// ```
-pub fn postfixed(postfix: &str) -> Postfixed<'_> {
-    Postfixed(postfix)
+pub fn postfixed<'a>(postfix: impl Into<Cow<'a, str>>) -> Postfixed<'a> {
+    Postfixed(postfix.into())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::with_vars;
     use serde::Deserialize;
-    use std::env;
 
     #[derive(Debug, Deserialize, PartialEq, Eq)]
     struct Test {
@@ -201,8 +270,9 @@ mod tests {
 
     #[test]
     fn test_postfixed() {
-        env::set_var("KEY_APP", "value");
-        let postfixed = postfixed("_APP").from_env::<Test>().unwrap();
+        let postfixed = with_vars([("KEY_APP", "value")], || {
+            postfixed("_APP").from_env::<Test>().unwrap()
+        });
 
         assert_eq!(
             postfixed,
@@ -211,4 +281,23 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_postfixed_strips_once() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Repeated {
+            key_app: String,
+        }
+
+        let vars = vec![(String::from("KEY_APP_APP"), String::from("value"))];
+
+        assert_eq!(
+            postfixed("_APP")
+                .from_iter::<Repeated, _>(vars)
+                .unwrap(),
+            Repeated {
+                key_app: String::from("value")
+            }
+        )
+    }
 }
@@ -1,7 +1,7 @@
 use crate::convert::maybe_invalid_unicode_vars_os;
-use crate::{from_iter, Result};
+use crate::{builder, Builder, Result};
 use serde::de;
-use std::{env, string::String};
+use std::{borrow::Cow, env, string::String};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////////
 
@@ -18,8 +18,8 @@ use std::{env, string::String};
 ///
 /// assert_eq!(with_prefix.prefix(), "APP_")
 /// ```
-#[derive(Debug)]
-pub struct Prefixed<'a>(&'a str);
+#[derive(Debug, Clone)]
+pub struct Prefixed<'a>(Vec<Cow<'a, str>>);
 
 impl<'a> Prefixed<'a> {
     /// Deserialize some type `T` from a snapshot of the currently
@@ -158,25 +158,169 @@ impl<'a> Prefixed<'a> {
         T: de::DeserializeOwned,
         Iter: IntoIterator<Item = (String, String)>,
     {
-        from_iter(iter.into_iter().filter_map(|(k, v)| {
-            if k.starts_with(self.0) {
-                Some((k.trim_start_matches(self.0).to_owned(), v))
-            } else {
-                None
-            }
-        }))
+        self.builder().from_iter(iter)
+    }
+
+    /// Also consider the variables starting with `prefix`, for the keys no
+    /// variable with an earlier prefix sets, so prefixes are tried in order.
+    /// See [`crate::Builder::fallback_prefix`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::prefixed;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_HOST".to_owned(), "localhost".to_owned()),
+    ///     ("APP_PORT".to_owned(), "8080".to_owned()),
+    ///     ("MYAPP_PORT".to_owned(), "9090".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     prefixed("MYAPP_").or("APP_").from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         host: String::from("localhost"),
+    ///         port: 9090,
+    ///     }
+    /// )
+    /// ```
+    pub fn or(mut self, prefix: impl Into<Cow<'a, str>>) -> Self {
+        self.0.push(prefix.into());
+        self
+    }
+
+    /// Fail with [`crate::Error::UnknownKeys`] if any of the prefixed
+    /// variables don't match a field of the target struct.
+    /// See [`crate::Builder::deny_unknown`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::{prefixed, Error};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct CustomStruct {
+    ///     timeout: Option<u64>,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_TIMEOTU".to_owned(), "30".to_owned()),
+    ///     ("UNRELATED".to_owned(), "value".to_owned()),
+    /// ];
+    ///
+    /// let error = prefixed("APP_")
+    ///     .deny_unknown(true)
+    ///     .from_iter::<CustomStruct, _>(vars)
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error, Error::UnknownKeys(vec!["APP_TIMEOTU".to_owned()]))
+    /// ```
+    pub fn deny_unknown(&self, deny: bool) -> Builder {
+        self.builder().deny_unknown(deny)
+    }
+
+    /// Whether to strip the prefix from the keys, see [`crate::Builder::strip`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::prefixed;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     app_key: String,
+    /// }
+    ///
+    /// let vars = vec![("APP_KEY".to_owned(), "value".to_owned())];
+    ///
+    /// let custom_struct: CustomStruct =
+    ///     prefixed("APP_").strip(false).from_iter(vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         app_key: String::from("value")
+    ///     }
+    /// )
+    /// ```
+    pub fn strip(&self, strip: bool) -> Builder {
+        self.builder().strip(strip)
+    }
+
+    /// Additionally filter on a postfix, stripping both ends of the keys.
+    /// See [`crate::Builder::postfix`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::prefixed;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     key: String,
+    /// }
+    ///
+    /// let vars = vec![
+    ///     ("APP_KEY_PROD".to_owned(), "production".to_owned()),
+    ///     ("APP_KEY_DEV".to_owned(), "development".to_owned()),
+    /// ];
+    ///
+    /// let custom_struct: CustomStruct = prefixed("APP_")
+    ///     .postfixed("_PROD")
+    ///     .from_iter(vars)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     custom_struct,
+    ///     CustomStruct {
+    ///         key: String::from("production")
+    ///     }
+    /// )
+    /// ```
+    pub fn postfixed<'p>(&self, postfix: impl Into<Cow<'p, str>>) -> Builder {
+        self.builder().postfix(postfix.into())
     }
 
     /// Retrieve the prefix specified at the time
     /// of constructing an instance of [`Prefixed`]
     pub fn prefix(&self) -> &str {
-        self.0
+        &self.0[0]
+    }
+
+    /// Retrieve every prefix, in the order they're tried
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|prefix| prefix.as_ref())
+    }
+
+    /// A [`Builder`] set up with the prefixes
+    fn builder(&self) -> Builder {
+        let (prefix, fallbacks) = self.0.split_first().expect("at least one prefix");
+
+        fallbacks
+            .iter()
+            .fold(builder().prefix(prefix.as_ref()), |builder, prefix| {
+                builder.fallback_prefix(prefix.as_ref())
+            })
     }
 }
 
 /// Aids in deserializing some type `T` from environment variables,
 /// where the keys are prefixed. Users are meant to obtain a [`Prefixed`]
-/// struct by calling [`prefixed`].
+/// struct by calling [`prefixed`]. The prefix can be borrowed or owned,
+/// such as one computed at runtime.
 ///
 /// # Example
 ///
@@ -185,18 +329,23 @@ impl<'a> Prefixed<'a> {
 ///
 /// let with_prefix = prefixed("APP_");
 ///
-/// assert_eq!(with_prefix.prefix(), "APP_")
+/// assert_eq!(with_prefix.prefix(), "APP_");
+///
+/// let tenant = "acme";
+/// let with_prefix = prefixed(format!("{}_", tenant.to_uppercase()));
+///
+/// assert_eq!(with_prefix.prefix(), "ACME_")
 /// ```
-pub fn prefixed(prefix: &str) -> Prefixed<'_> {
-    Prefixed(prefix)
+pub fn prefixed<'a>(prefix: impl Into<Cow<'a, str>>) -> Prefixed<'a> {
+    Prefixed(vec![prefix.into()])
 }
 
 #[cfg(test)]
 mod test_prefixed {
     use serde::Deserialize;
-    use std::env;
 
     use super::prefixed;
+    use crate::testing::with_vars;
 
     #[derive(Debug, Deserialize, PartialEq, Eq)]
     struct Test {
@@ -205,8 +354,9 @@ mod test_prefixed {
 
     #[test]
     fn test_prefixed() {
-        env::set_var("APP_KEY", "value");
-        let prefixed = prefixed("APP_").from_env::<Test>().unwrap();
+        let prefixed = with_vars([("APP_KEY", "value")], || {
+            prefixed("APP_").from_env::<Test>().unwrap()
+        });
 
         assert_eq!(
             prefixed,
@@ -215,4 +365,103 @@ mod test_prefixed {
             }
         )
     }
+
+    #[test]
+    fn test_prefixed_strips_once() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Repeated {
+            app_name: String,
+        }
+
+        let vars = vec![(String::from("APP_APP_NAME"), String::from("value"))];
+
+        assert_eq!(
+            prefixed("APP_")
+                .from_iter::<Repeated, _>(vars)
+                .unwrap(),
+            Repeated {
+                app_name: String::from("value")
+            }
+        )
+    }
+
+    #[test]
+    fn test_prefixed_fallbacks() {
+        use crate::Error;
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+
+        let prefix = String::from("MYAPP_");
+        let vars = vec![
+            (String::from("APP_HOST"), String::from("localhost")),
+            (String::from("MYAPP_PORT"), String::from("9090")),
+            (String::from("APP_PORT"), String::from("8080")),
+            (String::from("LEGACY_HOST"), String::from("legacy")),
+        ];
+
+        assert_eq!(
+            prefixed(prefix.clone())
+                .or("APP_")
+                .or("LEGACY_")
+                .from_iter::<Server, _>(vars)
+                .unwrap(),
+            Server {
+                host: String::from("localhost"),
+                port: 9090
+            }
+        );
+
+        // Errors name the variables with the prefix they were read with
+        let vars = vec![
+            (String::from("APP_PORT"), String::from("8080")),
+            (String::from("APP_HOTS"), String::from("localhost")),
+        ];
+
+        assert_eq!(
+            prefixed(prefix)
+                .or("APP_")
+                .deny_unknown(true)
+                .from_iter::<Server, _>(vars)
+                .unwrap_err(),
+            Error::UnknownKeys(vec![String::from("APP_HOTS")])
+        );
+    }
+
+    #[test]
+    fn test_prefixed_map() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let flags = with_vars(
+            [("FEATURE_FLAG_NEW_UI", "on"), ("FEATURE_FLAG_BETA", "true")],
+            || {
+                prefixed("FEATURE_FLAG_")
+                    .from_env::<HashMap<String, String>>()
+                    .unwrap()
+            },
+        );
+
+        assert_eq!(
+            flags,
+            HashMap::from([
+                (String::from("new_ui"), String::from("on")),
+                (String::from("beta"), String::from("true")),
+            ])
+        );
+
+        let vars = vec![
+            (String::from("FEATURE_FLAG_BETA"), String::from("true")),
+            (String::from("OTHER"), String::from("false")),
+        ];
+
+        assert_eq!(
+            prefixed("FEATURE_FLAG_")
+                .from_iter::<BTreeMap<String, bool>, _>(vars)
+                .unwrap(),
+            BTreeMap::from([(String::from("beta"), true)])
+        )
+    }
 }
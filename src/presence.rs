@@ -0,0 +1,194 @@
+//! Telling `Option<Option<T>>` and options of sequences, such as `Option<Vec<T>>`,
+//! apart from `Option<T>`, so that a variable that's set to an empty string
+//! can be distinguished from one that's unset
+//!
+//! Where the type being deserialized is known, such as for the values of the
+//! fields of a struct, it's probed with [`presence`], which follows it through
+//! its options.
+
+use crate::{Error, Result};
+use serde::de::{self, Deserialize};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// What a type makes of a variable that's set to an empty string, see [`presence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Presence {
+    /// An `Option<Option<T>>`, which is `Some(None)`, since the variable is set
+    NestedOption,
+
+    /// An option of a sequence, such as `Option<Vec<T>>`, which is `Some` of an
+    /// empty sequence, unless [`crate::Builder::empty_seqs_as_none`] is enabled
+    OptionOfSeq,
+
+    /// Anything else, which is `None` if it's an option
+    Other,
+}
+
+/// Learn what `T` makes of a variable that's set to an empty string
+pub(crate) fn presence<'de, T>() -> Presence
+where
+    T: Deserialize<'de>,
+{
+    let mut probe = Probe {
+        in_option: false,
+        presence: Presence::Other,
+    };
+    let _ = T::deserialize(&mut probe);
+
+    probe.presence
+}
+
+/// Follows a type through its options, until it finds out its [`Presence`]
+struct Probe {
+    in_option: bool,
+    presence: Presence,
+}
+
+impl<'de> de::Deserializer<'de> for &mut Probe {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::Custom(String::from("probe finished")))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.in_option {
+            true => {
+                self.presence = Presence::NestedOption;
+                Err(Error::Custom(String::from("probe finished")))
+            }
+            false => {
+                self.in_option = true;
+                visitor.visit_some(self)
+            }
+        }
+    }
+
+    fn deserialize_seq<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.in_option {
+            self.presence = Presence::OptionOfSeq;
+        }
+
+        Err(Error::Custom(String::from("probe finished")))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit
+        bytes byte_buf map unit_struct newtype_struct tuple_struct struct
+        identifier tuple ignored_any enum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::from_iter;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Overrides {
+        name: Option<Option<String>>,
+        port: Option<Option<u16>>,
+        host: Option<Option<String>>,
+        user: Option<String>,
+    }
+
+    #[test]
+    fn test_nested_option() {
+        let vars = vec![
+            (String::from("NAME"), String::from("")),
+            (String::from("PORT"), String::from("8080")),
+            (String::from("USER"), String::from("")),
+        ];
+
+        assert_eq!(
+            from_iter::<Overrides, _>(vars).unwrap(),
+            Overrides {
+                name: Some(None),
+                port: Some(Some(8080)),
+                host: None,
+                user: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_option_in_map() {
+        use std::collections::HashMap;
+
+        let vars = vec![
+            (String::from("NAME"), String::from("")),
+            (String::from("PORT"), String::from("8080")),
+        ];
+
+        assert_eq!(
+            from_iter::<HashMap<String, Option<Option<String>>>, _>(vars).unwrap(),
+            HashMap::from([
+                (String::from("name"), Some(None)),
+                (String::from("port"), Some(Some(String::from("8080")))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_option_of_seq() {
+        use std::collections::HashSet;
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        #[serde(transparent)]
+        struct Queue(Vec<String>);
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Lists {
+            hosts: Option<Vec<String>>,
+            tags: Option<HashSet<String>>,
+            queue: Option<Queue>,
+            ports: Option<Vec<u16>>,
+            weights: Vec<Option<u8>>,
+            name: Option<Option<String>>,
+        }
+
+        let vars = vec![
+            (String::from("HOSTS"), String::from("")),
+            (String::from("TAGS"), String::from("")),
+            (String::from("QUEUE"), String::from("")),
+            (String::from("WEIGHTS"), String::from("1,,3")),
+            (String::from("NAME"), String::from("")),
+        ];
+
+        assert_eq!(
+            from_iter::<Lists, _>(vars.clone()).unwrap(),
+            Lists {
+                hosts: Some(vec![]),
+                tags: Some(HashSet::new()),
+                queue: Some(Queue(vec![])),
+                ports: None,
+                weights: vec![Some(1), None, Some(3)],
+                name: Some(None),
+            }
+        );
+        assert_eq!(
+            crate::builder()
+                .empty_seqs_as_none(true)
+                .from_iter::<Lists, _>(vars)
+                .unwrap(),
+            Lists {
+                hosts: None,
+                tags: None,
+                queue: None,
+                ports: None,
+                weights: vec![Some(1), None, Some(3)],
+                name: Some(None),
+            }
+        );
+    }
+}
@@ -6,9 +6,16 @@ macro_rules! forward_parsed_values {
             fn $method<V>(self, visitor: V) -> Result<V::Value>
                 where V: de::Visitor<'de>
             {
-                match self.0.parse::<$typ>() {
+                self.check_unicode()?;
+
+                let parsed = match self.options.lenient_numbers {
+                    true => <$typ as $crate::parse::LenientNumber>::parse_lenient(&self.value),
+                    false => self.value.parse::<$typ>().map_err(|e| e.to_string()),
+                };
+
+                match parsed {
                     Ok(val) => val.into_deserializer().$method(visitor),
-                    Err(e) => Err(de::Error::custom(format_args!("{} while parsing value '{}'", e, self.0)))
+                    Err(e) => Err(de::Error::custom(format_args!("{} while parsing value '{}'", e, self.value)))
                 }
             }
         )*
@@ -0,0 +1,289 @@
+//! Overriding variables per environment, such as `PORT_PROD` over `PORT`
+
+use crate::convert::maybe_invalid_unicode_vars_os;
+use crate::{builder, Builder, Result};
+use serde::de;
+use std::{collections::HashMap, env};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Where the name of a profile goes in the keys of the variables it overrides.
+/// Set with [`Profiles::position`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProfilePosition {
+    /// After the key, as in `PORT_PROD`
+    #[default]
+    Postfix,
+
+    /// Before the key, as in `PROD_PORT`
+    Prefix,
+}
+
+/// Prefers the variables of the active profiles, such as `PORT_PROD` or `PORT_STAGING`,
+/// over the plain ones, such as `PORT`, when both are set. Users are meant to obtain
+/// this struct by calling [`profiles`], with the profiles in order of precedence.
+///
+/// The names of the profiles are matched ignoring case, and the variables of
+/// profiles that aren't active are left alone, like any other variable.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{profiles, ProfilePosition};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     host: String,
+///     port: u16,
+///     workers: u8,
+/// }
+///
+/// let vars = vec![
+///     ("HOST".to_owned(), "localhost".to_owned()),
+///     ("PORT".to_owned(), "8080".to_owned()),
+///     ("PROD_PORT".to_owned(), "80".to_owned()),
+///     ("WORKERS".to_owned(), "1".to_owned()),
+///     ("STAGING_WORKERS".to_owned(), "4".to_owned()),
+///     ("PROD_WORKERS".to_owned(), "16".to_owned()),
+/// ];
+///
+/// let custom_struct: CustomStruct = profiles(["prod", "staging"])
+///     .position(ProfilePosition::Prefix)
+///     .from_iter(vars)
+///     .unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         host: "localhost".to_owned(),
+///         port: 80,
+///         workers: 16,
+///     }
+/// )
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Profiles {
+    names: Vec<String>,
+    position: ProfilePosition,
+}
+
+impl Profiles {
+    /// Set where the name of the profiles goes in the keys of the variables,
+    /// see [`ProfilePosition`]
+    ///
+    /// Defaults to [`ProfilePosition::Postfix`].
+    pub fn position(mut self, position: ProfilePosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Retrieve the profiles, in order of precedence
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Create a [`Builder`] that resolves the variables of these profiles,
+    /// see [`Builder::profiles`], to combine them with other settings
+    pub fn builder(&self) -> Builder {
+        builder().profiles(self.clone())
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    ///
+    /// # Panics
+    /// if any of the environment variables contain invalid unicode
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use renvar::profiles;
+    /// use serde::Deserialize;
+    /// use std::env;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq, Eq)]
+    /// struct CustomStruct {
+    ///     profiles_timeout: u64,
+    /// }
+    ///
+    /// env::set_var("PROFILES_TIMEOUT", "30");
+    /// env::set_var("PROFILES_TIMEOUT_PROD", "5");
+    ///
+    /// let custom_struct: CustomStruct = profiles(["prod"]).from_env().unwrap();
+    ///
+    /// assert_eq!(custom_struct, CustomStruct { profiles_timeout: 5 })
+    /// ```
+    pub fn from_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(env::vars())
+    }
+
+    /// Deserialize some type `T` from a snapshot of the currently
+    /// running process's environment variables at invocation time, but doesn't panic
+    /// if any of the environment variables contain invalid unicode, instead returns
+    /// an error.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_os_env<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        self.from_iter(maybe_invalid_unicode_vars_os()?)
+    }
+
+    /// Deserialize some type `T` from an iterator `Iter` that is an iterator
+    /// over key-value pairs, preferring the variables of the profiles.
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn from_iter<T, Iter>(&self, iter: Iter) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+        Iter: IntoIterator<Item = (String, String)>,
+    {
+        self.builder().from_iter(iter)
+    }
+
+    /// Keep the variable of the profile with the highest precedence for each key,
+    /// falling back to the plain variable, and strip the profile from its key.
+    /// Variables are kept in the order they were given.
+    pub(crate) fn resolve(&self, vars: Vec<(String, String)>) -> Vec<(String, String)> {
+        let resolved: Vec<(String, usize, String)> = vars
+            .into_iter()
+            .map(|(key, value)| {
+                let (key, rank) = self.split(key);
+                (key, rank, value)
+            })
+            .collect();
+
+        let mut best: HashMap<&str, usize> = HashMap::new();
+
+        for (key, rank, _) in &resolved {
+            let best = best.entry(key).or_insert(*rank);
+            *best = (*best).min(*rank);
+        }
+
+        let best: HashMap<String, usize> = best
+            .into_iter()
+            .map(|(key, rank)| (key.to_owned(), rank))
+            .collect();
+
+        resolved
+            .into_iter()
+            .filter(|(key, rank, _)| best.get(key) == Some(rank))
+            .map(|(key, _, value)| (key, value))
+            .collect()
+    }
+
+    /// Strip the profile from `key`, returning the precedence of the profile,
+    /// which is the number of profiles for keys without any
+    fn split(&self, key: String) -> (String, usize) {
+        for (rank, name) in self.names.iter().enumerate() {
+            let affix = name.len() + 1;
+
+            if key.len() <= affix
+                || !key.is_char_boundary(affix)
+                || !key.is_char_boundary(key.len() - affix)
+            {
+                continue;
+            }
+
+            let stripped = match self.position {
+                ProfilePosition::Postfix => {
+                    let (rest, postfix) = key.split_at(key.len() - affix);
+
+                    (postfix.starts_with('_')
+                        && postfix[1..].eq_ignore_ascii_case(name))
+                    .then_some(rest)
+                }
+                ProfilePosition::Prefix => {
+                    let (prefix, rest) = key.split_at(affix);
+
+                    (prefix.ends_with('_')
+                        && prefix[..name.len()].eq_ignore_ascii_case(name))
+                    .then_some(rest)
+                }
+            };
+
+            if let Some(stripped) = stripped {
+                return (stripped.to_owned(), rank);
+            }
+        }
+
+        (key, self.names.len())
+    }
+}
+
+/// Aids in deserializing some type `T` from environment variables,
+/// where some of them are overridden per environment, such as `PORT_PROD`.
+/// Users are meant to obtain a [`Profiles`] struct by calling [`profiles`],
+/// giving the active profiles in order of precedence.
+///
+/// # Example
+///
+/// ```
+/// use renvar::profiles;
+///
+/// let profiles = profiles(["prod", "staging"]);
+///
+/// assert_eq!(profiles.names(), &["prod".to_owned(), "staging".to_owned()])
+/// ```
+pub fn profiles<I, S>(names: I) -> Profiles
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    Profiles {
+        names: names.into_iter().map(Into::into).collect(),
+        position: ProfilePosition::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{profiles, ProfilePosition};
+
+    #[test]
+    fn test_resolve() {
+        let vars = vec![
+            (String::from("PORT_PROD"), String::from("80")),
+            (String::from("PORT"), String::from("8080")),
+            (String::from("HOST_dev"), String::from("localhost")),
+            (
+                String::from("HOST_staging"),
+                String::from("staging.internal"),
+            ),
+            (String::from("_PROD"), String::from("empty key")),
+            (String::from("NAME"), String::from("api")),
+            (String::from("CAFÉ_PROD"), String::from("non-ascii key")),
+        ];
+
+        assert_eq!(
+            profiles(["prod", "staging"]).resolve(vars.clone()),
+            vec![
+                (String::from("PORT"), String::from("80")),
+                (String::from("HOST_dev"), String::from("localhost")),
+                (String::from("HOST"), String::from("staging.internal")),
+                (String::from("_PROD"), String::from("empty key")),
+                (String::from("NAME"), String::from("api")),
+                (String::from("CAFÉ"), String::from("non-ascii key")),
+            ]
+        );
+        assert_eq!(
+            profiles(["prod"])
+                .position(ProfilePosition::Prefix)
+                .resolve(vars.clone()),
+            vars
+        );
+    }
+}
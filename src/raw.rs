@@ -0,0 +1,112 @@
+//! Environment variables as they were read, before any deserialization
+
+use crate::convert::parse_str;
+use crate::{file, Result};
+use std::{env, path::Path, vec};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A list of `(key, value)` pairs as they were read from the environment or
+/// an env file, in their original order, for inspecting them before deserializing,
+/// such as with [`crate::lint`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::RawEnv;
+///
+/// let raw = RawEnv::parse("NAME=renvar\n# a comment\nPORT='8080'\n");
+///
+/// assert_eq!(raw.keys().collect::<Vec<_>>(), vec!["NAME", "PORT"]);
+/// assert_eq!(raw.get("PORT"), Some("8080"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawEnv {
+    vars: Vec<(String, String)>,
+}
+
+impl RawEnv {
+    /// Read the environment of the running process.
+    /// Variables that aren't valid unicode are skipped
+    pub fn from_env() -> Self {
+        env::vars_os()
+            .filter_map(|(key, value)| {
+                Some((key.into_string().ok()?, value.into_string().ok()?))
+            })
+            .collect()
+    }
+
+    /// Read a blob of `KEY=value` lines, which follows the
+    /// same format as described in [`crate::from_str`].
+    /// Lines that can't be parsed are skipped
+    pub fn parse(input: &str) -> Self {
+        parse_str(input)
+            .filter_map(|line| line.ok())
+            .map(|(key, value)| (key.to_owned(), value.into_owned()))
+            .collect()
+    }
+
+    /// Read the env file at `path`, which follows the
+    /// same format as described in [`crate::from_str`]
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or any of its lines can't be parsed,
+    /// see [`crate::Error::Parse`]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            vars: parse_str(&file::read(path.as_ref())?)
+                .map(|line| {
+                    line.map(|(key, value)| (key.to_owned(), value.into_owned()))
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// The value of the first variable named `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The names of the variables
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.vars.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// The `(key, value)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The number of variables
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Whether there are no variables
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+}
+
+impl FromIterator<(String, String)> for RawEnv {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            vars: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for RawEnv {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vars.into_iter()
+    }
+}
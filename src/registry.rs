@@ -0,0 +1,100 @@
+//! Variables stored as the values of a key in the Windows registry
+
+use crate::{repr, Error, Result, Source};
+use std::io;
+use winreg::{enums::*, types::FromRegValue, RegKey, RegValue, HKEY};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The values directly under a key of the Windows registry, such as
+/// `SOFTWARE\MyCompany\MyService`, as a [`Source`]. The names of the values
+/// are the keys of the variables, so one config struct can serve both services
+/// configured with environment variables and ones configured with the registry.
+///
+/// Strings are taken as they are, with environment variables in `REG_EXPAND_SZ`
+/// values left unexpanded. Numbers are written in decimal and the strings of
+/// `REG_MULTI_SZ` values are joined with commas, to be read into sequences.
+/// Any other type of value fails to load.
+///
+/// # Example
+///
+/// ```no_run
+/// use renvar::{from_source, RegistrySource};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Service {
+///     port: u16,
+///     hosts: Vec<String>,
+/// }
+///
+/// let source = RegistrySource::local_machine(r"SOFTWARE\MyCompany\MyService");
+///
+/// let service: Service = from_source(&source).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegistrySource {
+    hive: HKEY,
+    path: String,
+}
+
+impl RegistrySource {
+    /// Read the values of the key at `path` under `HKEY_LOCAL_MACHINE`,
+    /// where services usually keep their configuration
+    pub fn local_machine(path: &str) -> Self {
+        Self {
+            hive: HKEY_LOCAL_MACHINE,
+            path: path.to_owned(),
+        }
+    }
+
+    /// Read the values of the key at `path` under `HKEY_CURRENT_USER`
+    pub fn current_user(path: &str) -> Self {
+        Self {
+            hive: HKEY_CURRENT_USER,
+            path: path.to_owned(),
+        }
+    }
+}
+
+impl Source for RegistrySource {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        let io_error = |e: io::Error| {
+            Error::Custom(format!("failed to read registry key {}: {}", self.path, e))
+        };
+
+        let key = RegKey::predef(self.hive)
+            .open_subkey(&self.path)
+            .map_err(io_error)?;
+
+        key.enum_values()
+            .map(|value| {
+                let (name, value) = value.map_err(io_error)?;
+                let value = to_string(&value)
+                    .map_err(io_error)?
+                    .ok_or_else(|| {
+                        Error::Custom(format!(
+                            "registry value {} of key {} has the unsupported type {:?}",
+                            name, self.path, value.vtype
+                        ))
+                    })?;
+
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
+
+/// Write a registry value the way it's expected in an environment variable,
+/// or `None` if its type can't be
+fn to_string(value: &RegValue) -> io::Result<Option<String>> {
+    Ok(Some(match value.vtype {
+        REG_SZ | REG_EXPAND_SZ => String::from_reg_value(value)?,
+        REG_MULTI_SZ => {
+            Vec::<String>::from_reg_value(value)?.join(&repr::SEQ_DELIMITER.to_string())
+        }
+        REG_DWORD => u32::from_reg_value(value)?.to_string(),
+        REG_QWORD => u64::from_reg_value(value)?.to_string(),
+        _ => return Ok(None),
+    }))
+}
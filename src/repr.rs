@@ -0,0 +1,287 @@
+//! Textual representation of the values that don't have an obvious one,
+//! shared by the serializer and the deserializer so the two can't drift apart
+
+use crate::{Error, Result};
+use serde::de;
+use std::borrow::Cow;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Separates the elements of sequences
+pub(crate) const SEQ_DELIMITER: char = ',';
+
+/// Separates the variant of an enum from its data, as in `redis:host=localhost`
+pub(crate) const VARIANT_DELIMITER: char = ':';
+
+/// Separates the fields of a struct variant from their values
+pub(crate) const FIELD_DELIMITER: char = '=';
+
+/// Write an element of a sequence, escaping the delimiters it contains
+pub(crate) fn seq_element(element: String) -> String {
+    match element.contains(SEQ_DELIMITER) {
+        true => element.replace(SEQ_DELIMITER, &format!("\\{}", SEQ_DELIMITER)),
+        false => element,
+    }
+}
+
+/// Split `value` into at most `limit` elements at each `delimiter`, the last
+/// element holding the rest of `value`. Delimiters are kept in elements when
+/// they're escaped with a backslash, such as `a\,b`, or when they're between
+/// the quotes an element starts with, such as `"a,b"`.
+///
+/// The backslashes in front of delimiters, quotes and other backslashes are removed,
+/// other backslashes are kept. Quotes are left for the elements to be trimmed of.
+/// Elements without any escapes are borrowed from `value`.
+pub(crate) fn split_elements(
+    value: &str,
+    delimiter: char,
+    limit: usize,
+) -> Vec<Cow<'_, str>> {
+    let mut elements = Vec::new();
+
+    if limit == 0 {
+        return elements;
+    }
+
+    let mut start = 0;
+    let mut unescaped: Option<String> = None;
+    let mut quote = None;
+    let mut at_start = true;
+    let mut chars = value.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if elements.len() + 1 == limit {
+            break;
+        }
+
+        match c {
+            '\\' if chars.peek().is_some_and(|&(_, next)| {
+                matches!(next, '\\' | '"' | '\'') || next == delimiter
+            }) =>
+            {
+                let (_, next) = chars.next().unwrap_or((i, c));
+                unescaped
+                    .get_or_insert_with(|| value[start..i].to_owned())
+                    .push(next);
+                at_start = false;
+                continue;
+            }
+            c if quote == Some(c) => quote = None,
+            '"' | '\'' if quote.is_none() && at_start => quote = Some(c),
+            c if c == delimiter && quote.is_none() => {
+                elements.push(match unescaped.take() {
+                    Some(element) => Cow::Owned(element),
+                    None => Cow::Borrowed(&value[start..i]),
+                });
+                start = i + c.len_utf8();
+                at_start = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        at_start &= c.is_whitespace();
+
+        if let Some(element) = unescaped.as_mut() {
+            element.push(c);
+        }
+    }
+
+    elements.push(match unescaped {
+        Some(element) => Cow::Owned(element),
+        None => Cow::Borrowed(&value[start..]),
+    });
+
+    elements
+}
+
+/// Write the unit struct called `name`
+pub(crate) fn unit_struct(name: &'static str) -> String {
+    name.to_owned()
+}
+
+/// Check that `value` is the representation of the unit struct called `name`
+pub(crate) fn parse_unit_struct(name: &'static str, value: &str) -> Result<()> {
+    if value == unit_struct(name) {
+        Ok(())
+    } else {
+        Err(Error::Custom(format!(
+            "expected unit struct with name '{}', found '{}'",
+            name, value
+        )))
+    }
+}
+
+/// Write the unit variant called `variant`
+pub(crate) fn unit_variant(variant: &'static str) -> String {
+    variant.to_owned()
+}
+
+/// Find the variant among `variants` that `value` is the representation of.
+/// When `lenient`, case, dashes and underscores are ignored.
+pub(crate) fn parse_unit_variant(
+    value: &str,
+    variants: &'static [&'static str],
+    lenient: bool,
+) -> Result<&'static str> {
+    if !lenient {
+        return variants
+            .iter()
+            .find(|&&variant| value == unit_variant(variant))
+            .copied()
+            .ok_or_else(|| de::Error::unknown_variant(value, variants));
+    }
+
+    let normalized = normalize_variant(value);
+
+    variants
+        .iter()
+        .find(|&&variant| normalized == normalize_variant(&unit_variant(variant)))
+        .copied()
+        .ok_or_else(|| {
+            let expected = variants
+                .iter()
+                .map(|variant| format!("`{}`", variant))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            Error::Custom(format!(
+                "unknown variant `{}`, expected one of {}, ignoring case, dashes and underscores",
+                value, expected
+            ))
+        })
+}
+
+/// Lowercase `variant` and drop its dashes and underscores
+fn normalize_variant(variant: &str) -> String {
+    variant
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{builder, from_iter, to_vec};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    #[serde(rename = "v2")]
+    struct Version;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    struct Logging {
+        level: Level,
+        levels: Vec<Level>,
+        version: Version,
+    }
+
+    #[test]
+    fn test_symmetric_representation() {
+        let logging = Logging {
+            level: Level::Info,
+            levels: vec![Level::Debug, Level::Info],
+            version: Version,
+        };
+
+        let vars = to_vec(&logging).unwrap();
+
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("LEVEL"), String::from("info")),
+                (String::from("LEVELS"), String::from("debug,info")),
+                (String::from("VERSION"), String::from("v2")),
+            ]
+        );
+        assert_eq!(from_iter::<Logging, _>(vars).unwrap(), logging);
+
+        let error = from_iter::<Logging, _>(vec![
+            (String::from("LEVEL"), String::from("trace")),
+            (String::from("LEVELS"), String::from("")),
+            (String::from("VERSION"), String::from("v2")),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "unknown variant `trace`, expected `debug` or `info`"
+        );
+    }
+
+    #[test]
+    fn test_lenient_variants() {
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        #[serde(rename_all = "snake_case")]
+        enum Mode {
+            ReadOnly,
+            ReadWrite,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Eq)]
+        struct Storage {
+            mode: Mode,
+        }
+
+        let builder = builder().lenient_variants(true);
+
+        for value in ["read_only", "READ-ONLY", "ReadOnly", "readonly"] {
+            let vars = vec![(String::from("MODE"), value.to_owned())];
+
+            assert_eq!(
+                builder.from_iter::<Storage, _>(vars),
+                Ok(Storage {
+                    mode: Mode::ReadOnly
+                }),
+                "{}",
+                value
+            );
+        }
+
+        assert_eq!(
+            super::parse_unit_variant("append", &["read_only", "read_write"], true)
+                .unwrap_err()
+                .to_string(),
+            "unknown variant `append`, expected one of `read_only`, `read_write`, \
+             ignoring case, dashes and underscores"
+        );
+    }
+
+    #[test]
+    fn test_split_elements() {
+        assert_eq!(
+            super::split_elements(r#"a\,b, "c,d" ,'e',f\g"#, ',', usize::MAX),
+            vec!["a,b", r#" "c,d" "#, "'e'", r"f\g"]
+        );
+        assert_eq!(
+            super::split_elements(r"a:b\:c:d", ':', 2),
+            vec!["a", r"b\:c:d"]
+        );
+        assert!(super::split_elements("a,b", ',', 0).is_empty());
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+        struct People {
+            names: Vec<String>,
+        }
+
+        let people = People {
+            names: vec![String::from("Doe, John"), String::from("Jane")],
+        };
+
+        let vars = to_vec(&people).unwrap();
+
+        assert_eq!(
+            vars,
+            vec![(String::from("NAMES"), String::from(r"Doe\, John,Jane"))]
+        );
+        assert_eq!(from_iter::<People, _>(vars).unwrap(), people);
+    }
+}
@@ -0,0 +1,159 @@
+//! Replacing values that refer to content held elsewhere, such as
+//! `file:/run/secrets/db_pass`, see [`crate::Builder::resolver`]
+
+use crate::options::Hook;
+use crate::{file, Result};
+use std::path::Path;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Resolves the values written as `scheme:reference`, for a single scheme,
+/// into the content they refer to. Registered with [`crate::Builder::resolver`],
+/// which makes fetching secrets from files, password managers or secret stores
+/// pluggable, without the crate knowing about any of them.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, Error, Result, ValueResolver};
+/// use serde::Deserialize;
+///
+/// /// Resolves `upper:value` into `VALUE`
+/// struct Upper;
+///
+/// impl ValueResolver for Upper {
+///     fn scheme(&self) -> &str {
+///         "upper"
+///     }
+///
+///     fn resolve(&self, key: &str, reference: &str) -> Result<String> {
+///         match reference.is_empty() {
+///             true => Err(Error::Custom(format!("nothing to resolve for {}", key))),
+///             false => Ok(reference.to_uppercase()),
+///         }
+///     }
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     name: String,
+///     url: String,
+/// }
+///
+/// let vars = vec![
+///     ("NAME".to_owned(), "upper:renvar".to_owned()),
+///     ("URL".to_owned(), "https://example.com".to_owned()),
+/// ];
+///
+/// let custom_struct: CustomStruct = builder().resolver(Upper).from_iter(vars).unwrap();
+///
+/// assert_eq!(
+///     custom_struct,
+///     CustomStruct {
+///         name: "RENVAR".to_owned(),
+///         url: "https://example.com".to_owned()
+///     }
+/// );
+/// ```
+pub trait ValueResolver: Send + Sync {
+    /// The scheme handled by this resolver, such as `file`,
+    /// without the colon that separates it from the reference
+    fn scheme(&self) -> &str;
+
+    /// Resolve the `reference` that follows the scheme in the value of `key`
+    ///
+    /// # Errors
+    ///
+    /// If the content the reference points to can't be retrieved
+    fn resolve(&self, key: &str, reference: &str) -> Result<String>;
+}
+
+/// Resolves `file:/path/to/secret` into the contents of that file, such as the
+/// secrets Docker and Kubernetes mount into containers. A single trailing newline
+/// is removed, as most tools that write such files add one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileResolver;
+
+impl ValueResolver for FileResolver {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn resolve(&self, _: &str, reference: &str) -> Result<String> {
+        file::read_value(Path::new(reference))
+    }
+}
+
+/// A registered [`ValueResolver`], cheap to clone along with the builder
+pub(crate) type ResolverHook = Hook<dyn ValueResolver>;
+
+/// Resolve `value` with the resolver registered for its scheme,
+/// or leave it as it is if there's none
+pub(crate) fn resolve(
+    resolvers: &[ResolverHook],
+    key: &str,
+    value: String,
+) -> Result<String> {
+    let resolver = value
+        .split_once(':')
+        .and_then(|(scheme, reference)| {
+            resolvers
+                .iter()
+                .find(|resolver| resolver.0.scheme() == scheme)
+                .map(|resolver| (resolver, reference))
+        });
+
+    match resolver {
+        Some((resolver, reference)) => resolver.0.resolve(key, reference),
+        None => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileResolver;
+    use crate::{builder, Error};
+    use serde::Deserialize;
+    use std::{env, fs};
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Database {
+        user: String,
+        password: String,
+    }
+
+    #[test]
+    fn test_file_resolver() {
+        let path = env::temp_dir().join("renvar_test_file_resolver");
+        fs::write(&path, "hunter2\n").unwrap();
+
+        let vars = vec![
+            (String::from("DB_USER"), String::from("admin")),
+            (
+                String::from("DB_PASSWORD"),
+                format!("file:{}", path.display()),
+            ),
+        ];
+
+        let database = builder()
+            .prefix("DB_")
+            .resolver(FileResolver)
+            .from_iter::<Database, _>(vars.clone());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            database.unwrap(),
+            Database {
+                user: String::from("admin"),
+                password: String::from("hunter2"),
+            }
+        );
+        assert!(matches!(
+            builder()
+                .prefix("DB_")
+                .resolver(FileResolver)
+                .from_iter::<Database, _>(vars),
+            Err(Error::Io { .. })
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Constraints between fields, such as a field that requires another one,
+//! checked against the variables that were set
+
+use crate::ValidationError;
+use std::collections::HashSet;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A constraint between fields, set up with [`crate::Builder::requires`]
+/// or [`crate::Builder::conflicts`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rule {
+    /// Setting `field` requires every field of `required` to be set as well
+    Requires {
+        field: String,
+        required: Vec<String>,
+    },
+
+    /// At most one of the fields may be set
+    Conflicts(Vec<String>),
+}
+
+impl Rule {
+    /// Report every way the rule is broken, given the keys of the variables
+    /// that are set. Nested fields are written with their path, such as `tls.cert`,
+    /// which matches the key `tls_cert`
+    fn check(&self, set: &HashSet<String>, errors: &mut Vec<ValidationError>) {
+        let is_set = |field: &str| set.contains(&field.replace('.', "_"));
+
+        match self {
+            Rule::Requires { field, required } if is_set(field) => {
+                for required in required
+                    .iter()
+                    .filter(|required| !is_set(required))
+                {
+                    errors.push(ValidationError::new(
+                        field,
+                        format!("requires `{}` to be set as well", required),
+                    ));
+                }
+            }
+            Rule::Requires { .. } => {}
+            Rule::Conflicts(fields) => {
+                let set: Vec<&String> = fields
+                    .iter()
+                    .filter(|field| is_set(field))
+                    .collect();
+
+                if let [first, others @ ..] = set.as_slice() {
+                    for other in others {
+                        errors.push(ValidationError::new(
+                            first,
+                            format!(
+                                "conflicts with `{}`, only one of them may be set",
+                                other
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check every rule against the keys of the variables that are set,
+/// collecting all of the broken constraints
+pub(crate) fn check(rules: &[Rule], set: &HashSet<String>) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for rule in rules {
+        rule.check(set, &mut errors);
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Rule};
+    use crate::ValidationError;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_rules() {
+        let rules = vec![
+            Rule::Requires {
+                field: String::from("tls.cert"),
+                required: vec![String::from("tls.key"), String::from("tls.ca")],
+            },
+            Rule::Conflicts(vec![
+                String::from("password"),
+                String::from("password_file"),
+                String::from("password_command"),
+            ]),
+        ];
+
+        let set = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| key.to_string())
+                .collect::<HashSet<_>>()
+        };
+
+        assert!(check(&rules, &set(&["tls_key", "password_file"])).is_empty());
+        assert_eq!(
+            check(
+                &rules,
+                &set(&["tls_cert", "tls_ca", "password", "password_command"])
+            ),
+            vec![
+                ValidationError::new(
+                    "tls.cert",
+                    "requires `tls.key` to be set as well"
+                ),
+                ValidationError::new(
+                    "password",
+                    "conflicts with `password_command`, only one of them may be set"
+                ),
+            ]
+        );
+    }
+}
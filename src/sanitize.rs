@@ -3,3 +3,66 @@
 pub(crate) fn is_quote_or_whitespace(c: char) -> bool {
     c == '"' || c == '\'' || c == ' '
 }
+
+/// Byte offsets of NUL and other control characters in `value`
+///
+/// Tabs, newlines and carriage returns are allowed, since
+/// those legitimately show up in multi-line values
+pub(crate) fn control_char_offsets(value: &str) -> Vec<usize> {
+    value
+        .char_indices()
+        .filter(|(_, c)| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+/// How the keys or the values of variables are trimmed before they're
+/// deserialized. Set with [`crate::Builder::trim_keys`], [`crate::Builder::trim_values`]
+/// and [`crate::Builder::trim_elements`]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TrimPolicy {
+    /// Remove spaces, single quotes and double quotes from both ends,
+    /// however many there are, which is what the free functions do
+    #[default]
+    QuotesAndWhitespace,
+
+    /// Remove whitespace from both ends, leaving quotes alone
+    Whitespace,
+
+    /// Remove whitespace from both ends, then a single pair of matching quotes
+    /// surrounding the rest, leaving whatever is inside the quotes untouched,
+    /// so `" padded "` is read as ` padded `
+    Unquote,
+
+    /// Leave everything as it is, for when surrounding whitespace is significant
+    None,
+
+    /// Remove the characters the function returns `true` for from both ends
+    Custom(fn(char) -> bool),
+}
+
+impl TrimPolicy {
+    /// Trim `input` according to the policy
+    pub(crate) fn apply<'a>(&self, input: &'a str) -> &'a str {
+        match self {
+            TrimPolicy::QuotesAndWhitespace => {
+                input.trim_matches(is_quote_or_whitespace)
+            }
+            TrimPolicy::Whitespace => input.trim(),
+            TrimPolicy::Unquote => {
+                let input = input.trim();
+
+                ['"', '\'']
+                    .into_iter()
+                    .find_map(|quote| {
+                        input
+                            .strip_prefix(quote)
+                            .and_then(|rest| rest.strip_suffix(quote))
+                    })
+                    .unwrap_or(input)
+            }
+            TrimPolicy::None => input,
+            TrimPolicy::Custom(trimmer) => input.trim_matches(*trimmer),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Listing the variables a struct is read from, for generating
+//! `.env.example` files and deployment docs from it
+
+use crate::describe::describe;
+use crate::{to_vec, KeyCase, Result};
+use serde::{de, Serialize};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A field of a struct and the variable it's read from, see [`schema`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FieldSpec {
+    /// The name of the field, as serde knows it
+    pub name: &'static str,
+
+    /// The key of the variable, in SCREAMING_SNAKE_CASE
+    pub key: String,
+
+    /// The kind of value the field expects, as far as serde tells: the name
+    /// of a primitive such as `u16`, `bool` or `string`, `enum` for unit variants,
+    /// `seq` for delimited lists, `map` for nested structs and maps,
+    /// or `any` for types that deserialize themselves
+    pub hint: &'static str,
+
+    /// Whether the field is an [`Option`], which can be left unset
+    pub optional: bool,
+
+    /// The value of the field in the default of the struct,
+    /// only known to [`schema_with_defaults`]
+    pub default: Option<String>,
+}
+
+/// List the fields of the struct `T` with the variables they're read from,
+/// the kind of value they expect and whether they're optional.
+/// Nothing is deserialized, so no variables need to be set.
+///
+/// Returns an empty list for anything other than a struct with named fields,
+/// including structs with flattened fields, whose keys can't be known in advance.
+///
+/// # Example
+///
+/// ```
+/// use renvar::schema;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+///     hosts: Vec<String>,
+///     log_level: Option<String>,
+/// }
+///
+/// let specs = schema::<App>();
+///
+/// assert_eq!(specs[0].key, "PORT");
+/// assert_eq!(specs[0].hint, "u16");
+/// assert_eq!(specs[1].hint, "seq");
+/// assert!(specs[2].optional);
+/// ```
+pub fn schema<'de, T>() -> Vec<FieldSpec>
+where
+    T: de::Deserialize<'de>,
+{
+    describe::<T>()
+        .into_iter()
+        .map(|field| FieldSpec {
+            name: field.name,
+            key: KeyCase::ScreamingSnake.to_key(field.name),
+            hint: field.shape.hint(),
+            optional: field.optional,
+            default: None,
+        })
+        .collect()
+}
+
+/// Like [`schema`], also filling in the default of every field, taken
+/// from [`Default::default`] of the struct as it would be serialized,
+/// which matches the defaults of a struct marked `#[serde(default)]`
+///
+/// # Errors
+///
+/// If the default can't be serialized, see [`crate::to_vec`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::schema_with_defaults;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Deserialize, Serialize)]
+/// #[serde(default)]
+/// struct App {
+///     port: u16,
+///     log_level: Option<String>,
+/// }
+///
+/// let specs = schema_with_defaults::<App>().unwrap();
+///
+/// assert_eq!(specs[0].default.as_deref(), Some("0"));
+/// assert_eq!(specs[1].default, None);
+/// ```
+pub fn schema_with_defaults<T>() -> Result<Vec<FieldSpec>>
+where
+    T: de::DeserializeOwned + Serialize + Default,
+{
+    let defaults = to_vec(&T::default())?;
+
+    Ok(schema::<T>()
+        .into_iter()
+        .map(|mut spec| {
+            spec.default = defaults
+                .iter()
+                .find(|(key, _)| *key == spec.key)
+                .map(|(_, value)| value.clone());
+            spec
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{schema, schema_with_defaults, FieldSpec};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(default)]
+    struct Server {
+        max_connections: u32,
+        hosts: Vec<String>,
+        tls: Option<bool>,
+    }
+
+    impl Default for Server {
+        fn default() -> Self {
+            Self {
+                max_connections: 16,
+                hosts: vec![String::from("a"), String::from("b")],
+                tls: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_with_defaults() {
+        assert_eq!(
+            schema_with_defaults::<Server>().unwrap(),
+            vec![
+                FieldSpec {
+                    name: "max_connections",
+                    key: String::from("MAX_CONNECTIONS"),
+                    hint: "u32",
+                    optional: false,
+                    default: Some(String::from("16")),
+                },
+                FieldSpec {
+                    name: "hosts",
+                    key: String::from("HOSTS"),
+                    hint: "seq",
+                    optional: false,
+                    default: Some(String::from("a,b")),
+                },
+                FieldSpec {
+                    name: "tls",
+                    key: String::from("TLS"),
+                    hint: "bool",
+                    optional: true,
+                    default: None,
+                },
+            ]
+        );
+        assert!(schema::<Vec<String>>().is_empty());
+    }
+}
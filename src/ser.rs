@@ -0,0 +1,643 @@
+//! Module that provides an [`EnvVarSerializer`], the counterpart of
+//! [`crate::de::EnvVarDeserializer`]: serializes Rust structs into
+//! environment variable `(key, value)` pairs
+//!
+//! Users should prefer to use [`crate::to_vec`] over using the
+//! [`EnvVarSerializer`] directly.
+//!
+//! Keys are written in SCREAMING_SNAKE_CASE, unless another [`crate::KeyCase`]
+//! is chosen with [`EnvVarSerializer::with_key_case`], and values are written
+//! in the same representation the deserializer accepts, so that the
+//! pairs deserialize back into the same value.
+//!
+//! # Example
+//!
+//! ```
+//! use renvar::{from_iter, to_vec};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+//! struct CustomStruct {
+//!     field: String,
+//!     list: Vec<u16>,
+//! }
+//!
+//! let value = CustomStruct {
+//!     field: String::from("value"),
+//!     list: vec![1, 2, 3],
+//! };
+//!
+//! let vars = to_vec(&value).unwrap();
+//!
+//! assert_eq!(
+//!     vars,
+//!     vec![
+//!         (String::from("FIELD"), String::from("value")),
+//!         (String::from("LIST"), String::from("1,2,3")),
+//!     ]
+//! );
+//!
+//! assert_eq!(from_iter::<CustomStruct, _>(vars).unwrap(), value);
+//! ```
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::{repr, Error, KeyCase, Result};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serializer for environment variables
+///
+/// Accepts structs and maps at the top level, whose fields
+/// or entries each become a `(key, value)` pair
+#[derive(Debug, Default)]
+pub struct EnvVarSerializer {
+    vars: Vec<(String, String)>,
+    key: Option<String>,
+    key_case: KeyCase,
+}
+
+impl EnvVarSerializer {
+    /// Construct an empty [`EnvVarSerializer`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct an empty [`EnvVarSerializer`] that writes keys in `key_case`
+    pub fn with_key_case(key_case: KeyCase) -> Self {
+        Self {
+            key_case,
+            ..Self::default()
+        }
+    }
+
+    /// Consume the serializer, returning the `(key, value)` pairs
+    pub fn into_inner(self) -> Vec<(String, String)> {
+        self.vars
+    }
+
+    fn push(&mut self, key: &str, value: Option<String>) {
+        if let Some(value) = value {
+            self.vars.push((self.key_case.to_key(key), value));
+        }
+    }
+}
+
+/// Error for types that can't be represented at the top level
+fn top_level(kind: &str) -> Error {
+    Error::Custom(format!(
+        "expected a struct or a map at the top level, found {}",
+        kind
+    ))
+}
+
+macro_rules! top_level_unsupported {
+    ($($method:ident($($arg:ty),*) => $kind:expr,)*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok> {
+                Err(top_level($kind))
+            }
+        )*
+    }
+}
+
+impl ser::Serializer for &mut EnvVarSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    top_level_unsupported! {
+        serialize_bool(bool) => "a bool",
+        serialize_i8(i8) => "an integer",
+        serialize_i16(i16) => "an integer",
+        serialize_i32(i32) => "an integer",
+        serialize_i64(i64) => "an integer",
+        serialize_u8(u8) => "an integer",
+        serialize_u16(u16) => "an integer",
+        serialize_u32(u32) => "an integer",
+        serialize_u64(u64) => "an integer",
+        serialize_f32(f32) => "a float",
+        serialize_f64(f64) => "a float",
+        serialize_char(char) => "a char",
+        serialize_str(&str) => "a string",
+        serialize_bytes(&[u8]) => "bytes",
+        serialize_unit() => "a unit",
+        serialize_unit_struct(&'static str) => "a unit struct",
+        serialize_unit_variant(&'static str, u32, &'static str) => "an enum",
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = name;
+
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(top_level("an enum"))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(top_level("a sequence"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+        Err(top_level("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(top_level("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(top_level("an enum"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(top_level("an enum"))
+    }
+}
+
+impl ser::SerializeStruct for &mut EnvVarSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value.serialize(EnvVarValueSerializer)?;
+        self.push(key, value);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut EnvVarSerializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match key.serialize(EnvVarValueSerializer)? {
+            Some(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            None => Err(Error::Custom(String::from("map keys can't be empty"))),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.key.take().ok_or_else(|| {
+            Error::Custom(String::from("serialize_value called before serialize_key"))
+        })?;
+
+        let value = value.serialize(EnvVarValueSerializer)?;
+        self.push(&key, value);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serializes a single value into the string after `=`
+///
+/// Returns `None` for values that should be left out entirely, such as [`Option::None`]
+#[derive(Debug)]
+struct EnvVarValueSerializer;
+
+/// Error for types that can't be represented as the value of a variable
+fn nested(kind: &str) -> Error {
+    Error::Custom(format!("{} can't be serialized into a single value", kind))
+}
+
+macro_rules! serialize_display {
+    ($($method:ident($typ:ty),)*) => {
+        $(
+            fn $method(self, value: $typ) -> Result<Self::Ok> {
+                Ok(Some(value.to_string()))
+            }
+        )*
+    }
+}
+
+impl ser::Serializer for EnvVarValueSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = Impossible<Option<String>, Error>;
+    type SerializeMap = Impossible<Option<String>, Error>;
+    type SerializeStruct = Impossible<Option<String>, Error>;
+    type SerializeStructVariant = Impossible<Option<String>, Error>;
+
+    serialize_display! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+        String::from_utf8(value.to_vec())
+            .map(Some)
+            .map_err(|_| nested("non UTF-8 bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Some(String::new()))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        Ok(Some(repr::unit_struct(name)))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(repr::unit_variant(variant)))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        let _ = name;
+
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(nested("an enum variant with data"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or_default())))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(nested("an enum variant with data"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(nested("a nested map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Err(nested("a nested struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(nested("an enum variant with data"))
+    }
+}
+
+/// Serializes sequences into comma delimited values
+#[derive(Debug)]
+struct SeqSerializer(Vec<String>);
+
+impl SeqSerializer {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value.serialize(EnvVarValueSerializer)?;
+        self.0
+            .push(repr::seq_element(value.unwrap_or_default()));
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0.join(&repr::SEQ_DELIMITER.to_string())))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serialize some type `T` into `(key, value)` pairs of environment variables
+///
+/// `T` is expected to be a struct or a map. Keys are uppercased and
+/// fields that are [`None`] are left out.
+///
+/// # Errors
+///
+/// If `T` isn't a struct or a map, or it contains values that can't be
+/// represented as a single environment variable, such as nested structs
+///
+/// # Example
+///
+/// ```
+/// use renvar::to_vec;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct CustomStruct {
+///     key: String,
+///     port: u16,
+///     maybe: Option<String>,
+/// }
+///
+/// let value = CustomStruct {
+///     key: String::from("value"),
+///     port: 8080,
+///     maybe: None,
+/// };
+///
+/// assert_eq!(
+///     to_vec(&value).unwrap(),
+///     vec![
+///         (String::from("KEY"), String::from("value")),
+///         (String::from("PORT"), String::from("8080")),
+///     ]
+/// )
+/// ```
+pub fn to_vec<T>(value: &T) -> Result<Vec<(String, String)>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = EnvVarSerializer::new();
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_vec;
+    use crate::from_iter;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Unit;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct NewType(u64);
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Enumeration {
+        A,
+        B,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Test {
+        string_field: String,
+        sequence: Vec<String>,
+        empty_sequence: Vec<String>,
+        unit: Unit,
+        newtype: NewType,
+        optional_field: Option<String>,
+        missing_field: Option<String>,
+        enumeration: Enumeration,
+        float: f64,
+        flag: bool,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Test {
+            string_field: String::from("hello"),
+            sequence: vec![String::from("first"), String::from("second")],
+            empty_sequence: vec![],
+            unit: Unit,
+            newtype: NewType(123),
+            optional_field: Some(String::from("some")),
+            missing_field: None,
+            enumeration: Enumeration::B,
+            float: 1.5,
+            flag: true,
+        };
+
+        let vars = to_vec(&value).unwrap();
+
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("STRING_FIELD"), String::from("hello")),
+                (String::from("SEQUENCE"), String::from("first,second")),
+                (String::from("EMPTY_SEQUENCE"), String::from("")),
+                (String::from("UNIT"), String::from("Unit")),
+                (String::from("NEWTYPE"), String::from("123")),
+                (String::from("OPTIONAL_FIELD"), String::from("some")),
+                (String::from("ENUMERATION"), String::from("B")),
+                (String::from("FLOAT"), String::from("1.5")),
+                (String::from("FLAG"), String::from("true")),
+            ]
+        );
+
+        assert_eq!(from_iter::<Test, _>(vars).unwrap(), value);
+    }
+
+    #[test]
+    fn test_map() {
+        let value = BTreeMap::from([("key", 1), ("other_key", 2)]);
+
+        assert_eq!(
+            to_vec(&value).unwrap(),
+            vec![
+                (String::from("KEY"), String::from("1")),
+                (String::from("OTHER_KEY"), String::from("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        #[derive(Serialize)]
+        struct Nested {
+            inner: BTreeMap<String, String>,
+        }
+
+        assert!(to_vec(&1u8).is_err());
+        assert!(to_vec(&Nested {
+            inner: BTreeMap::new()
+        })
+        .is_err());
+    }
+}
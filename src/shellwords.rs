@@ -0,0 +1,123 @@
+//! Values written with the quoting rules of POSIX shells, and splitting
+//! them into words, for variables holding command line arguments
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Remove the quotes and backslashes of `value` following the rules of POSIX shells,
+/// keeping the whitespace between words, but not the one around them
+pub(crate) fn unquote(value: &str) -> Result<String, String> {
+    Ok(scan(value, false)?.pop().unwrap_or_default())
+}
+
+/// Split `value` into words at unquoted whitespace, removing the quotes
+/// and backslashes following the rules of POSIX shells, like the shell does
+/// with the arguments of a command
+pub(crate) fn split(value: &str) -> Result<Vec<String>, String> {
+    scan(value, true)
+}
+
+/// Read the words of `value`, which is a single word unless `split` is set.
+///
+/// Single quotes keep everything until the next one as it is, double quotes
+/// keep everything until the next unescaped one, with `\` only escaping `$`,
+/// `` ` ``, `"`, `\` and newlines, and `\` escapes any character outside of quotes.
+/// Escaped newlines are removed.
+fn scan(value: &str, split: bool) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    // Where the unquoted whitespace ending the word starts
+    let mut trailing = None;
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            if split {
+                words.extend(word.take());
+            } else if let Some(word) = &mut word {
+                trailing.get_or_insert(word.len());
+                word.push(c);
+            }
+
+            continue;
+        }
+
+        let current = word.get_or_insert_with(String::new);
+        trailing = None;
+
+        match c {
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => current.push(c),
+                    None => return Err(String::from("unterminated single quote")),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('\n') => {}
+                        Some(c @ ('$' | '`' | '"' | '\\')) => current.push(c),
+                        Some(c) => {
+                            current.push('\\');
+                            current.push(c);
+                        }
+                        None => return Err(String::from("unterminated double quote")),
+                    },
+                    Some(c) => current.push(c),
+                    None => return Err(String::from("unterminated double quote")),
+                }
+            },
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => current.push(c),
+                None => return Err(String::from("expected a character after `\\`")),
+            },
+            c => current.push(c),
+        }
+    }
+
+    if let Some(mut word) = word {
+        if let Some(trailing) = trailing {
+            word.truncate(trailing);
+        }
+
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split, unquote};
+
+    #[test]
+    fn test_shell_words() {
+        assert_eq!(
+            split(r#"--foo 'a b' "c \"d\" \$e \f" g\ h '' 'it'\''s'"#),
+            Ok(vec![
+                String::from("--foo"),
+                String::from("a b"),
+                String::from("c \"d\" $e \\f"),
+                String::from("g h"),
+                String::from(""),
+                String::from("it's"),
+            ])
+        );
+        assert_eq!(split("  "), Ok(vec![]));
+        assert_eq!(
+            unquote(r#"  'single'"double" and  more\  "#),
+            Ok(String::from("singledouble and  more "))
+        );
+        assert_eq!(unquote(""), Ok(String::new()));
+        assert_eq!(
+            split("'open"),
+            Err(String::from("unterminated single quote"))
+        );
+        assert_eq!(
+            unquote("trailing\\"),
+            Err(String::from("expected a character after `\\`"))
+        );
+    }
+}
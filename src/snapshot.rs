@@ -0,0 +1,206 @@
+//! Capturing the environment at a point in time and comparing captures
+
+use crate::{from_iter, Builder, Change, RawEnv, Result};
+use serde::de;
+use std::collections::BTreeMap;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The environment variables of the running process at the time of
+/// [`EnvSnapshot::capture`], sorted by key, which can be compared with a later
+/// capture with [`EnvSnapshot::diff`] or deserialized like the environment itself.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{Change, EnvSnapshot};
+/// use serde::Deserialize;
+/// use std::env;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct Database {
+///     snapshot_doc_db_host: String,
+/// }
+///
+/// env::set_var("SNAPSHOT_DOC_DB_HOST", "localhost");
+/// let before = EnvSnapshot::capture();
+///
+/// env::set_var("SNAPSHOT_DOC_DB_HOST", "remote");
+/// let after = EnvSnapshot::capture();
+///
+/// let changes = before.diff(&after);
+///
+/// assert!(changes.contains(&Change {
+///     key: "SNAPSHOT_DOC_DB_HOST".to_owned(),
+///     old: Some("localhost".to_owned()),
+///     new: Some("remote".to_owned()),
+/// }));
+///
+/// let database: Database = before.deserialize().unwrap();
+///
+/// assert_eq!(database.snapshot_doc_db_host, "localhost");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvSnapshot {
+    vars: BTreeMap<String, String>,
+}
+
+impl EnvSnapshot {
+    /// Capture the environment of the running process.
+    /// Variables that aren't valid unicode are skipped
+    pub fn capture() -> Self {
+        RawEnv::from_env().into_iter().collect()
+    }
+
+    /// The value of the variable named `key` at the time of the capture
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(String::as_str)
+    }
+
+    /// The `(key, value)` pairs, sorted by key
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The number of variables
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Whether there are no variables
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Compare with a later snapshot, returning a [`Change`] for every
+    /// variable that was added, removed or set to another value, sorted by key
+    pub fn diff(&self, other: &EnvSnapshot) -> Vec<Change> {
+        let mut keys: Vec<&String> = self
+            .vars
+            .keys()
+            .chain(other.vars.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter(|key| self.vars.get(*key) != other.vars.get(*key))
+            .map(|key| Change {
+                key: key.clone(),
+                old: self.vars.get(key).cloned(),
+                new: other.vars.get(key).cloned(),
+            })
+            .collect()
+    }
+
+    /// Deserialize some type `T` from the variables of the snapshot,
+    /// like [`crate::from_iter`] does
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn deserialize<T>(&self) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        from_iter(self.vars.clone())
+    }
+
+    /// Deserialize some type `T` from the variables of the snapshot
+    /// with the settings of `builder`
+    ///
+    /// # Errors
+    ///
+    /// Any errors that might occur during deserialization
+    pub fn deserialize_with<T>(&self, builder: &Builder) -> Result<T>
+    where
+        T: de::DeserializeOwned,
+    {
+        builder.from_iter(self.vars.clone())
+    }
+}
+
+impl FromIterator<(String, String)> for EnvSnapshot {
+    /// Later variables override earlier ones with the same key
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            vars: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for EnvSnapshot {
+    type Item = (String, String);
+    type IntoIter = std::collections::btree_map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vars.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnvSnapshot;
+    use crate::{builder, Change};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let before: EnvSnapshot = vec![
+            (String::from("APP_HOST"), String::from("localhost")),
+            (String::from("APP_PORT"), String::from("8080")),
+            (String::from("APP_DEBUG"), String::from("true")),
+        ]
+        .into_iter()
+        .collect();
+        let after: EnvSnapshot = vec![
+            (String::from("APP_HOST"), String::from("localhost")),
+            (String::from("APP_PORT"), String::from("9090")),
+            (String::from("APP_WORKERS"), String::from("4")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                Change {
+                    key: String::from("APP_DEBUG"),
+                    old: Some(String::from("true")),
+                    new: None,
+                },
+                Change {
+                    key: String::from("APP_PORT"),
+                    old: Some(String::from("8080")),
+                    new: Some(String::from("9090")),
+                },
+                Change {
+                    key: String::from("APP_WORKERS"),
+                    old: None,
+                    new: Some(String::from("4")),
+                },
+            ]
+        );
+        assert!(after.diff(&after).is_empty());
+
+        let server: Server = after
+            .deserialize_with(&builder().prefix("APP_"))
+            .unwrap();
+
+        assert_eq!(
+            server,
+            Server {
+                host: String::from("localhost"),
+                port: 9090,
+            }
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Pluggable origins of variables, such as secret stores
+//! queried at startup, fed through the same deserializer
+
+use crate::convert::maybe_invalid_unicode_vars_os;
+use crate::{from_iter, RawEnv, Result};
+use serde::de;
+#[cfg(feature = "async_source")]
+use std::future::Future;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Somewhere variables can be loaded from, such as a remote secret store.
+///
+/// Implemented for closures returning the variables, for [`RawEnv`], for the environment
+/// of the running process with [`ProcessEnv`] and for [`crate::MockEnv`] in tests,
+/// so application code can take any `E: Source` and tests never touch the environment.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_source, Result, Source};
+/// use serde::Deserialize;
+///
+/// /// Hands out a fixed set of variables, where a real source would query a service
+/// struct Static;
+///
+/// impl Source for Static {
+///     fn load(&self) -> Result<Vec<(String, String)>> {
+///         Ok(vec![("PORT".to_owned(), "8080".to_owned())])
+///     }
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     port: u16,
+/// }
+///
+/// assert_eq!(
+///     from_source::<CustomStruct, _>(&Static).unwrap(),
+///     CustomStruct { port: 8080 }
+/// );
+/// ```
+pub trait Source {
+    /// Load the `(key, value)` pairs
+    ///
+    /// # Errors
+    ///
+    /// If the variables can't be loaded
+    fn load(&self) -> Result<Vec<(String, String)>>;
+}
+
+impl<F> Source for F
+where
+    F: Fn() -> Result<Vec<(String, String)>>,
+{
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        self()
+    }
+}
+
+impl Source for RawEnv {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.clone().into_iter().collect())
+    }
+}
+
+/// The environment of the running process as a [`Source`], read every time it's loaded.
+/// Loading fails with [`crate::Error::InvalidUnicode`] if any of
+/// the variables contain invalid unicode, like [`crate::from_os_env`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessEnv;
+
+impl Source for ProcessEnv {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        Ok(maybe_invalid_unicode_vars_os()?.collect())
+    }
+}
+
+/// Like [`Source`], for when loading the variables means waiting on I/O,
+/// such as a request to Vault or AWS SSM made with an async client
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_async_source, AsyncSource, Result};
+/// use serde::Deserialize;
+///
+/// struct Remote;
+///
+/// impl AsyncSource for Remote {
+///     async fn load(&self) -> Result<Vec<(String, String)>> {
+///         // A real source would await a request here
+///         Ok(vec![("PORT".to_owned(), "8080".to_owned())])
+///     }
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     port: u16,
+/// }
+///
+/// async fn load() -> Result<CustomStruct> {
+///     from_async_source(&Remote).await
+/// }
+/// ```
+#[cfg(feature = "async_source")]
+pub trait AsyncSource {
+    /// Load the `(key, value)` pairs
+    ///
+    /// # Errors
+    ///
+    /// If the variables can't be loaded
+    fn load(&self) -> impl Future<Output = Result<Vec<(String, String)>>> + Send;
+}
+
+/// Deserialize some type `T` from the variables loaded from `source`
+///
+/// Like with [`crate::from_iter`], single quotes, double quotes
+/// and whitespace will be trimmed
+///
+/// # Errors
+///
+/// If the source fails to load or any errors that might occur during deserialization
+pub fn from_source<T, S>(source: &S) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    S: Source + ?Sized,
+{
+    from_iter(source.load()?)
+}
+
+/// Deserialize some type `T` from the variables loaded from the async `source`
+///
+/// Like with [`crate::from_iter`], single quotes, double quotes
+/// and whitespace will be trimmed
+///
+/// # Errors
+///
+/// If the source fails to load or any errors that might occur during deserialization
+#[cfg(feature = "async_source")]
+pub async fn from_async_source<T, S>(source: &S) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    S: AsyncSource + ?Sized,
+{
+    from_iter(source.load().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_source;
+    use crate::{builder, Error, RawEnv};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Database {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_sources() {
+        let raw = RawEnv::parse("HOST=localhost\nPORT=5432\n");
+
+        assert_eq!(
+            from_source::<Database, _>(&raw).unwrap(),
+            Database {
+                host: String::from("localhost"),
+                port: 5432,
+            }
+        );
+
+        let source = || Ok(vec![(String::from("DB_HOST"), String::from(" 'remote' "))]);
+
+        assert_eq!(
+            builder()
+                .prefix("DB_")
+                .from_source::<Database, _>(&source),
+            Err(Error::MissingValue(String::from("port")))
+        );
+
+        let failing = || Err(Error::Custom(String::from("unreachable")));
+
+        assert_eq!(
+            from_source::<Database, _>(&failing),
+            Err(Error::Custom(String::from("unreachable")))
+        );
+    }
+
+    #[cfg(feature = "async_source")]
+    #[test]
+    fn test_async_source() {
+        use super::{from_async_source, AsyncSource};
+        use std::{
+            future::Future,
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+
+        struct Remote;
+
+        impl AsyncSource for Remote {
+            async fn load(&self) -> crate::Result<Vec<(String, String)>> {
+                Ok(vec![
+                    (String::from("HOST"), String::from("remote")),
+                    (String::from("PORT"), String::from("6432")),
+                ])
+            }
+        }
+
+        let mut future = pin!(from_async_source::<Database, _>(&Remote));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(database) => assert_eq!(
+                database.unwrap(),
+                Database {
+                    host: String::from("remote"),
+                    port: 6432,
+                }
+            ),
+            Poll::Pending => panic!("nothing to wait for"),
+        }
+    }
+}
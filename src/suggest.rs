@@ -0,0 +1,87 @@
+//! "Did you mean" suggestions for misspelled keys
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Find the candidate closest to `target`, as long as it's close enough
+/// to be a plausible misspelling: at most a third of its characters differ
+pub(crate) fn closest<'a, I>(target: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (target.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (levenshtein(target, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Number of single character insertions, deletions or
+/// substitutions needed to turn `a` into `b`
+pub(crate) fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest, levenshtein};
+    use crate::{from_iter, Error};
+    use serde::Deserialize;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("database_url", "databse_url"), 1);
+        assert_eq!(
+            closest("database_url", ["data", "databse_url", "database_uri"]),
+            Some("databse_url")
+        );
+        assert_eq!(closest("port", ["home", "user"]), None);
+    }
+
+    #[test]
+    fn test_missing_field_suggestion() {
+        #[derive(Debug, Deserialize)]
+        struct Database {
+            #[allow(dead_code)]
+            database_url: String,
+        }
+
+        let error = from_iter::<Database, _>(vec![
+            (String::from("HOME"), String::from("/root")),
+            (String::from("DATABSE_URL"), String::from("postgres://")),
+        ])
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            Error::SimilarKey {
+                field: String::from("database_url"),
+                similar: String::from("DATABSE_URL"),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "missing `database_url`; found similar variable `DATABSE_URL`"
+        );
+    }
+}
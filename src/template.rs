@@ -0,0 +1,280 @@
+//! Generating `.env.example` files and markdown docs from the fields of a struct
+
+use crate::schema::{schema, FieldSpec};
+use serde::de;
+use std::fmt::Write;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Writes the variables of a struct out as an `.env.example` file or as
+/// a markdown table, from the list of its fields given by [`crate::schema`].
+/// Users are meant to obtain this struct by calling [`template`], or [`Template::new`]
+/// with the fields from [`crate::schema_with_defaults`] to include defaults.
+///
+/// Serde knows nothing about doc comments, so the description
+/// of each field is registered with [`Template::doc`].
+///
+/// # Example
+///
+/// ```
+/// use renvar::template;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+///     log_level: Option<String>,
+/// }
+///
+/// let template = template::<App>()
+///     .prefix("APP_")
+///     .doc("port", "Port the server listens on");
+///
+/// let example = template.env_example();
+/// let lines: Vec<&str> = example.lines().collect();
+///
+/// assert_eq!(
+///     lines,
+///     vec![
+///         "# Port the server listens on",
+///         "# u16",
+///         "APP_PORT=",
+///         "",
+///         "# string, optional",
+///         "# APP_LOG_LEVEL=",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Template<'a> {
+    specs: Vec<FieldSpec>,
+    prefix: &'a str,
+    docs: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Template<'a> {
+    /// Create a template for the fields in `specs`
+    pub fn new(specs: Vec<FieldSpec>) -> Self {
+        Self {
+            specs,
+            prefix: "",
+            docs: Vec::new(),
+        }
+    }
+
+    /// Set the prefix the keys are written with
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Describe `field`, which can span several lines
+    pub fn doc(mut self, field: &'a str, doc: &'a str) -> Self {
+        self.docs.push((field, doc));
+        self
+    }
+
+    /// Write an `.env.example` file, listing every variable with its description
+    /// and type in comments above it. Variables are set to their default if it's
+    /// known and left empty otherwise, optional ones being commented out.
+    pub fn env_example(&self) -> String {
+        let mut output = String::new();
+
+        for (i, spec) in self.specs.iter().enumerate() {
+            if i > 0 {
+                output.push('\n');
+            }
+
+            if let Some(doc) = self.doc_of(spec) {
+                for line in doc.lines() {
+                    let _ = writeln!(output, "# {}", line);
+                }
+            }
+
+            match spec.optional {
+                true => {
+                    let _ = writeln!(output, "# {}, optional", spec.hint);
+                    output.push_str("# ");
+                }
+                false => {
+                    let _ = writeln!(output, "# {}", spec.hint);
+                }
+            }
+
+            let _ = writeln!(
+                output,
+                "{}{}={}",
+                self.prefix,
+                spec.key,
+                spec.default.as_deref().unwrap_or_default()
+            );
+        }
+
+        output
+    }
+
+    /// Write a markdown table listing every variable with
+    /// its type, whether it's required, its default and description.
+    /// Fields with a default or of type `Option` aren't required
+    pub fn markdown(&self) -> String {
+        let mut output = String::from(
+            "| Variable | Type | Required | Default | Description |\n\
+             | --- | --- | --- | --- | --- |\n",
+        );
+
+        for spec in &self.specs {
+            let default = match &spec.default {
+                Some(default) => format!("`{}`", default),
+                None => String::new(),
+            };
+            let doc = self
+                .doc_of(spec)
+                .map(|doc| doc.lines().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default();
+
+            let _ = writeln!(
+                output,
+                "| `{}{}` | `{}` | {} | {} | {} |",
+                self.prefix,
+                spec.key,
+                spec.hint,
+                if spec.optional || spec.default.is_some() {
+                    "no"
+                } else {
+                    "yes"
+                },
+                escape(&default),
+                escape(&doc)
+            );
+        }
+
+        output
+    }
+
+    fn doc_of(&self, spec: &FieldSpec) -> Option<&'a str> {
+        self.docs
+            .iter()
+            .rev()
+            .find(|(field, _)| *field == spec.name)
+            .map(|(_, doc)| *doc)
+    }
+}
+
+/// Escape the pipes that would otherwise end a markdown table cell
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|")
+}
+
+/// Create a [`Template`] for the fields of the struct `T`
+///
+/// # Example
+///
+/// ```
+/// use renvar::template;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+/// }
+///
+/// let template = template::<App>().doc("port", "Port the server listens on");
+/// ```
+pub fn template<'a, 'de, T>() -> Template<'a>
+where
+    T: de::Deserialize<'de>,
+{
+    Template::new(schema::<T>())
+}
+
+/// Write an `.env.example` file for the struct `T`, see [`Template::env_example`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::generate_env_example;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+/// }
+///
+/// assert_eq!(generate_env_example::<App>("APP_"), "# u16\nAPP_PORT=\n");
+/// ```
+pub fn generate_env_example<'de, T>(prefix: &str) -> String
+where
+    T: de::Deserialize<'de>,
+{
+    template::<T>().prefix(prefix).env_example()
+}
+
+/// Write a markdown table of the variables of the struct `T`, see [`Template::markdown`]
+///
+/// # Example
+///
+/// ```
+/// use renvar::generate_markdown;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct App {
+///     port: u16,
+/// }
+///
+/// assert!(generate_markdown::<App>().contains("| `PORT` | `u16` | yes |  |  |"));
+/// ```
+pub fn generate_markdown<'de, T>() -> String
+where
+    T: de::Deserialize<'de>,
+{
+    template::<T>().markdown()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use crate::schema_with_defaults;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(default)]
+    struct Server {
+        hosts: Vec<String>,
+        tls: Option<bool>,
+    }
+
+    impl Default for Server {
+        fn default() -> Self {
+            Self {
+                hosts: vec![String::from("0.0.0.0"), String::from("::")],
+                tls: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_template_with_defaults() {
+        let template = Template::new(schema_with_defaults::<Server>().unwrap())
+            .prefix("SVC_")
+            .doc("hosts", "Hosts to bind to,\nseparated by a comma | or not");
+
+        assert_eq!(
+            template.env_example(),
+            "# Hosts to bind to,\n\
+             # separated by a comma | or not\n\
+             # seq\n\
+             SVC_HOSTS=0.0.0.0,::\n\
+             \n\
+             # bool, optional\n\
+             # SVC_TLS=\n"
+        );
+        assert_eq!(
+            template.markdown(),
+            "| Variable | Type | Required | Default | Description |\n\
+             | --- | --- | --- | --- | --- |\n\
+             | `SVC_HOSTS` | `seq` | no | `0.0.0.0,::` | Hosts to bind to, separated by a comma \\| or not |\n\
+             | `SVC_TLS` | `bool` | no |  |  |\n"
+        );
+    }
+}
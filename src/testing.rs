@@ -0,0 +1,350 @@
+//! Helpers for tests that read configuration from the environment
+//!
+//! The environment is global to the process, while tests run in parallel threads,
+//! so tests that set variables can observe each other's changes. Every helper
+//! of this module holds the same lock while the environment is modified,
+//! so tests using them run one at a time and always see their own variables.
+//!
+//! With the `proptest` feature, [`roundtrip`] checks that values survive
+//! being serialized into variables and deserialized back.
+//!
+//! # Example
+//!
+//! ```
+//! use renvar::{from_env, testing::with_vars};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Eq)]
+//! struct CustomStruct {
+//!     testing_doc_port: u16,
+//! }
+//!
+//! let custom_struct: CustomStruct =
+//!     with_vars([("TESTING_DOC_PORT", "8080")], || from_env().unwrap());
+//!
+//! assert_eq!(custom_struct, CustomStruct { testing_doc_port: 8080 });
+//! assert!(std::env::var("TESTING_DOC_PORT").is_err());
+//! ```
+
+#[cfg(feature = "proptest")]
+use crate::{from_iter, to_vec};
+#[cfg(feature = "proptest")]
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    strategy::Strategy,
+    test_runner::{Config, TestCaseError, TestRunner},
+};
+#[cfg(feature = "proptest")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "proptest")]
+use std::fmt::Debug;
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    sync::{Mutex, MutexGuard},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Serializes every modification of the environment made through this module
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Restores the variables it saved when dropped, even if the test panicked
+struct Restore {
+    saved: Vec<(OsString, Option<OsString>)>,
+}
+
+impl Restore {
+    /// Save the current values of `keys`
+    fn save<'a>(keys: impl IntoIterator<Item = &'a OsStr>) -> Self {
+        Self {
+            saved: keys
+                .into_iter()
+                .map(|key| (key.to_owned(), env::var_os(key)))
+                .collect(),
+        }
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        // Restore in reverse, so a key given several times ends up with its first saved value
+        for (key, value) in self.saved.iter().rev() {
+            match value {
+                Some(value) => env::set_var(key, value),
+                None => env::remove_var(key),
+            }
+        }
+    }
+}
+
+/// Take the lock held by the helpers of this module, for tests that
+/// modify or read the environment by other means. Released when dropped.
+///
+/// The lock isn't reentrant: calling [`with_vars`] or [`without_vars`]
+/// while holding it, or from within their closures, deadlocks.
+/// A test that panicked while holding it doesn't poison it for the others.
+pub fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Set the variables in `vars`, run `f` and restore the variables to what they
+/// were before, removing those that weren't set, then return what `f` returned.
+/// The variables are restored even if `f` panics.
+///
+/// Holds the lock described in [`lock`] for the whole duration,
+/// so `f` must not call any other helper of this module.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, testing::with_vars};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     host: String,
+///     user: String,
+/// }
+///
+/// let custom_struct: CustomStruct = with_vars(
+///     [("TESTING_DOC_HOST", "localhost"), ("TESTING_DOC_USER", "admin")],
+///     || builder().prefix("TESTING_DOC_").from_env().unwrap(),
+/// );
+///
+/// assert_eq!(custom_struct.host, "localhost");
+/// assert_eq!(custom_struct.user, "admin");
+/// ```
+pub fn with_vars<I, K, V, F, R>(vars: I, f: F) -> R
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<OsStr>,
+    V: AsRef<OsStr>,
+    F: FnOnce() -> R,
+{
+    let _lock = lock();
+    let vars: Vec<(K, V)> = vars.into_iter().collect();
+    let _restore = Restore::save(vars.iter().map(|(key, _)| key.as_ref()));
+
+    for (key, value) in &vars {
+        env::set_var(key, value);
+    }
+
+    f()
+}
+
+/// Remove the variables in `keys`, run `f` and restore the variables to what
+/// they were before, then return what `f` returned. Same as [`with_vars`]
+/// otherwise, for testing what happens when variables are missing.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_env, testing::without_vars, Error};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     testing_doc_token: String,
+/// }
+///
+/// let error = without_vars(["TESTING_DOC_TOKEN"], || {
+///     from_env::<CustomStruct>().unwrap_err()
+/// });
+///
+/// assert_eq!(error, Error::MissingValue("testing_doc_token".to_owned()));
+/// ```
+pub fn without_vars<I, K, F, R>(keys: I, f: F) -> R
+where
+    I: IntoIterator<Item = K>,
+    K: AsRef<OsStr>,
+    F: FnOnce() -> R,
+{
+    let _lock = lock();
+    let keys: Vec<K> = keys.into_iter().collect();
+    let _restore = Restore::save(keys.iter().map(AsRef::as_ref));
+
+    for key in &keys {
+        env::remove_var(key);
+    }
+
+    f()
+}
+
+/// Generate arbitrary values of `T`, a struct or a map, with [proptest](https://docs.rs/proptest), serialize
+/// each of them into variables with [`crate::to_vec`], deserialize them back with
+/// [`crate::from_iter`] and check that the result equals the original value.
+/// Catches the values that don't survive the trip, such as strings with
+/// surrounding quotes or whitespace, which are trimmed when deserialized.
+///
+/// # Panics
+///
+/// If a value doesn't survive the trip, with the simplest such value proptest found
+///
+/// # Example
+///
+/// ```
+/// use renvar::testing::roundtrip;
+/// use std::collections::BTreeMap;
+///
+/// roundtrip::<BTreeMap<u8, (i64, bool)>>();
+/// ```
+#[cfg(feature = "proptest")]
+pub fn roundtrip<T>()
+where
+    T: Arbitrary + Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    roundtrip_with(any::<T>())
+}
+
+/// Same as [`roundtrip`], generating the values with `strategy`,
+/// for types that don't implement [`Arbitrary`] or to narrow down
+/// the values to those the type is meant to hold
+///
+/// # Panics
+///
+/// If a value doesn't survive the trip, with the simplest such value proptest found
+///
+/// # Example
+///
+/// ```
+/// use proptest::prelude::*;
+/// use renvar::testing::roundtrip_with;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// roundtrip_with(
+///     ("[a-z][a-z0-9.-]{0,20}", any::<u16>()).prop_map(|(host, port)| Server { host, port }),
+/// );
+/// ```
+#[cfg(feature = "proptest")]
+pub fn roundtrip_with<T, S>(strategy: S)
+where
+    S: Strategy<Value = T>,
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    // Failures are reported by the panic, rather than saved next to the source
+    let config = Config {
+        failure_persistence: None,
+        ..Config::default()
+    };
+
+    let result = TestRunner::new(config).run(&strategy, |value| {
+        let vars =
+            to_vec(&value).map_err(|error| TestCaseError::fail(error.to_string()))?;
+        let deserialized: T = from_iter(vars.clone()).map_err(|error| {
+            TestCaseError::fail(format!("{} while deserializing {:?}", error, vars))
+        })?;
+
+        match deserialized == value {
+            true => Ok(()),
+            false => Err(TestCaseError::fail(format!(
+                "deserialized {:?} from {:?}",
+                deserialized, vars
+            ))),
+        }
+    });
+
+    if let Err(error) = result {
+        panic!("value didn't survive the round trip: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lock, with_vars, without_vars};
+    use std::{env, panic};
+
+    #[test]
+    fn test_with_vars_restores() {
+        {
+            let _lock = lock();
+            env::set_var("RENVAR_TEST_WITH_VARS_KEPT", "before");
+            env::remove_var("RENVAR_TEST_WITH_VARS_NEW");
+        }
+
+        let seen = with_vars(
+            [
+                ("RENVAR_TEST_WITH_VARS_KEPT", "during"),
+                ("RENVAR_TEST_WITH_VARS_NEW", "during"),
+            ],
+            || {
+                (
+                    env::var("RENVAR_TEST_WITH_VARS_KEPT").unwrap(),
+                    env::var("RENVAR_TEST_WITH_VARS_NEW").unwrap(),
+                )
+            },
+        );
+
+        assert_eq!(seen, (String::from("during"), String::from("during")));
+        assert_eq!(env::var("RENVAR_TEST_WITH_VARS_KEPT").unwrap(), "before");
+        assert!(env::var_os("RENVAR_TEST_WITH_VARS_NEW").is_none());
+
+        let panicked = panic::catch_unwind(|| {
+            without_vars(["RENVAR_TEST_WITH_VARS_KEPT"], || {
+                assert!(env::var_os("RENVAR_TEST_WITH_VARS_KEPT").is_none());
+                panic!("the variables are restored anyway")
+            })
+        });
+
+        assert!(panicked.is_err());
+        assert_eq!(env::var("RENVAR_TEST_WITH_VARS_KEPT").unwrap(), "before");
+
+        // The lock isn't poisoned by the panic
+        with_vars([("RENVAR_TEST_WITH_VARS_KEPT", "after")], || {});
+
+        let _lock = lock();
+        env::remove_var("RENVAR_TEST_WITH_VARS_KEPT");
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn test_roundtrip() {
+        use super::{roundtrip, roundtrip_with};
+        use proptest::{arbitrary::any, strategy::Strategy};
+        use serde::{Deserialize, Serialize};
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Server {
+            port: u16,
+            debug: bool,
+            workers: Option<u8>,
+            tags: Vec<u32>,
+        }
+
+        roundtrip::<BTreeMap<u8, (i64, bool)>>();
+        roundtrip_with(any::<(u16, bool, Option<u8>, Vec<u32>)>().prop_map(
+            |(port, debug, workers, tags)| Server {
+                port,
+                debug,
+                workers,
+                tags,
+            },
+        ));
+
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Padded {
+            name: String,
+        }
+
+        // Surrounding whitespace is trimmed, so padded strings don't survive
+        let padded = panic::catch_unwind(|| {
+            roundtrip_with(" [a-z]{1,4}".prop_map(|name| Padded { name }))
+        })
+        .unwrap_err();
+        let message = padded.downcast_ref::<String>().unwrap();
+
+        assert!(
+            message.contains(r#"deserialized Padded { name: "a" }"#),
+            "{}",
+            message
+        );
+    }
+}
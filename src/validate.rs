@@ -0,0 +1,173 @@
+//! Checks that run on a value after it was deserialized,
+//! for constraints its type can't express, such as ranges
+
+use crate::{Error, Result};
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A constraint a field of a deserialized value breaks, see [`Validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The name of the field
+    pub field: String,
+
+    /// What's wrong with its value
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Report that the value of `field` breaks a constraint, described by `message`
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Checks on a deserialized value, run by [`validate`]. Every broken
+/// constraint should be reported at once, so they can all be fixed in one go.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{from_iter, validate, Error, Validate, ValidationError};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Server {
+///     port: u16,
+///     workers: u8,
+/// }
+///
+/// impl Validate for Server {
+///     fn validate(&self) -> Result<(), Vec<ValidationError>> {
+///         let mut errors = Vec::new();
+///
+///         if self.port == 0 {
+///             errors.push(ValidationError::new("port", "must be between 1 and 65535"));
+///         }
+///         if self.workers == 0 {
+///             errors.push(ValidationError::new("workers", "must not be zero"));
+///         }
+///
+///         match errors.is_empty() {
+///             true => Ok(()),
+///             false => Err(errors),
+///         }
+///     }
+/// }
+///
+/// let vars = vec![
+///     ("PORT".to_owned(), "0".to_owned()),
+///     ("WORKERS".to_owned(), "4".to_owned()),
+/// ];
+///
+/// assert_eq!(
+///     from_iter::<Server, _>(vars).and_then(validate).unwrap_err(),
+///     Error::Validation(vec![ValidationError::new(
+///         "port",
+///         "must be between 1 and 65535"
+///     )])
+/// );
+/// ```
+pub trait Validate {
+    /// Check the value, returning every constraint it breaks
+    ///
+    /// # Errors
+    ///
+    /// If any of the constraints are broken
+    fn validate(&self) -> std::result::Result<(), Vec<ValidationError>>;
+}
+
+/// Run the checks of [`Validate`] on `value`, handing it back if it passes them.
+/// Meant to be chained after any of the `from_*` functions with [`Result::and_then`],
+/// which [`crate::Builder::from_env_validated`] and [`crate::Builder::from_iter_validated`] do.
+///
+/// # Errors
+///
+/// [`Error::Validation`] with every constraint the value breaks
+pub fn validate<T>(value: T) -> Result<T>
+where
+    T: Validate,
+{
+    match value.validate() {
+        Ok(()) => Ok(value),
+        Err(errors) => Err(Error::Validation(errors)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Validate, ValidationError};
+    use crate::{builder, Error};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Range {
+        start: u32,
+        end: u32,
+        step: u32,
+    }
+
+    impl Validate for Range {
+        fn validate(&self) -> Result<(), Vec<ValidationError>> {
+            let mut errors = Vec::new();
+
+            if self.start > self.end {
+                errors.push(ValidationError::new("start", "must not exceed end"));
+            }
+            if self.step == 0 {
+                errors.push(ValidationError::new("step", "must not be zero"));
+            }
+
+            match errors.is_empty() {
+                true => Ok(()),
+                false => Err(errors),
+            }
+        }
+    }
+
+    #[test]
+    fn test_validated() {
+        let vars = |start: &str, end: &str, step: &str| {
+            vec![
+                (String::from("RANGE_START"), start.to_owned()),
+                (String::from("RANGE_END"), end.to_owned()),
+                (String::from("RANGE_STEP"), step.to_owned()),
+            ]
+        };
+        let builder = builder().prefix("RANGE_");
+
+        assert_eq!(
+            builder.from_iter_validated::<Range, _>(vars("1", "10", "2")),
+            Ok(Range {
+                start: 1,
+                end: 10,
+                step: 2,
+            })
+        );
+
+        // Every broken constraint is reported at once
+        assert_eq!(
+            builder.from_iter_validated::<Range, _>(vars("10", "1", "0")),
+            Err(Error::Validation(vec![
+                ValidationError::new("start", "must not exceed end"),
+                ValidationError::new("step", "must not be zero"),
+            ]))
+        );
+
+        // Deserialization errors come first
+        assert!(matches!(
+            builder.from_iter_validated::<Range, _>(vars("ten", "1", "0")),
+            Err(Error::Custom(_))
+        ));
+    }
+}
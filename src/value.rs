@@ -0,0 +1,578 @@
+//! A value of the serde data model held in memory, for handing values
+//! produced by user code, such as [`crate::Builder::parse_with`] parsers,
+//! to the `Deserialize` impl of whatever type the field has
+
+use crate::{Error, Result};
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer, StrDeserializer},
+        IntoDeserializer,
+    },
+    ser::{self, Serialize},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Anything a type can serialize itself into
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Value>),
+    Unit,
+    Newtype(Box<Value>),
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Variant(&'static str, Box<Value>),
+}
+
+/// Serialize `value` into a [`Value`]
+pub(crate) fn to_value<T>(value: &T) -> Result<Value>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+struct ValueSerializer;
+
+macro_rules! serialize_as {
+    ($($method:ident($typ:ty) => $variant:ident($convert:ty),)*) => {
+        $(
+            fn $method(self, value: $typ) -> Result<Value> {
+                Ok(Value::$variant(<$convert>::from(value)))
+            }
+        )*
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    serialize_as! {
+        serialize_bool(bool) => Bool(bool),
+        serialize_i8(i8) => I64(i64),
+        serialize_i16(i16) => I64(i64),
+        serialize_i32(i32) => I64(i64),
+        serialize_i64(i64) => I64(i64),
+        serialize_i128(i128) => I128(i128),
+        serialize_u8(u8) => U64(u64),
+        serialize_u16(u16) => U64(u64),
+        serialize_u32(u32) => U64(u64),
+        serialize_u64(u64) => U64(u64),
+        serialize_u128(u128) => U128(u128),
+        serialize_f32(f32) => F64(f64),
+        serialize_f64(f64) => F64(f64),
+        serialize_char(char) => Char(char),
+        serialize_str(&str) => String(String),
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value> {
+        Ok(Value::Bytes(value.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Value::Some(Box::new(to_value(value)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Value> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::Variant(variant, Box::new(Value::Unit)))
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Value::Newtype(Box::new(to_value(value)?)))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Value::Variant(variant, Box::new(to_value(value)?)))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<SeqSerializer> {
+        Ok(SeqSerializer {
+            variant: None,
+            values: Vec::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<SeqSerializer> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            values: Vec::new(),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+            variant: None,
+            entries: Vec::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+            variant: Some(variant),
+            entries: Vec::new(),
+            key: None,
+        })
+    }
+}
+
+/// Wrap `value` in the enum variant it belongs to, if any
+fn variant(variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => Value::Variant(variant, Box::new(value)),
+        None => value,
+    }
+}
+
+struct SeqSerializer {
+    variant: Option<&'static str>,
+    values: Vec<Value>,
+}
+
+impl SeqSerializer {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        Ok(variant(self.variant, Value::Seq(self.values)))
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+struct MapSerializer {
+    variant: Option<&'static str>,
+    entries: Vec<(Value, Value)>,
+    key: Option<Value>,
+}
+
+impl MapSerializer {
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.entries
+            .push((Value::String(key.to_owned()), to_value(value)?));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value> {
+        Ok(variant(self.variant, Value::Map(self.entries)))
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(to_value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.key.take().ok_or_else(|| {
+            Error::Custom(String::from("serialize_value called before serialize_key"))
+        })?;
+
+        self.entries.push((key, to_value(value)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        self.finish()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::I64(value) => visitor.visit_i64(value),
+            Value::U64(value) => visitor.visit_u64(value),
+            Value::I128(value) => visitor.visit_i128(value),
+            Value::U128(value) => visitor.visit_u128(value),
+            Value::F64(value) => visitor.visit_f64(value),
+            Value::Char(value) => visitor.visit_char(value),
+            Value::String(value) => visitor.visit_string(value),
+            Value::Bytes(value) => visitor.visit_byte_buf(value),
+            Value::None => visitor.visit_none(),
+            Value::Some(value) => visitor.visit_some(*value),
+            Value::Unit => visitor.visit_unit(),
+            Value::Newtype(value) => visitor.visit_newtype_struct(*value),
+            Value::Seq(values) => {
+                let mut seq = SeqDeserializer::new(values.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            Value::Variant(variant, value) => visitor.visit_enum(VariantAccess {
+                variant,
+                value: *value,
+            }),
+        }
+    }
+
+    /// Bytes are handed out one by one, so decoded values deserialize into `Vec<u8>`
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Bytes(bytes) => {
+                let mut seq = SeqDeserializer::new(bytes.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::None | Value::Unit => visitor.visit_none(),
+            Value::Some(value) => visitor.visit_some(*value),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Newtype(value) => visitor.visit_newtype_struct(*value),
+            value => visitor.visit_newtype_struct(value),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::String(variant) => visitor
+                .visit_enum(IntoDeserializer::<Error>::into_deserializer(variant)),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Hands an enum variant and its content to the visitor
+struct VariantAccess {
+    variant: &'static str,
+    value: Value,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Value)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(StrDeserializer::<Error>::new(self.variant))?;
+
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Value {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self {
+            Value::Unit => Ok(()),
+            _ => Err(de::Error::custom("expected a unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_value;
+    use serde::{Deserialize, Serialize};
+    use std::{collections::BTreeMap, fmt::Debug, time::Duration};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { width: u32, height: u32 },
+    }
+
+    fn round_trip<T>(value: T)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + PartialEq + Debug,
+    {
+        assert_eq!(T::deserialize(to_value(&value).unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(Duration::from_millis(1500));
+        round_trip(Some(vec![(1u8, 'a'), (2, 'b')]));
+        round_trip(BTreeMap::from([(String::from("a"), -1i128)]));
+        round_trip(vec![
+            Shape::Point,
+            Shape::Circle(0.5),
+            Shape::Rect {
+                width: 2,
+                height: 3,
+            },
+        ]);
+    }
+}
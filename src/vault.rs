@@ -0,0 +1,194 @@
+//! Variables stored as a secret in the KV version 2 engine of HashiCorp Vault
+
+use crate::{json, Error, Result, Source};
+use std::{env, fmt};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A secret of the KV version 2 secrets engine of HashiCorp Vault, as a [`Source`]
+/// whose variables are the key-value pairs of the latest version of that secret.
+///
+/// String values are taken as they are, anything else is written as JSON.
+///
+/// # Example
+///
+/// ```no_run
+/// use renvar::{builder, VaultSource};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Database {
+///     username: String,
+///     password: String,
+/// }
+///
+/// // Reads `VAULT_ADDR` and `VAULT_TOKEN`, like the vault CLI
+/// let source = VaultSource::from_env("myapp/database").unwrap();
+///
+/// let database: Database = builder().from_source(&source).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct VaultSource {
+    address: String,
+    token: String,
+    mount: String,
+    path: String,
+    namespace: Option<String>,
+}
+
+impl VaultSource {
+    /// Read the secret at `path` from the Vault server at `address`,
+    /// such as `https://vault.example.com:8200`, authenticating with `token`.
+    ///
+    /// The secrets engine is expected to be mounted at `secret`,
+    /// the default of Vault, unless set with [`VaultSource::mount`]
+    pub fn new(address: &str, token: &str, path: &str) -> Self {
+        Self {
+            address: address.trim_end_matches('/').to_owned(),
+            token: token.to_owned(),
+            mount: String::from("secret"),
+            path: path.trim_matches('/').to_owned(),
+            namespace: None,
+        }
+    }
+
+    /// Read the secret at `path` from the server set in `VAULT_ADDR`,
+    /// authenticating with the token set in `VAULT_TOKEN`.
+    /// The namespace is read from `VAULT_NAMESPACE`, if it's set
+    ///
+    /// # Errors
+    ///
+    /// If `VAULT_ADDR` or `VAULT_TOKEN` isn't set
+    pub fn from_env(path: &str) -> Result<Self> {
+        let var = |name: &str| {
+            env::var(name).map_err(|_| Error::MissingValue(name.to_owned()))
+        };
+
+        let source = Self::new(&var("VAULT_ADDR")?, &var("VAULT_TOKEN")?, path);
+
+        Ok(match env::var("VAULT_NAMESPACE") {
+            Ok(namespace) => source.namespace(&namespace),
+            Err(_) => source,
+        })
+    }
+
+    /// Set the path the secrets engine is mounted at
+    pub fn mount(mut self, mount: &str) -> Self {
+        self.mount = mount.trim_matches('/').to_owned();
+        self
+    }
+
+    /// Set the namespace the secret belongs to, for Vault Enterprise
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_owned());
+        self
+    }
+
+    /// The URL of the secret in the HTTP API
+    fn url(&self) -> String {
+        format!("{}/v1/{}/data/{}", self.address, self.mount, self.path)
+    }
+}
+
+impl fmt::Debug for VaultSource {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("VaultSource")
+            .field("address", &self.address)
+            .field("token", &"<redacted>")
+            .field("mount", &self.mount)
+            .field("path", &self.path)
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+impl Source for VaultSource {
+    fn load(&self) -> Result<Vec<(String, String)>> {
+        let error = |reason: String| {
+            Error::Custom(format!(
+                "failed to read secret {}/{} from Vault: {}",
+                self.mount, self.path, reason
+            ))
+        };
+
+        let mut request = ureq::get(&self.url()).set("X-Vault-Token", &self.token);
+
+        if let Some(namespace) = &self.namespace {
+            request = request.set("X-Vault-Namespace", namespace);
+        }
+
+        let response: serde_json::Value = request
+            .call()
+            .map_err(|e| error(e.to_string()))?
+            .into_json()
+            .map_err(|e| error(e.to_string()))?;
+
+        match response
+            .get("data")
+            .and_then(|data| data.get("data"))
+        {
+            Some(data) => json::object_vars(data.clone())
+                .ok_or_else(|| error(String::from("the secret isn't a key-value map"))),
+            None => Err(error(String::from("the response holds no secret"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VaultSource;
+    use crate::Source;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    #[test]
+    fn test_vault_source() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+
+            for line in BufReader::new(&stream).lines() {
+                let line = line.unwrap();
+                if line.is_empty() {
+                    break;
+                }
+                request.push(line);
+            }
+
+            let body =
+                r#"{"data":{"data":{"username":"admin","port":5432},"metadata":{}}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+
+            request
+        });
+
+        let source = VaultSource::new(&address, "s.token", "/myapp/db/").mount("kv");
+        let vars = source.load().unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(request[0], "GET /v1/kv/data/myapp/db HTTP/1.1");
+        assert!(request
+            .iter()
+            .any(|header| header.eq_ignore_ascii_case("x-vault-token: s.token")));
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("port"), String::from("5432")),
+                (String::from("username"), String::from("admin")),
+            ]
+        );
+        assert!(!format!("{:?}", source).contains("s.token"));
+    }
+}
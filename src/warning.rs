@@ -0,0 +1,224 @@
+//! Non-fatal issues found while deserializing
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A non-fatal issue found while deserializing, delivered to the callback set
+/// with [`crate::Builder::on_warning`] or collected into [`Warnings`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// Several variables mapped to the same key and one of them was
+    /// discarded, according to the [`crate::DuplicateKeyPolicy`] in effect
+    ShadowedKey {
+        /// The name of the variable whose value was kept
+        kept: String,
+
+        /// The name of the variable whose value was discarded
+        shadowed: String,
+    },
+
+    /// A field was read from a variable that's deprecated, such as an alias set up
+    /// with [`crate::Builder::alias`] or a field marked with [`crate::Builder::deprecate`]
+    DeprecatedKey {
+        /// The name of the deprecated variable
+        key: String,
+
+        /// The name of the variable to use instead, if there's one
+        replacement: Option<String>,
+
+        /// The version the variable will stop being read in, if it's known
+        removal: Option<String>,
+    },
+
+    /// The name or the value of a variable wasn't valid unicode and was converted
+    /// lossily, replacing the invalid sequences with `U+FFFD`, see
+    /// [`crate::Builder::from_os_env_lossy`]
+    LossyConversion {
+        /// The name of the variable, converted lossily
+        key: String,
+    },
+
+    /// A field marked as secret with [`crate::Config::secret`] was read from
+    /// the defaults file set with [`crate::Builder::defaults`], which is
+    /// meant to be committed along with the code
+    DefaultedSecret {
+        /// The name of the variable in the defaults file
+        key: String,
+    },
+}
+
+impl Warning {
+    /// Rewrite the names of the variables mentioned by the warning
+    pub(crate) fn map_keys(self, f: impl Fn(String) -> String) -> Self {
+        match self {
+            Warning::ShadowedKey { kept, shadowed } => Warning::ShadowedKey {
+                kept: f(kept),
+                shadowed: f(shadowed),
+            },
+            Warning::DeprecatedKey {
+                key,
+                replacement,
+                removal,
+            } => Warning::DeprecatedKey {
+                key: f(key),
+                replacement: replacement.map(&f),
+                removal,
+            },
+            Warning::LossyConversion { key } => {
+                Warning::LossyConversion { key: f(key) }
+            }
+            Warning::DefaultedSecret { key } => {
+                Warning::DefaultedSecret { key: f(key) }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::ShadowedKey { kept, shadowed } => write!(
+                fmt,
+                "environment variable {} is shadowed by {}",
+                shadowed, kept
+            ),
+            Warning::DeprecatedKey {
+                key,
+                replacement,
+                removal,
+            } => {
+                write!(fmt, "environment variable {} is deprecated", key)?;
+
+                if let Some(replacement) = replacement {
+                    write!(fmt, ", use {} instead", replacement)?;
+                }
+
+                match removal {
+                    Some(removal) => write!(fmt, ", it will be removed in {}", removal),
+                    None => Ok(()),
+                }
+            }
+            Warning::LossyConversion { key } => write!(
+                fmt,
+                "environment variable {} isn't valid unicode and was converted lossily",
+                key
+            ),
+            Warning::DefaultedSecret { key } => write!(
+                fmt,
+                "secret {} is read from the defaults file, set it in the environment instead",
+                key
+            ),
+        }
+    }
+}
+
+/// How a field marked with [`crate::Builder::deprecate`] is deprecated,
+/// reported by the [`Warning::DeprecatedKey`] emitted when it's read
+///
+/// # Example
+///
+/// ```
+/// use renvar::Deprecation;
+///
+/// let deprecation = Deprecation::new()
+///     .replacement("request_timeout")
+///     .removal("2.0");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    pub(crate) replacement: Option<String>,
+    pub(crate) removal: Option<String>,
+}
+
+impl Deprecation {
+    /// Construct a [`Deprecation`] with neither a replacement nor a removal version
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The field to use instead, reported with the name of its variable
+    pub fn replacement(mut self, field: &str) -> Self {
+        self.replacement = Some(field.to_owned());
+        self
+    }
+
+    /// The version the field will be removed in
+    pub fn removal(mut self, version: &str) -> Self {
+        self.removal = Some(version.to_owned());
+        self
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// A collection of [`Warning`]s, filled by every [`crate::Builder`]
+/// it's handed to with [`crate::Builder::warnings`].
+///
+/// Cheap to clone, all clones share the same collection.
+///
+/// # Example
+///
+/// ```
+/// use renvar::{builder, Warning, Warnings};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct CustomStruct {
+///     key: String,
+/// }
+///
+/// let warnings = Warnings::new();
+///
+/// let custom_struct: CustomStruct = builder()
+///     .warnings(&warnings)
+///     .from_str("KEY=first\nKEY=second\n")
+///     .unwrap();
+///
+/// assert_eq!(custom_struct.key, "second");
+/// assert_eq!(
+///     warnings.take(),
+///     vec![Warning::ShadowedKey {
+///         kept: "KEY".to_owned(),
+///         shadowed: "KEY".to_owned()
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Warnings(Arc<Mutex<Vec<Warning>>>);
+
+impl Warnings {
+    /// Construct an empty [`Warnings`] collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of warnings collected so far
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no warnings were collected so far
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove and return the warnings collected so far
+    pub fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub(crate) fn push(&self, warning: Warning) {
+        self.lock().push(warning);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Warning>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,253 @@
+//! Reloading configuration whenever the env file it's read from changes
+
+use crate::{builder, Builder, Error, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::de;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Handle to the latest value deserialized from a watched env file.
+/// Users are meant to obtain this struct by calling [`watch_file`] or [`Builder::watch_file`].
+///
+/// Cloning the handle is cheap and every clone sees the same value.
+/// The file stops being watched once the handle and all of its clones are dropped.
+pub struct Watched<T> {
+    current: Arc<RwLock<Arc<T>>>,
+    _watcher: Arc<RecommendedWatcher>,
+}
+
+impl<T> Watched<T> {
+    /// The value deserialized from the last readable version of the file.
+    /// Returns the same value until the file changes and is successfully
+    /// deserialized again, so it can be called as often as needed.
+    pub fn get(&self) -> Arc<T> {
+        Arc::clone(
+            &self
+                .current
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+}
+
+impl<T> Clone for Watched<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: Arc::clone(&self.current),
+            _watcher: Arc::clone(&self._watcher),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Watched<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Watched")
+            .field("current", &self.get())
+            .finish()
+    }
+}
+
+/// Start watching the env file at `path` with the settings of `builder`,
+/// see [`Builder::watch_file`]
+pub(crate) fn watch<T, F>(
+    builder: &Builder,
+    path: &Path,
+    mut callback: F,
+) -> Result<Watched<T>>
+where
+    T: de::DeserializeOwned + Send + Sync + 'static,
+    F: FnMut(Result<Arc<T>>) + Send + 'static,
+{
+    let current = Arc::new(RwLock::new(Arc::new(builder.from_file::<T>(path)?)));
+
+    let reload = {
+        let builder = builder.clone();
+        let current = Arc::clone(&current);
+        let path = path.to_owned();
+
+        move |changed: Result<()>| match changed
+            .and_then(|()| builder.from_file::<T>(&path))
+        {
+            Ok(value) => {
+                let value = Arc::new(value);
+
+                *current
+                    .write()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Arc::clone(&value);
+
+                callback(Ok(value))
+            }
+            Err(error) => callback(Err(error)),
+        }
+    };
+
+    Ok(Watched {
+        current,
+        _watcher: Arc::new(watch_path(path, reload)?),
+    })
+}
+
+/// Call `on_change` whenever the file at `path` is created or modified, or with
+/// the error if watching it fails, until the returned watcher is dropped
+pub(crate) fn watch_path<F>(path: &Path, mut on_change: F) -> Result<RecommendedWatcher>
+where
+    F: FnMut(Result<()>) + Send + 'static,
+{
+    let io_error = |error: notify::Error| Error::Io {
+        path: path.to_owned(),
+        reason: error.to_string(),
+    };
+
+    // Editors usually save by replacing the file, which would leave a watch
+    // on the file itself pointing to the old one, so its directory is watched
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+        _ => PathBuf::from("."),
+    };
+
+    let handler = {
+        let path = path.to_owned();
+
+        move |event: notify::Result<notify::Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(error) => {
+                    return on_change(Err(Error::Io {
+                        path: path.clone(),
+                        reason: error.to_string(),
+                    }))
+                }
+            };
+
+            let changed =
+                matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == file_name.as_deref());
+
+            if changed {
+                on_change(Ok(()));
+            }
+        }
+    };
+
+    let mut watcher = notify::recommended_watcher(handler).map_err(io_error)?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .map_err(io_error)?;
+
+    Ok(watcher)
+}
+
+/// Deserialize some type `T` from the env file at `path`, which follows the same
+/// format as described in [`crate::from_str`], and deserialize it again whenever
+/// the file changes, handing every new value or error to `callback`.
+///
+/// The returned [`Watched`] handle always holds the latest value. When the changed
+/// file fails to deserialize, such as when it's caught halfway through being written,
+/// the error goes to `callback` and the handle keeps the previous value.
+///
+/// `callback` runs on a background thread owned by the watcher.
+///
+/// # Errors
+///
+/// If the file can't be read or watched, or any errors that might
+/// occur during the first deserialization
+///
+/// # Example
+///
+/// ```
+/// use renvar::watch_file;
+/// use serde::Deserialize;
+/// use std::{env, fs};
+///
+/// #[derive(Debug, Deserialize, PartialEq, Eq)]
+/// struct CustomStruct {
+///     workers: u8,
+/// }
+///
+/// let path = env::temp_dir().join("renvar_doc_watch_file.env");
+/// fs::write(&path, "WORKERS=4").unwrap();
+///
+/// let config = watch_file::<CustomStruct, _>(&path, |reloaded| {
+///     if let Err(error) = reloaded {
+///         eprintln!("keeping the previous configuration: {}", error);
+///     }
+/// })
+/// .unwrap();
+///
+/// assert_eq!(*config.get(), CustomStruct { workers: 4 });
+/// # fs::remove_file(&path).unwrap();
+/// ```
+pub fn watch_file<T, F>(path: impl AsRef<Path>, callback: F) -> Result<Watched<T>>
+where
+    T: de::DeserializeOwned + Send + Sync + 'static,
+    F: FnMut(Result<Arc<T>>) + Send + 'static,
+{
+    builder().watch_file(path, callback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::watch_file;
+    use serde::Deserialize;
+    use std::{env, fs, sync::mpsc, time::Duration};
+
+    #[derive(Debug, Deserialize, PartialEq, Eq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn test_watch_file() {
+        let path = env::temp_dir().join("renvar_test_watch_file.env");
+        fs::write(&path, "HOST=localhost\nPORT=8080\n").unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let watched = watch_file::<Server, _>(&path, move |reloaded| {
+            let _ = sender.send(reloaded);
+        })
+        .unwrap();
+
+        assert_eq!(watched.get().port, 8080);
+
+        fs::write(&path, "HOST=localhost\nPORT=not a port\n").unwrap();
+
+        let error = receiver
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        assert!(error.is_err());
+        assert_eq!(watched.get().port, 8080);
+
+        // Replace the file like editors do
+        let replacement = path.with_extension("env.new");
+        fs::write(&replacement, "HOST=remote\nPORT=9090\n").unwrap();
+        fs::rename(&replacement, &path).unwrap();
+
+        let reloaded = loop {
+            match receiver
+                .recv_timeout(Duration::from_secs(10))
+                .unwrap()
+            {
+                Ok(reloaded) if reloaded.port == 9090 => break reloaded,
+                _ => continue,
+            }
+        };
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.host, "remote");
+        assert_eq!(watched.get().host, "remote");
+    }
+}